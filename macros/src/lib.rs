@@ -0,0 +1,79 @@
+/*!
+Procedural macros for `memory_logger`. These are re-exported by `memory_logger` through the
+`macros` feature, and should be used from there.
+*/
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, ItemFn};
+
+
+/// Runs a function as a test, capturing the entries it logs. The function may take a
+/// `&memory_logger::TestCapture`, through which the captured entries are exposed, and may
+/// return anything a `#[test]` function may, such as a `Result`. Other attributes, such as
+/// `#[should_panic]`, are kept.
+///
+/// ```ignore
+/// #[memory_logger::test]
+/// fn retries(logs: &memory_logger::TestCapture) {
+///     log::warn!("Retrying.");
+///
+///     assert_eq!(logs.entries()[0].message(), "Retrying.");
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(attributes: TokenStream, item: TokenStream) -> TokenStream {
+	let function = parse_macro_input!(item as ItemFn);
+
+	let expanded = if !attributes.is_empty() {
+		Err(
+			Error::new(
+				proc_macro2::TokenStream::from(attributes).span(),
+				"memory_logger::test takes no arguments"
+			)
+		)
+	} else {
+		expand(function)
+	};
+
+	expanded
+		.unwrap_or_else(Error::into_compile_error)
+		.into()
+}
+
+
+fn expand(function: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+	let ItemFn { attrs, vis, sig, block } = function;
+
+	if let Some(asyncness) = &sig.asyncness {
+		return Err(
+			Error::new(asyncness.span(), "memory_logger::test does not support async functions")
+		);
+	}
+
+	let name = &sig.ident;
+	let output = &sig.output;
+
+	let test = match sig.inputs.len() {
+		0 => quote! { |_| #name() },
+		1 => quote! { #name },
+		_ => return Err(
+			Error::new(
+				sig.inputs.span(),
+				"memory_logger::test functions take at most a `&memory_logger::TestCapture`"
+			)
+		),
+	};
+
+	Ok(
+		quote! {
+			#[::core::prelude::v1::test]
+			#(#attrs)*
+			#vis fn #name() #output {
+				#sig #block
+
+				::memory_logger::__run_test(#test)
+			}
+		}
+	)
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+use log::{Log, Record};
+use log4rs::append::Append;
+
+
+/// A log4rs appender buffering records in a memory logger, so that existing log4rs
+/// configurations can add in-memory capture alongside their file and console appenders.
+///
+/// The appender is created along with its logger through `setup_appender`, as log4rs is
+/// installed as the global logger instead. The logger's level and target filters apply to
+/// the records it receives.
+///
+/// ```
+/// # use regex::Regex;
+/// use log4rs::config::{Appender, Root};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, Config};
+/// let (logger, appender) = MemoryLogger::setup_appender(
+///     Config::new(log::Level::Info, Regex::new("^mycrate")?)
+/// );
+///
+/// let config = log4rs::Config::builder()
+///     .appender(Appender::builder().build("memory", Box::new(appender)))
+///     .build(Root::builder().appender("memory").build(log::LevelFilter::Info))?;
+///
+/// log4rs::init_config(config)?;
+///
+/// log::info!(target: "mycrate", "Connected.");
+///
+/// assert_eq!(&*logger.read(), "[mycrate] INFO  | Connected.\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub struct MemoryAppender {
+	logger: &'static dyn Log,
+}
+
+
+impl MemoryAppender {
+	pub(crate) fn new(logger: &'static dyn Log) -> Self {
+		Self { logger }
+	}
+}
+
+
+impl fmt::Debug for MemoryAppender {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MemoryAppender")
+			.finish_non_exhaustive()
+	}
+}
+
+
+impl Append for MemoryAppender {
+	fn append(&self, record: &Record) -> anyhow::Result<()> {
+		self.logger.log(record);
+		Ok(())
+	}
+
+
+	fn flush(&self) {
+		self.logger.flush();
+	}
+}
@@ -0,0 +1,92 @@
+//! An arena of lines, packed into fixed-size chunks, so that lines don't need an
+//! allocation each, and are never copied as the arena grows.
+
+use std::collections::VecDeque;
+
+
+/// The capacity of a chunk. Longer lines get a chunk of their own.
+const CHUNK: usize = 4096;
+
+
+/// The location of a line in an arena.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Slice {
+	/// The index of the chunk, counting released chunks.
+	chunk: usize,
+	offset: usize,
+	len: usize,
+}
+
+
+impl Slice {
+	/// The length of the line, including the trailing newline.
+	pub(crate) fn len(&self) -> usize {
+		self.len
+	}
+}
+
+
+#[derive(Debug, Default)]
+pub(crate) struct Arena {
+	chunks: VecDeque<String>,
+	/// How many chunks have been released.
+	released: usize,
+}
+
+
+impl Arena {
+	/// Append a line, followed by a newline.
+	pub(crate) fn push(&mut self, line: &str) -> Slice {
+		let len = line.len() + 1;
+
+		let fits = self.chunks
+			.back()
+			.is_some_and(|chunk| chunk.capacity() - chunk.len() >= len);
+
+		if !fits {
+			self.chunks.push_back(String::with_capacity(len.max(CHUNK)));
+		}
+
+		let chunk = self.chunks
+			.back_mut()
+			.expect("a chunk should fit the line");
+
+		let offset = chunk.len();
+
+		chunk.push_str(line);
+		chunk.push('\n');
+
+		Slice {
+			chunk: self.released + self.chunks.len() - 1,
+			offset,
+			len,
+		}
+	}
+
+
+	/// The line at the given location, including the trailing newline.
+	pub(crate) fn get(&self, slice: &Slice) -> &str {
+		&self.chunks[slice.chunk - self.released][slice.offset .. slice.offset + slice.len]
+	}
+
+
+	/// Remove the given line and the lines following it.
+	pub(crate) fn truncate(&mut self, first: &Slice) {
+		self.chunks.truncate(first.chunk - self.released + 1);
+
+		if let Some(chunk) = self.chunks.back_mut() {
+			chunk.truncate(first.offset);
+		}
+	}
+
+
+	/// Release the chunks preceding the given line's, or all chunks if there is no line.
+	pub(crate) fn release(&mut self, oldest: Option<&Slice>) {
+		let first = oldest.map_or(self.released + self.chunks.len(), |slice| slice.chunk);
+
+		while self.released < first {
+			self.chunks.pop_front();
+			self.released += 1;
+		}
+	}
+}
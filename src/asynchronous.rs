@@ -1,4 +1,27 @@
-use std::io::{self, Write};
+use std::{
+	collections::{HashMap, VecDeque},
+	fmt::Display,
+	io::{self, Read, Write},
+	panic::{self, AssertUnwindSafe},
+	path::Path,
+	sync::{
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+		Arc,
+		Condvar,
+		Mutex,
+		MutexGuard,
+	},
+	thread::ThreadId,
+};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::{
+	net::{SocketAddr, ToSocketAddrs},
+	time::Duration,
+};
+
+#[cfg(all(feature = "target", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::time::Instant;
 
 use log::{Level, Log, Metadata, Record, SetLoggerError};
 
@@ -7,14 +30,291 @@ use flume::{Sender, Receiver};
 #[cfg(feature = "target")]
 use regex::Regex;
 
+use crate::{
+	batch::{Batches, Packet},
+	capture,
+	file,
+	format,
+	metrics::Counters,
+	persist,
+	pool,
+	query::{Criteria, Source},
+	retention::Retention,
+	storage::Backend,
+	Config,
+	Expectation,
+	Format,
+	Hit,
+	LogEntry,
+	LogWriter,
+	MemoryLog,
+	MemoryUsage,
+	Metrics,
+	Query,
+	RingStorage,
+	Schema,
+	Stats,
+	Storage,
+	Summary,
+	View,
+	writer::Push,
+};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::{tail, Pipeline, Worker};
+
+#[cfg(all(feature = "control", unix))]
+use crate::control;
+
+#[cfg(feature = "crypto")]
+use crate::crypto::Key;
+
+#[cfg(feature = "framing")]
+use crate::framing;
+
+#[cfg(feature = "http")]
+use crate::http;
+
+#[cfg(feature = "sse")]
+use crate::sse;
+
+#[cfg(all(feature = "ws", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use crate::ws;
+
+#[cfg(all(feature = "ws", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::net::TcpListener;
+
+#[cfg(all(feature = "journald", unix))]
+use crate::journald;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+use crate::console;
+
+#[cfg(feature = "sentry")]
+use sentry_core::protocol::Breadcrumb;
+
+#[cfg(feature = "tracing")]
+use crate::{layer, BridgeError};
+
+#[cfg(feature = "log4rs")]
+use crate::MemoryAppender;
+
+#[cfg(feature = "snapshot")]
+use crate::Normalizer;
+
+#[cfg(feature = "target")]
+use crate::{Routes, Routing};
+
+#[cfg(feature = "stream")]
+use std::{
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+#[cfg(feature = "stream")]
+use flume::r#async::RecvStream;
+
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+
+#[cfg(feature = "tokio")]
+use tokio::sync::watch;
+
+#[cfg(feature = "tokio")]
+use crate::updates::announce;
+
 
 struct Logger {
-	level: Level,
+	config: Config,
 
-	#[cfg(feature = "target")]
-	target: Regex,
+	/// The current level, which starts at the configured one.
+	level: AtomicUsize,
+
+	counters: Option<Counters>,
+
+	retention: Option<Retention>,
+
+	tx: Sender<Packet>,
+
+	/// The sequence number of the next entry.
+	sequence: AtomicU64,
+
+	/// How many entries have been sent through the channel.
+	sent: AtomicU64,
+
+	batches: Option<Batches>,
+
+	subscribers: Mutex<Vec<Sender<LogEntry>>>,
+
+	/// The number of subscribers, to skip locking when there are none.
+	subscribed: AtomicUsize,
+
+	/// Announces how many entries have been logged.
+	#[cfg(feature = "tokio")]
+	updates: watch::Sender<u64>,
+}
+
+
+impl Logger {
+	/// Get a buffer for a new entry, from the pool if pooling is enabled and a buffer is
+	/// available.
+	fn buffer(&self) -> String {
+		if self.config.pool > 0 {
+			pool::take()
+		} else {
+			String::new()
+		}
+	}
+
+
+	/// Return an entry's buffer to the pool, if pooling is enabled and the pool is not
+	/// full.
+	fn recycle(&self, line: String) {
+		if self.config.pool > 0 {
+			pool::recycle(line, self.config.pool);
+		}
+	}
+
+
+	/// Send a copy of an entry to each subscriber, dropping the ones that have
+	/// unsubscribed, and the bounded ones that have fallen behind.
+	fn broadcast(&self, entry: &LogEntry) {
+		if self.subscribed.load(Ordering::Acquire) == 0 {
+			return;
+		}
+
+		let mut subscribers = self.lock_subscribers();
+
+		subscribers.retain(|tx| tx.try_send(entry.clone()).is_ok());
+
+		self.subscribed.store(subscribers.len(), Ordering::Release);
+	}
+
+
+	/// Send an entry through the channel.
+	fn send(&self, entry: LogEntry) {
+		let batches = match &self.batches {
+			Some(batches) => batches,
+			None => return self.transmit(Packet::Entry(entry)),
+		};
+
+		if let Some(batch) = batches.push(entry) {
+			self.transmit(Packet::Batch(batch));
+		}
+	}
+
+
+	/// Send the pending retention markers, if any, so that dumps and drains include them.
+	fn flush_pending(&self) {
+		if let Some(retention) = &self.retention {
+			let mut observed = Vec::new();
+
+			retention.flush_pending(
+				|| self.sequence.fetch_add(1, Ordering::Relaxed),
+				|entry| {
+					if self.config.observes(entry.level) {
+						observed.push(entry.clone());
+					}
+
+					self.send(entry);
+				}
+			);
+
+			for entry in observed {
+				self.config.observe(&entry);
+			}
+		}
+	}
 
-	tx: Sender<Box<str>>,
+
+	/// Send the partial batches of all threads through the channel.
+	fn flush_batches(&self) {
+		if let Some(batches) = &self.batches {
+			let entries = batches.take();
+
+			if !entries.is_empty() {
+				self.transmit(Packet::Batch(entries));
+			}
+		}
+	}
+
+
+	fn transmit(&self, packet: Packet) {
+		#[cfg(feature = "tokio")]
+		let sequence = packet.last().map(|entry| entry.sequence);
+
+		let len = packet.len() as u64;
+
+		self.tx.send(packet)
+			.expect("channel should not be closed");
+
+		self.sent.fetch_add(len, Ordering::Release);
+
+		#[cfg(feature = "tokio")]
+		if let Some(sequence) = sequence {
+			announce(&self.updates, sequence);
+		}
+	}
+
+
+	fn lock_subscribers(&self) -> MutexGuard<'_, Vec<Sender<LogEntry>>> {
+		self.subscribers
+			.lock()
+			.expect("subscribers lock poisoned")
+	}
+
+
+	/// Capture a record, regardless of the level and target filters.
+	fn capture(&self, record: &Record) {
+		if let Some(counters) = &self.counters {
+			counters.record(record.level(), format::record_target(record));
+		}
+
+		if self.retention.as_ref().is_some_and(|retention| !retention.sample(record.level())) {
+			return;
+		}
+
+		let entry = LogEntry::render_into(
+			self.buffer(),
+			&self.config.format,
+			self.sequence.fetch_add(1, Ordering::Relaxed),
+			record.level(),
+			format::record_target(record),
+			self.config.details(record),
+			self.config.message(record),
+			self.config.now()
+		);
+
+		self.broadcast(&entry);
+
+		let retention = match &self.retention {
+			Some(retention) => retention,
+			None => {
+				self.config.observe(&entry);
+				self.send(entry);
+				return;
+			}
+		};
+
+		let mut observed = Vec::new();
+
+		retention.admit(
+			entry,
+			|| self.sequence.fetch_add(1, Ordering::Relaxed),
+			|entry| {
+				if self.config.observes(entry.level) {
+					observed.push(entry.clone());
+				}
+
+				self.send(entry);
+			}
+		);
+
+		// Observed once the retention lock is released, as the callbacks may log.
+		for entry in observed {
+			self.config.observe(&entry);
+		}
+	}
 }
 
 
@@ -22,41 +322,25 @@ impl Log for Logger {
 	fn enabled(&self, metadata: &Metadata) -> bool {
 		#[cfg(feature = "target")]
 		{
-			if !self.target.is_match(metadata.target()) {
+			if !self.config.target.is_match(metadata.target()) {
 				return false;
 			}
 		}
 
-		metadata.level() <= self.level
+		metadata.level() as usize <= self.level.load(Ordering::Relaxed)
 	}
 
 
 	fn log(&self, record: &Record) {
 		if self.enabled(record.metadata()) {
-			let target =
-				if record.target().is_empty() {
-					record
-						.module_path()
-						.unwrap_or("?")
-				} else {
-					record.target()
-				};
-
-			self.tx.send(
-				format!(
-					"[{}] {:<5} | {}",
-					target,
-					record.level(),
-					record.args()
-				)
-					.into_boxed_str()
-			)
-				.expect("channel should not be closed");
+			self.capture(record);
 		}
 	}
 
 
-	fn flush(&self) { }
+	fn flush(&self) {
+		self.flush_batches();
+	}
 }
 
 
@@ -64,26 +348,102 @@ impl std::fmt::Debug for Logger {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		let mut debug_struct = f.debug_struct("Logger");
 
-		debug_struct.field("level", &self.level);
+		debug_struct.field("config", &self.config);
 
-		#[cfg(feature = "target")]
-		{
-			debug_struct.field("target", &self.target);
+		debug_struct.finish()
+	}
+}
+
+
+/// Entries moved out of the channel, either by non-consuming reads or by the drain
+/// thread.
+struct Store {
+	/// Entries moved by non-consuming reads, if retention is disabled.
+	entries: VecDeque<LogEntry>,
+
+	/// Entries moved by the drain thread, if retention is enabled.
+	storage: Box<dyn Storage>,
+
+	/// How many entries the drain thread has moved out of the channel.
+	drained: u64,
+}
+
+
+impl Store {
+	/// Evict the oldest retained entries until the configured limits are met, returning
+	/// how many were evicted.
+	fn bound(&mut self, config: &Config) -> usize {
+		let capacity = if config.retain == 0 { usize::MAX } else { config.retain };
+		let budget = config.max_bytes.unwrap_or(usize::MAX);
+		let len = self.storage.len();
+
+		if len > capacity {
+			self.storage.evict(len - capacity);
 		}
 
-		debug_struct.finish()
+		while self.storage.bytes() > budget && !self.storage.is_empty() {
+			let before = self.storage.len();
+
+			self.storage.evict(1);
+
+			// A failing storage may not evict anything, which must not spin the drain
+			// thread.
+			if self.storage.len() >= before {
+				break;
+			}
+		}
+
+		len.saturating_sub(self.storage.len())
 	}
 }
 
 
-/// An asynchronous memory logger. Logging and read operations never block.
+/// An asynchronous memory logger. Logging operations never block, and read operations
+/// only block on other concurrent reads that use `peek`.
+///
+/// Reads and dumps consume the buffered entries, unless retention is enabled through
+/// [`Config::retain`](../struct.Config.html#method.retain) or
+/// [`Config::storage`](../struct.Config.html#method.storage). In such case, a background
+/// thread drains logged entries into a ring buffer, or the given storage, and reads yield
+/// copies of the retained entries:
+///
+/// ```
+/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).retain(2);
+/// let logger = MemoryLogger::setup_with(config)?;
+///
+/// log::info!(target: "mycrate", "First.");
+/// log::info!(target: "mycrate", "Second.");
+/// log::info!(target: "mycrate", "Third.");
+///
+/// for _ in 0 .. 2 {
+///     let entries: Vec<_> = logger.read_entries().collect();
+///
+///     assert_eq!(entries.len(), 2);
+///     assert_eq!(entries[0].message(), "Second.");
+///     assert_eq!(entries[1].message(), "Third.");
+/// }
+/// # Ok(())
+/// # }
+/// ```
 ///
 /// You should have only a single instance of this in your program.
 pub struct MemoryLogger {
 	logger: Logger,
 	// Receiver is not (Sync + Send), which is required by the Log trait.
 	// Therefore, we implement Log just for the Logger struct.
-	rx: Receiver<Box<str>>,
+	rx: Receiver<Packet>,
+	// Entries moved out of the channel. These are older than the entries in the channel,
+	// and are consumed first.
+	retained: Mutex<Store>,
+	// Signaled whenever the drain thread moves an entry.
+	drained: Condvar,
+	// The sequence number of the next entry when each mark was set.
+	marks: Mutex<HashMap<Box<str>, u64>>,
+	// How many entries have been removed from the buffer, by reads or evictions.
+	removed: AtomicU64,
 }
 
 
@@ -121,24 +481,247 @@ impl MemoryLogger {
 		#[cfg(feature = "target")]
 		target: Regex,
 	) -> Result<&'static Self, SetLoggerError> {
-		let (tx, rx) = flume::unbounded();
+		Self::setup_with(
+			Config::new(
+				level,
+				#[cfg(feature = "target")]
+				target,
+			)
+		)
+	}
 
-		let logger = Box::leak(
-			Box::new(
-				Self {
-					logger: Logger {
-						level,
 
-						#[cfg(feature = "target")]
-						target,
+	/// Initializes the global logger with a new MemoryLogger instance, using the given
+	/// configuration. This function should only be called once.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config, Format, Syslog};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let target = Regex::new("^mycrate::my_module")?; // optional
+	/// let config = Config::new(log::Level::Info, target)
+	///     .format(Format::Syslog(Syslog::new("myapp")));
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::warn!(target: "mycrate::my_module", "This is a warning.");
+	///
+	/// let entry = logger.read().next().unwrap();
+	///
+	/// assert!(entry.starts_with("<12>1 "));
+	/// assert!(entry.contains(" - myapp "));
+	/// assert!(entry.ends_with("[mycrate::my_module] This is a warning."));
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Retention policies decide which entries are buffered, such as only the ones around
+	/// errors:
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # use std::{thread, time::Duration};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .retain_on_error(Duration::from_millis(100));
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "Request 1.");
+	/// thread::sleep(Duration::from_millis(200));
+	/// log::info!(target: "mycrate", "Request 2.");
+	/// log::error!(target: "mycrate", "Request 2 failed.");
+	///
+	/// let entries: Vec<_> = logger.read_entries().collect();
+	///
+	/// assert_eq!(entries.len(), 2);
+	/// assert_eq!(entries[0].message(), "Request 2.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Or limiting how many entries each target may log per second:
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # use std::{thread, time::Duration};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).rate_limit(2);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// for i in 0 .. 5 {
+	///     log::info!(target: "mycrate::net", "Retrying {}.", i);
+	/// }
+	/// thread::sleep(Duration::from_secs(1));
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let messages: Vec<_> = logger.read_entries().map(|entry| entry.message().to_owned()).collect();
+	///
+	/// assert_eq!(
+	///     messages,
+	///     ["Retrying 0.", "Retrying 1.", "suppressed 3 messages", "Connected."]
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Or collapsing runs of identical entries:
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).deduplicate(true);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// for _ in 0 .. 58 {
+	///     log::warn!(target: "mycrate::net", "Connection refused.");
+	/// }
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let messages: Vec<_> = logger.read_entries().map(|entry| entry.message().to_owned()).collect();
+	///
+	/// assert_eq!(
+	///     messages,
+	///     ["Connection refused.", "last message repeated 57 times", "Connected."]
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Retained entries may be kept in a custom storage, such as a file:
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config, FileStorage};
+	/// # use regex::Regex;
+	/// # use std::fs;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let path = std::env::temp_dir().join("memory_logger_storage.log");
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .storage(FileStorage::create(&path)?)
+	///     .retain(2);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "First.");
+	/// log::info!(target: "mycrate", "Second.");
+	/// log::info!(target: "mycrate", "Third.");
+	///
+	/// let messages: Vec<_> = logger.read_entries().map(|entry| entry.message().to_owned()).collect();
+	///
+	/// assert_eq!(messages, ["Second.", "Third."]);
+	/// assert!(fs::read_to_string(&path)?.ends_with("[mycrate] INFO  | Third.\n"));
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Or in memory, spilling older entries to disk past a threshold:
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config, SpillStorage};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .storage(SpillStorage::new(32)?);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "First.");
+	/// log::info!(target: "mycrate", "Second.");
+	/// log::info!(target: "mycrate", "Third.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump(&mut dump)?;
+	///
+	/// assert_eq!(
+	///     String::from_utf8(dump)?,
+	///     "[mycrate] INFO  | First.\n\
+	///      [mycrate] INFO  | Second.\n\
+	///      [mycrate] INFO  | Third.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance.
+	pub fn setup_with(config: Config) -> Result<&'static Self, SetLoggerError> {
+		Self::install(config, Vec::new())
+	}
+
 
-						tx,
-					},
+	/// Setup a new MemoryLogger like `setup_with`, also installing a global `tracing`
+	/// subscriber that forwards events through a [`MemoryLayer`](../struct.MemoryLayer.html),
+	/// so that events emitted through `tracing`, such as by dependencies, are buffered too.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?);
+	/// let logger = MemoryLogger::setup_with_tracing_bridge(config)?;
+	///
+	/// log::info!(target: "mycrate", "Through log.");
+	/// tracing::info!(target: "mycrate", "Through tracing.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump(&mut dump)?;
+	///
+	/// assert_eq!(
+	///     String::from_utf8(dump)?,
+	///     "[mycrate] INFO  | Through log.\n\
+	///      [mycrate] INFO  | Through tracing.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance. If the subscriber cannot be installed,
+	/// the logger remains installed.
+	#[cfg(feature = "tracing")]
+	pub fn setup_with_tracing_bridge(config: Config) -> Result<&'static Self, BridgeError> {
+		let logger = Self::setup_with(config)?;
+
+		layer::install_subscriber()?;
+
+		Ok(logger)
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, buffering the entries of the snapshot at
+	/// the given path, as written by `persist`. Restored entries are renumbered from zero,
+	/// and new entries are numbered after them. If the snapshot is missing or unreadable,
+	/// the logger starts empty.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let path = std::env::temp_dir().join("memory_logger_async_restore.snapshot");
+	/// # let _ = std::fs::remove_file(&path);
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?);
+	/// let logger = MemoryLogger::setup_with_restore(config, &path)?;
+	///
+	/// log::info!(target: "mycrate", "Started.");
+	///
+	/// // Restored by the next run.
+	/// logger.persist(&path)?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance.
+	pub fn setup_with_restore<P>(config: Config, path: P) -> Result<&'static Self, SetLoggerError>
+	where
+		P: AsRef<Path>
+	{
+		let restored = persist::read(path.as_ref()).unwrap_or_default();
+
+		Self::install(config, restored)
+	}
 
-					rx,
-				}
-			)
-		);
+
+	fn install(config: Config, restored: Vec<LogEntry>) -> Result<&'static Self, SetLoggerError> {
+		let level = config.level;
+
+		let logger = Self::build(config, restored);
 
 		log::set_logger(&logger.logger)?;
 
@@ -146,33 +729,2375 @@ impl MemoryLogger {
 			level.to_level_filter()
 		);
 
+		logger.start();
+
 		Ok(logger)
 	}
 
 
-	/// Dump the contents to a writer, cleaning the buffered contents.
-	pub fn dump<W>(&self, mut writer: W) -> io::Result<()>
-	where
-		W: Write
-	{
-		for record in self.rx.try_iter() {
-			writer.write_all(
-				record.as_bytes()
-			)?;
+	/// Create a new logger, without installing or starting it.
+	fn build(config: Config, restored: Vec<LogEntry>) -> &'static Self {
+		let level = config.level;
 
-			writeln!(writer)?;
-		}
+		let (tx, rx) = flume::unbounded();
 
-		Ok(())
-	}
+		// Retained entries are kept in a ring buffer by default, bounded by the drain thread.
+		let mut store = Store {
+			entries: VecDeque::new(),
+			storage: config.storage
+				.as_ref()
+				.and_then(Backend::take)
+				.unwrap_or_else(|| Box::new(RingStorage::new(usize::MAX))),
+			drained: 0,
+		};
 
+		let sequence = restored.len() as u64;
+		for (index, mut entry) in restored.into_iter().enumerate() {
+			entry.sequence = index as u64;
 
-	/// Gets an iterator to the buffered entries.
+			if config.retaining() {
+				store.storage.append(entry);
+			} else {
+				store.entries.push_back(entry);
+			}
+		}
+
+		store.bound(&config);
+
+		Box::leak(
+			Box::new(
+				Self {
+					logger: Logger {
+						level: AtomicUsize::new(level as usize),
+
+						counters: config.metrics.then(Counters::default),
+
+						retention: Retention::new(&config),
+
+						batches: (config.batch > 0).then(|| Batches::new(config.batch)),
+
+						config,
+
+						tx,
+
+						sequence: AtomicU64::new(sequence),
+
+						sent: AtomicU64::new(0),
+
+						subscribers: Mutex::default(),
+
+						subscribed: AtomicUsize::new(0),
+
+						#[cfg(feature = "tokio")]
+						updates: watch::Sender::new(0),
+					},
+
+					rx,
+
+					retained: Mutex::new(store),
+
+					drained: Condvar::new(),
+
+					marks: Mutex::default(),
+
+					removed: AtomicU64::new(0),
+				}
+			)
+		)
+	}
+
+
+	/// Start draining the channel in the background, if retention is enabled.
+	fn start(&'static self) {
+		// Without threads, entries are moved out of the channel by reads instead.
+		#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+		if self.retaining() {
+			std::thread::Builder::new()
+				.name("memory_logger".into())
+				.spawn(move || self.drain())
+				.expect("failed to spawn drain thread");
+		}
+	}
+
+
+	/// Setup a new MemoryLogger per route of the given routing, partitioning entries into
+	/// them by target, and install a logger routing records to them. See
+	/// [`Routing`](../struct.Routing.html).
+	///
+	/// Returns the new MemoryLogger instances, by route name.
+	#[cfg(feature = "target")]
+	pub fn setup_routed(routing: Routing) -> Result<Routes<Self>, SetLoggerError> {
+		routing.install(Self::setup_composable)
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, without installing it as the global
+	/// logger, along with a `log::Log` implementation buffering records in it, which may be
+	/// plugged into dispatchers such as fern's. See [`MemoryLog`](../struct.MemoryLog.html).
+	///
+	/// Returns the new MemoryLogger instance and its log.
+	pub fn setup_composable(config: Config) -> (&'static Self, MemoryLog) {
+		let logger = Self::build(config, Vec::new());
+
+		logger.start();
+
+		(logger, MemoryLog::new(&logger.logger))
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, without installing it as the global
+	/// logger, along with a log4rs appender buffering records in it. See
+	/// [`MemoryAppender`](../struct.MemoryAppender.html).
+	///
+	/// Returns the new MemoryLogger instance and its appender.
+	#[cfg(feature = "log4rs")]
+	pub fn setup_appender(config: Config) -> (&'static Self, MemoryAppender) {
+		let logger = Self::build(config, Vec::new());
+
+		logger.start();
+
+		(logger, MemoryAppender::new(&logger.logger))
+	}
+
+
+	/// The current level. Entries more verbose than this level are discarded.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::debug!(target: "mycrate", "Ignored.");
+	/// logger.set_level(log::Level::Debug);
+	/// log::debug!(target: "mycrate", "Captured.");
+	///
+	/// assert_eq!(logger.level(), log::Level::Debug);
+	/// assert_eq!(logger.len(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn level(&self) -> Level {
+		let level = self.logger.level.load(Ordering::Relaxed);
+
+		Level::iter()
+			.find(|candidate| *candidate as usize == level)
+			.expect("level should be valid")
+	}
+
+
+	/// Change the level, such as to capture debug entries while investigating an issue.
+	/// This also sets the `log` crate's maximum level.
+	pub fn set_level(&self, level: Level) {
+		self.logger.level.store(level as usize, Ordering::Relaxed);
+
+		log::set_max_level(
+			level.to_level_filter()
+		);
+	}
+
+
+	/// The configuration this logger was set up with.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// assert_eq!(logger.config().level(), log::Level::Info);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn config(&self) -> &Config {
+		&self.logger.config
+	}
+
+
+	/// A snapshot of the counts of captured entries, by level and target. Counts are only
+	/// kept if enabled through [`Config::metrics`](../struct.Config.html#method.metrics),
+	/// otherwise they are all zero.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).metrics(true);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::error!(target: "mycrate::db", "Connection lost.");
+	/// log::info!(target: "mycrate::db", "Reconnected.");
+	///
+	/// let metrics = logger.metrics();
+	///
+	/// assert_eq!(metrics.levels().get(log::Level::Error), 1);
+	/// assert_eq!(metrics.targets()["mycrate::db"].total(), 2);
+	/// assert!(
+	///     metrics
+	///         .to_prometheus()
+	///         .contains("memory_logger_entries_total{level=\"error\",target=\"mycrate::db\"} 1\n")
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn metrics(&self) -> Metrics {
+		self.logger.counters
+			.as_ref()
+			.map(Counters::snapshot)
+			.unwrap_or_default()
+	}
+
+
+	/// Statistics of the buffered entries: counts by level and target, and the timestamps
+	/// of the oldest and newest entries. The buffered entries are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::warn!(target: "mycrate::db", "Slow query.");
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let stats = logger.stats();
+	///
+	/// assert_eq!(stats.len(), 2);
+	/// assert_eq!(stats.levels().get(log::Level::Warn), 1);
+	/// assert_eq!(stats.targets()["mycrate::net"].get(log::Level::Info), 1);
+	/// assert!(stats.first() <= stats.last());
+	/// assert_eq!(logger.len(), 2);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn stats(&self) -> Stats {
+		if self.retaining() {
+			return Stats::of(self.sync().storage.iter());
+		}
+
+		Stats::of(&self.gather().entries)
+	}
+
+
+	/// The memory used by the buffered entries: the total length of their lines, and the
+	/// number of entries. The buffered entries are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let usage = logger.memory_usage();
+	///
+	/// assert_eq!(usage.bytes(), "[mycrate] INFO  | Connected.".len());
+	/// assert_eq!(usage.entries(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn memory_usage(&self) -> MemoryUsage {
+		if self.retaining() {
+			let retained = self.sync();
+
+			return MemoryUsage {
+				bytes: retained.storage.bytes(),
+				entries: retained.storage.len(),
+			};
+		}
+
+		let retained = self.gather();
+
+		MemoryUsage {
+			bytes: retained.entries
+				.iter()
+				.map(|entry| entry.line.len())
+				.sum(),
+			entries: retained.entries.len(),
+		}
+	}
+
+
+	/// The number of buffered entries. This does not consume the buffered entries.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// assert!(logger.is_empty());
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// assert_eq!(logger.len(), 1);
+	/// assert!(!logger.is_empty());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn len(&self) -> usize {
+		if self.retaining() {
+			return self.sync().storage.len();
+		}
+
+		self.gather().entries.len()
+	}
+
+
+	/// Whether there are no buffered entries.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+
+	/// A compact report of the buffered entries: total entries, counts per level, the `top`
+	/// noisiest targets, and the first and last errors. The buffered entries are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate::net", "Connecting.");
+	/// log::error!(target: "mycrate::db", "Connection lost.");
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let summary = logger.summary(1);
+	///
+	/// assert_eq!(
+	///     summary.to_string(),
+	///     "3 entries: 1 error, 0 warn, 2 info, 0 debug, 0 trace\n\
+	///      noisiest targets: mycrate::net (2)\n\
+	///      first error: [mycrate::db] ERROR | Connection lost.\n\
+	///      last error: [mycrate::db] ERROR | Connection lost."
+	/// );
+	/// assert_eq!(logger.len(), 3);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn summary(&self, top: usize) -> Summary {
+		Summary::from_entries(self.peek(), top)
+	}
+
+
+	/// Dump the contents to a writer, cleaning the buffered contents.
+	///
+	/// If writing fails, the buffered contents are kept, so that no entries are lost. Note
+	/// that the entries written before the failure will be written again by the next dump.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::io::{self, Write};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// struct Disconnected;
+	///
+	/// impl Write for Disconnected {
+	///     fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+	///         Err(io::ErrorKind::BrokenPipe.into())
+	///     }
+	///
+	///     fn flush(&mut self) -> io::Result<()> {
+	///         Ok(())
+	///     }
+	/// }
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// assert!(logger.dump(Disconnected).is_err());
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump(&mut dump)?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		self.config().write_header(&mut writer)?;
+
+		self.dump_with(
+			|entry| {
+				writer.write_all(
+					entry.as_bytes()
+				)?;
+
+				writeln!(writer)
+			}
+		)
+	}
+
+
+	/// Dump the contents to a writer like `dump`, rendering the entries in the given format
+	/// instead of the configured one.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config, Format};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .format(Format::Message);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_as(&mut dump, &Format::Text)?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_as<W>(&self, mut writer: W, format: &Format) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut line = String::new();
+
+		self.config().write_header(&mut writer)?;
+
+		self.dump_with(
+			|entry| {
+				line.clear();
+				entry.write_as(format, &mut line);
+				line.push('\n');
+
+				writer.write_all(line.as_bytes())
+			}
+		)
+	}
+
+
+	/// Dump the contents to a writer in a versioned, machine-readable format, cleaning the
+	/// buffered contents only if writing succeeds. Such dumps can be read through
+	/// [`Schema::read`](../enum.Schema.html#method.read) by tooling built against other
+	/// versions of this crate. The process header, if any, is not written.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Schema};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_schema(&mut dump, Schema::Json)?;
+	///
+	/// assert!(dump.starts_with(b"{\"schema\":\"memory_logger\",\"version\":1}\n"));
+	///
+	/// let entries = Schema::read(&dump[..])?;
+	///
+	/// assert_eq!(entries[0].message(), "Connected.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_schema<W>(&self, mut writer: W, schema: Schema) -> io::Result<()>
+	where
+		W: Write
+	{
+		schema.write_header(&mut writer)?;
+
+		self.dump_with(|entry| schema.write_entry(&mut writer, entry))
+	}
+
+
+	/// Dump the contents to a writer, keeping the buffered contents, so that they may be
+	/// dumped again later.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut first = Vec::new();
+	/// logger.dump_keep(&mut first)?;
+	///
+	/// let mut second = Vec::new();
+	/// logger.dump(&mut second)?;
+	///
+	/// assert_eq!(first, second);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_keep<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		self.config().write_header(&mut writer)?;
+
+		for record in self.peek() {
+			writer.write_all(
+				record.as_bytes()
+			)?;
+
+			writeln!(writer)?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Dump the contents to a writer, normalizing volatile fields, such as timestamps, to
+	/// placeholders, so that the dump can be committed as a snapshot. The buffered contents
+	/// are kept. The process header, if any, is not written.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Normalizer};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let buffer = [0u8; 16];
+	/// log::info!(target: "mycrate", "Allocated a buffer at {:p}.", &buffer);
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_normalized(&mut dump, &Normalizer::new())?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] INFO  | Allocated a buffer at [pointer].\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "snapshot")]
+	pub fn dump_normalized<W>(&self, mut writer: W, normalizer: &Normalizer) -> io::Result<()>
+	where
+		W: Write
+	{
+		for record in self.peek() {
+			writer.write_all(
+				normalizer
+					.normalize(record.as_str())
+					.as_bytes()
+			)?;
+
+			writeln!(writer)?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Dump the entries with the given level or above to a writer, removing them from the
+	/// buffered contents only if writing succeeds. Entries below the given level are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Debug, Regex::new("^mycrate")?)?;
+	/// log::debug!(target: "mycrate", "Polling.");
+	/// log::error!(target: "mycrate", "Connection lost.");
+	/// log::debug!(target: "mycrate", "Polling.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_filtered(&mut dump, log::Level::Warn)?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] ERROR | Connection lost.\n");
+	/// assert_eq!(logger.len(), 2);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_filtered<W>(&self, mut writer: W, level: Level) -> io::Result<()>
+	where
+		W: Write
+	{
+		self.config().write_header(&mut writer)?;
+
+		let mut write = |entry: &LogEntry| {
+			writer.write_all(entry.as_bytes())?;
+			writeln!(writer)
+		};
+
+		self.logger.flush_pending();
+
+		if self.retaining() {
+			return self.sync()
+				.storage
+				.iter()
+				.filter(|entry| entry.level <= level)
+				.try_for_each(|entry| write(&entry));
+		}
+
+		let mut retained = self.gather();
+
+		retained.entries
+			.iter()
+			.filter(|entry| entry.level <= level)
+			.try_for_each(&mut write)?;
+
+		let (dumped, kept) = std::mem::take(&mut retained.entries)
+			.into_iter()
+			.partition::<Vec<_>, _>(|entry| entry.level <= level);
+
+		retained.entries = kept.into();
+
+		self.removed(dumped.len());
+
+		for entry in dumped {
+			self.logger.recycle(entry.line);
+		}
+
+		Ok(())
+	}
+
+
+	/// Dump the contents to several writers in a single pass, such as a file and stderr.
+	/// The buffered contents are cleaned only if writing to all of them succeeds.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let (mut file, mut stderr) = (Vec::new(), Vec::new());
+	/// logger.dump_all(&mut [&mut file, &mut stderr])?;
+	///
+	/// assert_eq!(String::from_utf8(file)?, "[mycrate] INFO  | Connected.\n");
+	/// assert_eq!(String::from_utf8(stderr)?, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_all(&self, writers: &mut [&mut dyn Write]) -> io::Result<()> {
+		for writer in writers.iter_mut() {
+			self.config().write_header(writer)?;
+		}
+
+		self.dump_with(
+			|entry| {
+				for writer in writers.iter_mut() {
+					writer.write_all(entry.as_bytes())?;
+					writeln!(writer)?;
+				}
+
+				Ok(())
+			}
+		)
+	}
+
+
+	/// Dump the contents to the file at the given path like `dump`, appending to it. The file
+	/// is created if it does not exist.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::fs;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_asynchronous_dump.log");
+	/// # let _ = fs::remove_file(&path);
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// logger.dump_to_path(&path)?;
+	///
+	/// log::info!(target: "mycrate", "Disconnected.");
+	/// logger.dump_to_path(&path)?;
+	///
+	/// assert_eq!(
+	///     fs::read_to_string(&path)?,
+	///     "[mycrate] INFO  | Connected.\n\
+	///      [mycrate] INFO  | Disconnected.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_to_path<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		file::append(path.as_ref(), |writer| self.dump(writer))
+	}
+
+
+	/// Dump the contents to the file at the given path like `dump_to_path`, rotating it
+	/// first if it has reached `max_size` bytes. Rotated files are named by appending a
+	/// number to the path, from `.1` for the newest, and at most `keep` of them are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::fs;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_asynchronous_rotation.log");
+	/// # let _ = fs::remove_file(&path);
+	///
+	/// for i in 0 .. 3 {
+	///     log::info!(target: "mycrate", "Dump {}.", i);
+	///     logger.dump_to_rotating_path(&path, 1, 1)?;
+	/// }
+	///
+	/// assert_eq!(fs::read_to_string(&path)?, "[mycrate] INFO  | Dump 2.\n");
+	/// assert_eq!(fs::read_to_string(path.with_extension("log.1"))?, "[mycrate] INFO  | Dump 1.\n");
+	/// assert!(!path.with_extension("log.2").exists());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_to_rotating_path<P>(&self, path: P, max_size: u64, keep: usize) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		let path = path.as_ref();
+
+		file::rotate(path, max_size, keep)?;
+
+		self.dump_to_path(path)
+	}
+
+
+	/// Serve a request for the buffered entries, described by its query string, such as
+	/// `level=warn&since=42`. The buffered contents are kept. See the
+	/// [`http`](../http/index.html) module for the parameters and response format.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// log::warn!(target: "mycrate", "Retrying.");
+	///
+	/// let response = logger.serve_http("level=warn");
+	/// let body = std::str::from_utf8(response.body())?;
+	///
+	/// assert_eq!(response.status(), 200);
+	/// assert!(body.starts_with(r#"{"entries":[{"sequence":1,"#));
+	/// assert!(body.ends_with(r#""message":"Retrying.","line":"[mycrate] WARN  | Retrying."}],"next":2}"#));
+	/// assert_eq!(logger.serve_http("level=loud").status(), 400);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "http")]
+	pub fn serve_http(&self, query: &str) -> http::Response {
+		http::serve(query, &self.peek())
+	}
+
+
+	/// Listen on the given address for plain TCP clients, such as `nc`, sending each of them
+	/// the buffered entries followed by a live tail of new entries. The buffered contents
+	/// are kept. Each client is served by its own thread, and is disconnected if it falls
+	/// more than 1024 entries behind, or if writing to it blocks for 10 seconds.
+	///
+	/// Returns the bound address, which is useful when binding to port `0`.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::io::{BufRead, BufReader};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Started.");
+	///
+	/// let address = logger.serve_tcp("127.0.0.1:0")?;
+	/// let mut client = BufReader::new(std::net::TcpStream::connect(address)?);
+	/// let mut line = String::new();
+	///
+	/// client.read_line(&mut line)?;
+	/// assert_eq!(line, "[mycrate] INFO  | Started.\n");
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// line.clear();
+	/// client.read_line(&mut line)?;
+	/// assert_eq!(line, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn serve_tcp<A>(&'static self, address: A) -> io::Result<SocketAddr>
+	where
+		A: ToSocketAddrs
+	{
+		tail::serve_tcp(address, move |stream| self.follow(stream))
+	}
+
+
+	/// Write the buffered entries to the writer, followed by new entries as they are logged,
+	/// until writing fails or the writer falls behind.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn follow<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		// Subscribe first, so that entries logged meanwhile are not missed.
+		let rx = self.subscribe_bounded(tail::BACKLOG);
+
+		let mut last = None;
+
+		for entry in self.peek() {
+			writeln!(writer, "{}", entry.line)?;
+			last = Some(entry.sequence);
+		}
+
+		loop {
+			let entry = rx
+				.recv()
+				.map_err(|_| io::Error::other("the client fell behind the live tail"))?;
+
+			if last.map_or(true, |last| entry.sequence > last) {
+				writeln!(writer, "{}", entry.line)?;
+			}
+		}
+	}
+
+
+	/// Listen on the given Unix domain socket path for control commands, such as `dump`,
+	/// `clear` and `set-level debug`. Each client is served by its own thread. See the
+	/// [`control`](../control/index.html) module for the protocol.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::{io::{BufRead, BufReader, Write}, os::unix::net::UnixStream};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_asynchronous.sock");
+	///
+	/// logger.serve_uds(&path)?;
+	///
+	/// let mut client = UnixStream::connect(&path)?;
+	/// client.write_all(b"set-level debug\n")?;
+	///
+	/// let mut response = String::new();
+	/// BufReader::new(client).read_line(&mut response)?;
+	///
+	/// assert_eq!(response, "ok\n");
+	/// assert_eq!(logger.level(), log::Level::Debug);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "control", unix))]
+	pub fn serve_uds<P>(&'static self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		control::serve_uds(path.as_ref(), self)
+	}
+
+
+	/// Write a snapshot of the buffered entries to the given path, to be restored by
+	/// `setup_with_restore`. The buffered contents are kept.
+	///
+	/// Snapshots keep the line, level, target and timestamp of entries, but not their
+	/// location, thread or key-value pairs. Key-value pairs rendered as JSON are restored as
+	/// part of the message.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_async_persist.snapshot");
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// logger.persist(&path)?;
+	///
+	/// assert_eq!(logger.len(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn persist<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		persist::write(path.as_ref(), &self.peek())
+	}
+
+
+	/// Spawn a background worker exporting the buffered entries to the pipeline's sinks
+	/// at its interval, consuming the buffered entries.
+	///
+	/// If retention is enabled, the buffered entries are kept, and the worker exports the
+	/// entries logged after this call instead. The worker is stopped when the returned handle is dropped.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, LogEntry, Pipeline};
+	/// # use regex::Regex;
+	/// # use std::time::Duration;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let (tx, rx) = std::sync::mpsc::channel();
+	/// let pipeline = Pipeline::new(Duration::from_secs(1))
+	///     .sink(
+	///         move |entries: &[LogEntry]| {
+	///             for entry in entries {
+	///                 tx.send(entry.message().to_owned()).unwrap();
+	///             }
+	///             Ok(())
+	///         }
+	///     );
+	/// let worker = logger.export(pipeline)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// worker.stop();
+	///
+	/// assert_eq!(rx.try_iter().collect::<Vec<_>>(), ["Connected."]);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn export(&'static self, pipeline: Pipeline) -> io::Result<Worker> {
+		if self.retaining() {
+			let subscription = self.subscribe();
+
+			return pipeline.spawn(move || subscription.read().collect());
+		}
+
+		pipeline.spawn(move || self.read_entries().collect())
+	}
+
+
+	/// Dump the contents to a writer like `dump`, but printing the target only once for
+	/// consecutive entries that share it. Repeated targets are replaced by a ditto mark.
+	/// This only affects the default format. Other formats are dumped as-is.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate::net", "Connecting.");
+	/// log::warn!(target: "mycrate::net", "Connection refused, retrying.");
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_elided(&mut dump)?;
+	///
+	/// assert_eq!(
+	///     String::from_utf8(dump)?,
+	///     "[mycrate::net] INFO  | Connecting.\n\
+	///      \" WARN  | Connection refused, retrying.\n\
+	///      \" INFO  | Connected.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_elided<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut previous: Option<Arc<str>> = None;
+
+		self.dump_with(
+			|entry| {
+				match self.logger.config.format.strip_target(&entry.target, &entry.line) {
+					Some(rest) if previous.as_ref() == Some(&entry.target) => {
+						writer.write_all(b"\"")?;
+						writer.write_all(rest.as_bytes())?;
+					}
+
+					_ => {
+						writer.write_all(entry.line.as_bytes())?;
+						previous = Some(entry.target.clone());
+					}
+				}
+
+				writeln!(writer)
+			}
+		)
+	}
+
+
+	/// Dump the contents to a writer as frames, cleaning the buffered contents. See the
+	/// [`framing`](../framing/index.html) module for the format.
+	#[cfg(feature = "framing")]
+	pub fn dump_framed<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut frame = Vec::new();
+
+		self.dump_with(
+			|entry| {
+				frame.clear();
+
+				framing::encode_entry(
+					entry.level,
+					&entry.target,
+					entry.message(),
+					entry.timestamp,
+					|bytes| frame.extend_from_slice(bytes)
+				);
+
+				writer.write_all(&frame)
+			}
+		)
+	}
+
+
+	/// Dump the contents to a writer encrypted with the given key, cleaning the buffered
+	/// contents. Each entry's line is sealed separately, so dumps may be appended to the same
+	/// file. See the [`crypto`](../crypto/index.html) module for the format.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, crypto::Key};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let key = Key::new([7; 32]);
+	///
+	/// log::info!(target: "mycrate", "User alice@example.com logged in.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_encrypted(&mut dump, &key)?;
+	///
+	/// assert!(!dump.windows(5).any(|window| window == b"alice"));
+	/// assert_eq!(key.decrypt(&dump)?, "[mycrate] INFO  | User alice@example.com logged in.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "crypto")]
+	pub fn dump_encrypted<W>(&self, mut writer: W, key: &Key) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut frame = Vec::new();
+		let mut line = Vec::new();
+
+		self.dump_with(
+			|entry| {
+				frame.clear();
+				line.clear();
+				line.extend_from_slice(entry.line.as_bytes());
+				line.push(b'\n');
+
+				key.seal(&line, &mut frame);
+				writer.write_all(&frame)
+			}
+		)
+	}
+
+
+	/// Dump the contents to the systemd journal, cleaning the buffered contents.
+	///
+	/// Each entry is sent with its message, and the `PRIORITY` and `CODE_MODULE` fields
+	/// set from its level and target. If sending fails, the entries sent so far are
+	/// removed, and the rest are kept.
+	///
+	/// ```no_run
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// log::info!("Shutting down.");
+	///
+	/// logger.dump_to_journal()?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "journald", unix))]
+	pub fn dump_to_journal(&self) -> io::Result<()> {
+		let mut journal = journald::Journal::connect()?;
+
+		self.logger.flush_pending();
+
+		if self.retaining() {
+			return self.sync()
+				.storage
+				.iter()
+				.try_for_each(|entry| journal.send(entry.level, &entry.target, entry.message()));
+		}
+
+		let mut retained = self.gather();
+
+		while let Some(entry) = retained.entries.front() {
+			journal.send(entry.level, &entry.target, entry.message())?;
+
+			if let Some(entry) = retained.entries.pop_front() {
+				self.removed(1);
+				self.logger.recycle(entry.line);
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// Dump the contents to the browser console, cleaning the buffered contents. Each entry
+	/// is written through the console function matching its level, such as `console.warn`
+	/// for warnings, so that devtools may filter them.
+	///
+	/// ```ignore
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// log::info!("Rendered.");
+	///
+	/// logger.dump_to_console();
+	/// ```
+	#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+	pub fn dump_to_console(&self) {
+		for entry in self.take() {
+			console::write(entry.level, &entry.line);
+
+			self.logger.recycle(entry.line);
+		}
+	}
+
+
+	/// Add the most recent entries as breadcrumbs to the current Sentry hub, so that error
+	/// reports include the log context leading up to the failure.
+	///
+	/// At most `limit` entries are added, oldest first.
+	/// The buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// log::warn!("Retrying.");
+	///
+	/// logger.add_breadcrumbs(100);
+	/// sentry_core::capture_message("Failed to connect", sentry_core::Level::Error);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "sentry")]
+	pub fn add_breadcrumbs(&self, limit: usize) {
+		let entries = self.peek();
+		let skip = entries.len().saturating_sub(limit);
+
+		for entry in &entries[skip ..] {
+			sentry_core::add_breadcrumb(Breadcrumb::from(entry));
+		}
+	}
+
+
+	/// Run a function, appending the entries captured while it runs to the panic message
+	/// if it panics. See the [`fail_with_logs`](../macro.fail_with_logs.html) macro.
+	///
+	/// The buffered entries are not consumed, as with `peek`.
+	#[track_caller]
+	pub fn fail_with_logs<F, T>(&self, f: F) -> T
+	where
+		F: FnOnce() -> T
+	{
+		let start = self.logger.sequence.load(Ordering::Relaxed);
+
+		match panic::catch_unwind(AssertUnwindSafe(f)) {
+			Ok(value) => value,
+			Err(payload) => panic!(
+				"{}",
+				capture::report(
+					capture::panic_message(&*payload),
+					&self.text_since(start)
+				)
+			),
+		}
+	}
+
+
+	/// Assert that an entry with the given level, whose message contains the pattern, was
+	/// logged. See the [`assert_logged`](../macro.assert_logged.html) macro.
+	#[track_caller]
+	pub fn assert_logged(&self, level: Level, pattern: &str) {
+		if let Err(report) = capture::check_logged(&self.peek(), level, pattern) {
+			panic!("{}", report);
+		}
+	}
+
+
+	/// Assert that no errors were logged. See the
+	/// [`assert_no_errors`](../macro.assert_no_errors.html) macro.
+	#[track_caller]
+	pub fn assert_no_errors(&self) {
+		if let Err(report) = capture::check_no_errors(&self.peek()) {
+			panic!("{}", report);
+		}
+	}
+
+
+	/// Start expectations on a snapshot of the buffered entries, checked by
+	/// [`Expectation::verify`](../struct.Expectation.html#method.verify).
+	pub fn expect(&self) -> Expectation {
+		Expectation::new(self.peek())
+	}
+
+
+	/// Run a fallible function, appending the entries captured while it runs to the error
+	/// message if it fails or panics. This is meant for custom test harnesses, such as
+	/// `libtest-mimic`, where tests report failures through a `Result`:
+	///
+	/// ```ignore
+	/// Trial::test("connects", move || Ok(logger.with_logs(|| connect())?))
+	/// ```
+	///
+	/// The buffered entries are not consumed, as with `peek`.
+	pub fn with_logs<F, T, E>(&self, f: F) -> Result<T, String>
+	where
+		F: FnOnce() -> Result<T, E>,
+		E: Display,
+	{
+		let start = self.logger.sequence.load(Ordering::Relaxed);
+
+		let message = match panic::catch_unwind(AssertUnwindSafe(f)) {
+			Ok(Ok(value)) => return Ok(value),
+			Ok(Err(error)) => error.to_string(),
+			Err(payload) => capture::panic_message(&*payload).to_owned(),
+		};
+
+		Err(
+			capture::report(message, &self.text_since(start))
+		)
+	}
+
+
+	/// Render the buffered entries numbered from the given sequence number onwards.
+	fn text_since(&self, start: u64) -> String {
+		let mut text = String::new();
+
+		for entry in self.peek().iter().filter(|entry| entry.sequence >= start) {
+			text.push_str(&entry.line);
+			text.push('\n');
+		}
+
+		text
+	}
+
+
+	/// Gets an iterator to the rendered lines of the buffered entries. See
+	/// [`read_entries`](#method.read_entries) for the entries themselves.
+	///
+	/// This iterator will consume the entries, unless retention is enabled. If you wish to
+	/// iterate twice, you must collect.
+	pub fn read(&self) -> impl Iterator<Item = Box<str>> + '_ {
+		self.take()
+			.map(|entry| entry.line.into_boxed_str())
+	}
+
+
+	/// Gets an iterator to the buffered entries, with their level, target and timestamp.
+	///
+	/// This iterator will consume the entries, unless retention is enabled. If you wish to
+	/// iterate twice, you must collect.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::warn!(target: "mycrate", "Disk almost full.");
+	///
+	/// let entry = logger.read_entries().next().unwrap();
+	///
+	/// assert_eq!(entry.level(), log::Level::Warn);
+	/// assert_eq!(entry.message(), "Disk almost full.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn read_entries(&self) -> impl Iterator<Item = LogEntry> + '_ {
+		self.take()
+	}
+
+
+	/// Gets a stream of the buffered entries, which waits for new entries as they are
+	/// logged. This allows async applications to consume entries as they arrive:
+	///
+	/// ```ignore
+	/// let mut stream = logger.stream();
+	///
+	/// while let Some(entry) = stream.next().await {
+	///     panel.push(entry);
+	/// }
+	/// ```
+	///
+	/// Like `read`, this stream will consume the entries. It never ends.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use futures_core::Stream;
+	/// # use std::{pin::Pin, sync::Arc, task::{Context, Poll, Wake, Waker}};
+	/// # struct Noop;
+	/// # impl Wake for Noop { fn wake(self: Arc<Self>) { } }
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	///
+	/// let mut stream = logger.stream();
+	/// let waker = Waker::from(Arc::new(Noop));
+	/// let mut cx = Context::from_waker(&waker);
+	///
+	/// match Pin::new(&mut stream).poll_next(&mut cx) {
+	///     Poll::Ready(Some(entry)) => assert!(entry.contains("This is a info.")),
+	///     _ => panic!("entry should be ready"),
+	/// }
+	///
+	/// assert!(Pin::new(&mut stream).poll_next(&mut cx).is_pending());
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// # Panics
+	/// Panics if retention is enabled, as the drain thread would compete for the entries.
+	/// Use a [`Subscription`](struct.Subscription.html) stream instead.
+	#[cfg(feature = "stream")]
+	pub fn stream(&self) -> impl Stream<Item = LogEntry> + Unpin + '_ {
+		self.assert_not_retaining();
+
+		self.logger.flush_batches();
+
+		EntryStream {
+			retained: std::mem::take(&mut self.lock_retained().entries),
+			pending: self.rx.stream(),
+			removed: &self.removed,
+		}
+	}
+
+
+	/// Wait for the next entry, up to the given timeout. Returns `None` if no entry was
+	/// logged in time.
+	///
+	/// Like `read`, this consumes the entry.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::time::Duration;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// std::thread::spawn(|| log::info!(target: "mycrate", "This is a info."));
+	///
+	/// let entry = logger.recv_timeout(Duration::from_secs(5));
+	///
+	/// assert!(entry.unwrap().contains("This is a info."));
+	/// assert!(logger.recv_timeout(Duration::from_millis(10)).is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// # Panics
+	/// Panics if retention is enabled, as the drain thread would compete for the entries.
+	/// Use a [`Subscription`](struct.Subscription.html) instead.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn recv_timeout(&self, timeout: Duration) -> Option<LogEntry> {
+		self.assert_not_retaining();
+
+		self.logger.flush_batches();
+
+		let entry = self.pop_retained()
+			.or_else(|| self.unpack(self.rx.recv_timeout(timeout).ok()?));
+
+		self.removed(usize::from(entry.is_some()));
+
+		entry
+	}
+
+
+	/// Gets an iterator to the buffered entries, which waits for new entries as they are
+	/// logged, allowing a dedicated consumer thread to sleep instead of polling `read`.
+	///
+	/// Like `read`, this iterator will consume the entries. It never ends.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// std::thread::spawn(
+	///     || for i in 0 .. 3 {
+	///         log::info!(target: "mycrate", "Step {}.", i);
+	///     }
+	/// );
+	///
+	/// let entries: Vec<_> = logger.iter_blocking().take(3).collect();
+	///
+	/// assert_eq!(entries[2].message(), "Step 2.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// # Panics
+	/// Panics if retention is enabled, as the drain thread would compete for the entries.
+	/// Use a [`Subscription`](struct.Subscription.html) instead.
+	pub fn iter_blocking(&self) -> impl Iterator<Item = LogEntry> + '_ {
+		self.assert_not_retaining();
+
+		self.logger.flush_batches();
+
+		std::iter::from_fn(
+			move || self.pop_retained().or_else(|| self.unpack(self.rx.recv().ok()?))
+		)
+			.inspect(move |_| self.removed(1))
+	}
+
+
+	/// Gets a copy of the buffered entries, along with the version of the buffer they
+	/// reflect. Like `peek`, this doesn't consume the entries. See
+	/// [`View`](../struct.View.html).
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let view = logger.view();
+	/// assert_eq!(view.get(0).unwrap().message(), "Connected.");
+	///
+	/// // Every frame:
+	/// if logger.version() != view.version() {
+	///     unreachable!("nothing was logged");
+	/// }
+	///
+	/// log::info!(target: "mycrate", "Disconnected.");
+	/// assert_ne!(logger.version(), view.version());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn view(&self) -> View {
+		// Take the version first, so that a concurrent change yields a newer version.
+		let version = self.version();
+
+		View::new(self.peek(), version)
+	}
+
+
+	/// The version of the buffer, which changes whenever entries are logged or removed.
+	/// This is cheap, so that user interfaces may poll it every frame, only taking a new
+	/// [`view`](#method.view) once it changes.
+	pub fn version(&self) -> u64 {
+		self.logger.sent.load(Ordering::Acquire) + self.removed.load(Ordering::Acquire)
+	}
+
+
+	/// Count entries removed from the buffer.
+	fn removed(&self, count: usize) {
+		self.removed.fetch_add(count as u64, Ordering::Release);
+	}
+
+
+	/// Gets a copy of the buffered entries, without consuming them. Subsequent reads and
+	/// dumps will still yield such entries.
+	///
+	/// This moves the pending entries into an internal store, which is shared by all
+	/// consumers. Logging is not affected, but concurrent reads may briefly block each
+	/// other.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	///
+	/// assert_eq!(logger.peek().len(), 1);
+	/// assert_eq!(logger.peek()[0].sequence(), 0);
+	///
+	/// assert!(logger.read().next().unwrap().contains("This is a info."));
+	/// assert!(logger.peek().is_empty());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn peek(&self) -> Vec<LogEntry> {
+		if self.retaining() {
+			return self.sync()
+				.storage
+				.iter()
+				.collect();
+		}
+
+		let mut retained = self.gather();
+
+		retained
+			.entries
+			.make_contiguous()
+			.to_vec()
+	}
+
+
+	/// Gets the newest `n` buffered entries, oldest first. The buffered entries are not
+	/// consumed, as with `peek`.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// for i in 0 .. 10 {
+	///     log::info!(target: "mycrate", "Step {}.", i);
+	/// }
+	///
+	/// let tail = logger.tail(2);
+	///
+	/// assert_eq!(tail.len(), 2);
+	/// assert_eq!(tail[0].message(), "Step 8.");
+	/// assert_eq!(tail[1].message(), "Step 9.");
+	/// assert_eq!(tail[1].sequence(), 9);
+	/// assert_eq!(logger.len(), 10);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn tail(&self, n: usize) -> Vec<LogEntry> {
+		if self.retaining() {
+			let retained = self.sync();
+			let skip = retained.storage.len().saturating_sub(n);
+
+			return retained.storage
+				.iter()
+				.skip(skip)
+				.collect();
+		}
+
+		let retained = self.gather();
+		let skip = retained.entries.len().saturating_sub(n);
+
+		retained.entries
+			.iter()
+			.skip(skip)
+			.cloned()
+			.collect()
+	}
+
+
+	/// Locks the retained entries, moving the pending entries in the channel into them.
+	/// This must not be used if retention is enabled.
+	fn gather(&self) -> MutexGuard<'_, Store> {
+		self.logger.flush_batches();
+
+		let mut retained = self.lock_retained();
+		retained.entries.extend(self.rx.try_iter().flatten());
+		retained
+	}
+
+
+	/// Write each buffered entry, cleaning the buffered contents only if all writes succeed.
+	/// The contents are kept if retention is enabled.
+	fn dump_with<F>(&self, mut write: F) -> io::Result<()>
+	where
+		F: FnMut(&LogEntry) -> io::Result<()>
+	{
+		self.logger.flush_pending();
+
+		if self.retaining() {
+			return self.sync()
+				.storage
+				.iter()
+				.try_for_each(|entry| write(&entry));
+		}
+
+		let mut retained = self.gather();
+
+		retained.entries
+			.iter()
+			.try_for_each(&mut write)?;
+
+		self.removed(retained.entries.len());
+
+		for entry in retained.entries.drain(..) {
+			self.logger.recycle(entry.line);
+		}
+
+		Ok(())
+	}
+
+
+	/// Takes the retained entries, followed by the pending entries in the channel. If
+	/// retention is enabled, copies the retained entries instead.
+	///
+	/// Entries are taken one at a time, keeping the rest of a batch for the next reads, so
+	/// that those not yet iterated stay buffered if the iterator is dropped.
+	fn take(&self) -> impl Iterator<Item = LogEntry> + '_ {
+		let retaining = self.retaining();
+
+		self.logger.flush_pending();
+		self.logger.flush_batches();
+
+		let (copied, taken) = if retaining {
+			(self.sync().storage.iter().collect(), None)
+		} else {
+			let taken = std::iter::from_fn(
+				move || self.pop_retained().or_else(|| self.unpack(self.rx.try_recv().ok()?))
+			);
+
+			(Vec::new(), Some(taken))
+		};
+
+		copied
+			.into_iter()
+			.chain(
+				taken
+					.into_iter()
+					.flatten()
+			)
+			.inspect(move |_| if !retaining { self.removed(1) })
+	}
+
+
+	/// Move entries from the channel into the ring buffer, evicting the oldest entries once
+	/// full. This runs in the background thread for the lifetime of the program.
+	#[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), allow(dead_code))]
+	fn drain(&self) {
+		for packet in self.rx.iter() {
+			let mut retained = self.lock_retained();
+
+			for entry in packet {
+				self.retain(&mut retained, entry);
+			}
+
+			drop(retained);
+
+			self.drained.notify_all();
+		}
+	}
+
+
+	/// Move an entry into the ring buffer, evicting the oldest entries once full.
+	fn retain(&self, retained: &mut Store, entry: LogEntry) {
+		retained.storage.append(entry);
+		retained.drained += 1;
+
+		let evicted = retained.bound(&self.logger.config);
+		self.removed(evicted);
+	}
+
+
+	/// Wait for the drain thread to move all entries logged so far, and lock the ring
+	/// buffer.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn sync(&self) -> MutexGuard<'_, Store> {
+		self.logger.flush_batches();
+
+		let sent = self.logger.sent.load(Ordering::Acquire);
+
+		self.drained
+			.wait_while(self.lock_retained(), |retained| retained.drained < sent)
+			.expect("retained lock poisoned")
+	}
+
+
+	/// Move all entries logged so far into the ring buffer, and lock it. Without threads,
+	/// there is no drain thread to do so.
+	#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+	fn sync(&self) -> MutexGuard<'_, Store> {
+		self.logger.flush_batches();
+
+		let mut retained = self.lock_retained();
+
+		for entry in self.rx.try_iter().flatten() {
+			self.retain(&mut retained, entry);
+		}
+
+		retained
+	}
+
+
+	fn retaining(&self) -> bool {
+		self.logger.config.retaining()
+	}
+
+
+	/// Consumers that wait on the channel would compete with the drain thread when
+	/// retention is enabled.
+	fn assert_not_retaining(&self) {
+		assert!(!self.retaining(), "waiting for entries is not available with retention enabled");
+	}
+
+
+	/// Takes the first entry of a packet received from the channel, keeping the rest of a
+	/// batch for the next reads.
+	fn unpack(&self, packet: Packet) -> Option<LogEntry> {
+		match packet {
+			Packet::Entry(entry) => Some(entry),
+			Packet::Batch(entries) => {
+				let mut entries = entries.into_iter();
+				let first = entries.next();
+
+				self.lock_retained().entries.extend(entries);
+
+				first
+			}
+		}
+	}
+
+
+	/// Takes the oldest entry moved out of the channel by non-consuming reads.
+	fn pop_retained(&self) -> Option<LogEntry> {
+		self.lock_retained()
+			.entries
+			.pop_front()
+	}
+
+
+	fn lock_retained(&self) -> MutexGuard<'_, Store> {
+		self.retained
+			.lock()
+			.expect("retained lock poisoned")
+	}
+
+
+	/// Set a mark at the current point of the log, so that the entries logged after it may
+	/// be read with `read_since_mark`. Setting an existing mark moves it.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Starting.");
+	///
+	/// logger.mark("phase-1");
+	/// log::info!(target: "mycrate", "Loading.");
+	///
+	/// let mut phase_1 = logger.read_since_mark("phase-1").unwrap();
+	///
+	/// assert_eq!(phase_1.next().unwrap().message(), "Loading.");
+	/// assert!(phase_1.next().is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn mark(&self, name: &str) {
+		let sequence = self.logger.sequence.load(Ordering::Relaxed);
+
+		self.marks
+			.lock()
+			.expect("marks lock poisoned")
+			.insert(name.into(), sequence);
+	}
+
+
+	/// Gets an iterator to the buffered entries logged since the given mark was set, or
+	/// `None` if there is no such mark.
+	///
+	/// Like `read`, this iterator will consume the entries. Entries logged before the mark
+	/// are discarded.
+	pub fn read_since_mark(&self, name: &str) -> Option<impl Iterator<Item = LogEntry> + '_> {
+		let sequence = *self.marks
+			.lock()
+			.expect("marks lock poisoned")
+			.get(name)?;
+
+		Some(
+			self.take()
+				.filter_map(
+					move |entry| {
+						if entry.sequence >= sequence {
+							Some(entry)
+						} else {
+							self.logger.recycle(entry.line);
+							None
+						}
+					}
+				)
+		)
+	}
+
+
+	/// Query the buffered entries by level range, target, message and time range. The
+	/// buffered entries are not consumed, as with `peek`.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::warn!(target: "mycrate::db", "Slow query: 120ms.");
+	/// log::warn!(target: "mycrate::net", "Slow handshake: 300ms.");
+	/// log::info!(target: "mycrate::db", "Slow start.");
+	///
+	/// let entries = logger
+	///     .query()
+	///     .levels(log::Level::Error ..= log::Level::Warn)
+	///     .target(Regex::new("::db$")?)
+	///     .contains("Slow")
+	///     .run();
+	///
+	/// assert_eq!(entries.len(), 1);
+	/// assert_eq!(entries[0].message(), "Slow query: 120ms.");
+	/// assert_eq!(logger.len(), 3);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn query(&self) -> Query<'_> {
+		Query::new(self)
+	}
+
+
+	/// Search the buffered entries' lines for a pattern, returning the matching entries with
+	/// their indices and the positions of the matches, so that a debugging UI can jump
+	/// between hits. An empty pattern matches nothing. The buffered entries are not
+	/// consumed, as with `peek`.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connecting.");
+	/// log::warn!(target: "mycrate", "Timeout, timeout.");
+	///
+	/// let hits = logger.search("imeout");
+	///
+	/// assert_eq!(hits.len(), 1);
+	/// assert_eq!(hits[0].index(), 1);
+	/// assert_eq!(hits[0].matches(), [19 .. 25, 28 .. 34]);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn search(&self, pattern: &str) -> Vec<Hit> {
+		if self.retaining() {
+			let retained = self.sync();
+			let removed = self.removed.load(Ordering::Acquire) as usize;
+
+			return retained.storage
+				.iter()
+				.enumerate()
+				.filter_map(|(position, entry)| Hit::search(removed + position, entry, pattern))
+				.collect();
+		}
+
+		let retained = self.gather();
+		let removed = self.removed.load(Ordering::Acquire) as usize;
+
+		retained.entries
+			.iter()
+			.enumerate()
+			.filter(|(_, entry)| entry.line.contains(pattern))
+			.filter_map(
+				|(position, entry)| Hit::search(removed + position, entry.clone(), pattern)
+			)
+			.collect()
+	}
+
+
+	/// Wait until an entry matching the pattern is logged, or the timeout expires. The
+	/// buffered entries are searched first, so entries logged before the call are found
+	/// too. The buffered entries are not consumed, as with `peek`.
+	///
+	/// This is meant for tests that assert on entries logged by background threads.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::time::Duration;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// std::thread::spawn(|| log::info!(target: "mycrate", "Listening on port 8080."));
+	///
+	/// let entry = logger.wait_for(&Regex::new("port \\d+")?, Duration::from_secs(5));
+	///
+	/// assert_eq!(entry.unwrap().message(), "Listening on port 8080.");
+	/// assert!(logger.wait_for(&Regex::new("Stopped")?, Duration::from_millis(10)).is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "target", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+	pub fn wait_for(&self, pattern: &Regex, timeout: Duration) -> Option<LogEntry> {
+		let deadline = Instant::now() + timeout;
+
+		// Subscribe before searching, so that no entry is missed in between.
+		let subscription = self.subscribe();
+
+		if let Some(entry) = self.peek().into_iter().find(|entry| pattern.is_match(entry)) {
+			return Some(entry);
+		}
+
+		while let Ok(entry) = subscription.rx.recv_deadline(deadline) {
+			if pattern.is_match(&entry) {
+				return Some(entry);
+			}
+		}
+
+		None
+	}
+
+
+	/// Gets a receiver announcing how many entries have been logged, so that async
+	/// consumers may await new entries instead of polling the buffer.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let mut updates = logger.updates();
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	///
+	/// // In an async context, `updates.changed().await` waits for this.
+	/// assert!(updates.has_changed()?);
+	/// assert_eq!(*updates.borrow_and_update(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "tokio")]
+	pub fn updates(&self) -> watch::Receiver<u64> {
+		self.logger.updates.subscribe()
+	}
+
+
+	/// Subscribe to the entries logged from now on. Each subscription receives a copy of
+	/// every entry, independently of other subscriptions and of the logger's own buffer,
+	/// allowing several consumers to see all entries.
+	///
+	/// Dropping the subscription unsubscribes. While there are subscribers, logging takes a
+	/// short lock to distribute the entries.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let panel = logger.subscribe();
+	/// let dumper = logger.subscribe();
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	///
+	/// assert!(panel.read().next().unwrap().contains("This is a info."));
+	/// assert!(dumper.read().next().unwrap().contains("This is a info."));
+	/// assert!(logger.read().next().unwrap().contains("This is a info."));
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn subscribe(&self) -> Subscription {
+		let (tx, rx) = flume::unbounded();
+
+		self.attach(tx);
+
+		Subscription { rx }
+	}
+
+
+	/// Subscribe with room for the given number of pending entries. Once the subscription
+	/// falls further behind, it is disconnected, so that a stalled consumer can't grow
+	/// without bound.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn subscribe_bounded(&self, capacity: usize) -> Receiver<LogEntry> {
+		let (tx, rx) = flume::bounded(capacity.max(1));
+
+		self.attach(tx);
+
+		rx
+	}
+
+
+	/// Add a subscriber.
+	fn attach(&self, tx: Sender<LogEntry>) {
+		let mut subscribers = self.logger.lock_subscribers();
+
+		subscribers.push(tx);
+
+		self.logger.subscribed.store(subscribers.len(), Ordering::Release);
+	}
+
+
+	/// Gets the Server-Sent Events of the buffered entries logged after the given event
+	/// id, followed by the events of new entries as they are logged. The id is usually
+	/// taken from the `Last-Event-ID` header of reconnecting clients. The buffered contents
+	/// are kept. See the [`sse`](../sse/index.html) module for the format.
 	///
-	/// This iterator will consume the entries. If you wish to iterate twice, you must
-	/// collect.
-	pub fn read<'a>(&'a self) -> impl Iterator<Item = Box<str>> + 'a {
-		self.rx.try_iter()
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut events = logger.events(None);
+	///
+	/// log::warn!(target: "mycrate", "Retrying.");
+	///
+	/// assert!(events.next().unwrap().starts_with("id: 0\nevent: log\ndata: {"));
+	/// assert!(events.next().unwrap().ends_with("\"line\":\"[mycrate] WARN  | Retrying.\"}\n\n"));
+	///
+	/// // A reconnecting client only gets the entries it missed.
+	/// assert!(logger.events(Some(0)).next().unwrap().starts_with("id: 1\n"));
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "sse")]
+	pub fn events(&self, last_event_id: Option<u64>) -> sse::Events {
+		// Subscribe first, so that entries logged meanwhile are not missed.
+		let Subscription { rx } = self.subscribe();
+
+		let backlog = self.peek()
+			.into_iter()
+			.filter(|entry| last_event_id.map_or(true, |last| entry.sequence > last))
+			.collect();
+
+		sse::Events::new(backlog, rx, last_event_id)
+	}
+
+
+	/// Spawn a thread accepting WebSocket viewers from the given listener, pushing new
+	/// entries to each of them as they are logged. The buffered contents are kept. See the
+	/// [`ws`](../ws/index.html) module for the protocol.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, ws::Tail};
+	/// # use regex::Regex;
+	/// # use std::net::TcpListener;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let listener = TcpListener::bind("127.0.0.1:0")?;
+	/// let address = listener.local_addr()?;
+	/// logger.serve_ws(listener, Tail::new())?;
+	///
+	/// let (mut viewer, _) = tungstenite::connect(format!("ws://{}/", address))?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// assert_eq!(viewer.read()?.into_text()?.as_str(), "[mycrate] INFO  | Connected.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "ws", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+	pub fn serve_ws(&'static self, listener: TcpListener, tail: ws::Tail) -> io::Result<()> {
+		tail.spawn(listener, move |capacity| self.subscribe_bounded(capacity))
+	}
+
+
+	/// Gets an iterator to the buffered entries at or above the given level, such as only
+	/// warnings and errors.
+	///
+	/// Like `read`, this iterator will consume the entries. Entries below the given level
+	/// are discarded.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	/// log::warn!(target: "mycrate", "This is a warning.");
+	///
+	/// let mut warnings = logger.read_at_least(log::Level::Warn);
+	///
+	/// assert!(warnings.next().unwrap().contains("This is a warning."));
+	/// assert!(warnings.next().is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn read_at_least(&self, level: Level) -> impl Iterator<Item = LogEntry> + '_ {
+		self.take()
+			.filter_map(
+				move |entry| {
+					if entry.level <= level {
+						Some(entry)
+					} else {
+						self.logger.recycle(entry.line);
+						None
+					}
+				}
+			)
+	}
+
+
+	/// Gets the buffered entries logged from the calling thread. The buffered contents are
+	/// kept. As `cargo test` runs tests in parallel threads, this keeps assertions on logs
+	/// deterministic.
+	///
+	/// Entries are only bucketed by thread if thread capture is enabled through
+	/// [`Config::thread`](../struct.Config.html#method.thread). Otherwise, no entries are
+	/// returned.
+	///
+	/// ```
+	/// # use memory_logger::{asynchronous::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .thread(true);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// std::thread::spawn(|| log::info!(target: "mycrate", "From another test."))
+	///     .join()
+	///     .unwrap();
+	/// log::info!(target: "mycrate", "From this test.");
+	///
+	/// let entries = logger.read_current_thread();
+	///
+	/// assert_eq!(entries.len(), 1);
+	/// assert_eq!(entries[0].message(), "From this test.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn read_current_thread(&self) -> Vec<LogEntry> {
+		let current = std::thread::current().id();
+
+		let mut entries = self.peek();
+
+		entries.retain(|entry| entry.thread().is_some_and(|thread| thread.id() == current));
+
+		entries
+	}
+
+
+	/// Discards the buffered entries, including the retained ones if retention is enabled.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	/// logger.clear();
+	///
+	/// assert!(logger.read().next().is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn clear(&self) {
+		if self.retaining() {
+			let mut retained = self.sync();
+
+			self.removed(retained.storage.len());
+
+			return retained.storage.clear();
+		}
+
+		let mut retained = self.gather();
+
+		self.removed(retained.entries.len());
+
+		for entry in retained.entries.drain(..) {
+			self.logger.recycle(entry.line);
+		}
+	}
+
+
+	/// Buffer a synthetic entry, such as a marker between requests, or an event from a
+	/// subsystem that doesn't log through the `log` facade. The entry is rendered and
+	/// handled like a logged record, but regardless of the level and target filters.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// logger.push(log::Level::Info, "mycrate", "==== request 1234 start ====");
+	/// log::info!(target: "mycrate", "Handling.");
+	///
+	/// let entry = memory_logger::LogEntry::parse("[modem] WARN  | Signal lost.").unwrap();
+	/// logger.push_entry(entry);
+	///
+	/// let entries = logger.peek();
+	///
+	/// assert_eq!(entries[0].message(), "==== request 1234 start ====");
+	/// assert_eq!(entries[2].as_str(), "[modem] WARN  | Signal lost.");
+	/// assert_eq!(entries[2].sequence(), 2);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn push<M>(&self, level: Level, target: &str, message: M)
+	where
+		M: Display
+	{
+		self.logger.capture(
+			&Record::builder()
+				.level(level)
+				.target(target)
+				.args(format_args!("{}", message))
+				.build()
+		);
+	}
+
+
+	/// Buffer an entry that has already been built, such as one parsed from a dump, as
+	/// with `push`. The entry's line is kept as is, and it gets the next sequence number.
+	pub fn push_entry(&self, mut entry: LogEntry) {
+		entry.sequence = self.logger.sequence.fetch_add(1, Ordering::Relaxed);
+
+		self.logger.broadcast(&entry);
+		self.logger.config.observe(&entry);
+		self.logger.send(entry);
+	}
+
+
+	/// Import the entries of a previously written dump, appending them to the buffer as
+	/// with `push_entry`, such as to aggregate the logs of multiple runs or processes.
+	/// Returns the number of imported entries.
+	///
+	/// The dump may be a schema dump in either format, JSON Lines of entry objects, or text
+	/// lines in the default or syslog format. Text lines that can't be parsed are appended
+	/// to the preceding entry, as continuations of multi-line messages, or skipped if there
+	/// is none, such as the process header. Returns an error of kind `InvalidData` if a
+	/// JSON dump is malformed or of a newer version.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let dump = "header\n[worker] WARN  | Retrying:\n  connection refused\n[worker] INFO  | Done.\n";
+	///
+	/// assert_eq!(logger.import(dump.as_bytes())?, 2);
+	///
+	/// let entries = logger.peek();
+	///
+	/// assert_eq!(entries[0].message(), "Retrying:\n  connection refused");
+	/// assert_eq!(entries[1].target(), "worker");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn import<R>(&self, reader: R) -> io::Result<usize>
+	where
+		R: Read
+	{
+		let entries = Schema::import(reader)?;
+		let count = entries.len();
+
+		for entry in entries {
+			self.push_entry(entry);
+		}
+
+		Ok(count)
+	}
+
+
+	/// Gets an `io::Write` and `fmt::Write` adapter recording each written line as an entry
+	/// with the given level and target, such as to capture the output of a child process or
+	/// to stream a report built with `write!`. See [`LogWriter`](../struct.LogWriter.html).
+	pub fn as_writer(&self, level: Level, target: &str) -> LogWriter<'_> {
+		LogWriter::new(self, level, target)
+	}
+
+
+	/// Gets a guard of the entries logged during its lifetime, which are removed once it is
+	/// dropped, so that sequential tests are independent without explicit `clear` calls.
+	/// Entries logged before the guard are kept.
+	///
+	/// ```
+	/// # use memory_logger::asynchronous::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Starting.");
+	///
+	/// {
+	///     let guard = logger.capture_guard();
+	///     log::info!(target: "mycrate", "Testing.");
+	///
+	///     assert_eq!(guard.entries()[0].message(), "Testing.");
+	/// }
+	///
+	/// let guard = logger.capture_guard();
+	/// log::info!(target: "mycrate", "Testing again.");
+	///
+	/// assert_eq!(guard.take()[0].message(), "Testing again.");
+	/// assert_eq!(logger.peek()[0].message(), "Starting.");
+	/// assert_eq!(logger.peek().len(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn capture_guard(&self) -> CaptureGuard<'_> {
+		CaptureGuard {
+			logger: self,
+			start: self.logger.sequence.load(Ordering::Relaxed),
+			thread: None,
+		}
+	}
+
+
+	/// Like [`capture_guard`](#method.capture_guard), but only guards the entries logged
+	/// from the current thread. Requires [`Config::thread`](../struct.Config.html#method.thread).
+	#[cfg(feature = "macros")]
+	pub(crate) fn capture_thread_guard(&self) -> CaptureGuard<'_> {
+		CaptureGuard {
+			thread: Some(std::thread::current().id()),
+			..self.capture_guard()
+		}
+	}
+
+
+	/// Remove and return the buffered entries that match the predicate.
+	fn remove_where<F>(&self, mut remove: F) -> Vec<LogEntry>
+	where
+		F: FnMut(&LogEntry) -> bool
+	{
+		let removed: Vec<_> = if self.retaining() {
+			let mut retained = self.sync();
+
+			let (removed, kept): (Vec<_>, Vec<_>) = retained.storage
+				.take()
+				.into_iter()
+				.partition(|entry| remove(entry));
+
+			for entry in kept {
+				retained.storage.append(entry);
+			}
+
+			removed
+		} else {
+			let mut retained = self.gather();
+
+			let (removed, kept): (VecDeque<_>, VecDeque<_>) = retained.entries
+				.drain(..)
+				.partition(|entry| remove(entry));
+
+			retained.entries = kept;
+
+			removed.into()
+		};
+
+		self.removed(removed.len());
+
+		removed
+	}
+}
+
+
+impl Source for MemoryLogger {
+	fn select(&self, criteria: &Criteria) -> Vec<LogEntry> {
+		let matches = |entry: &LogEntry| criteria.matches(
+			entry.level,
+			&entry.target,
+			entry.message(),
+			entry.timestamp
+		);
+
+		if self.retaining() {
+			return self.sync()
+				.storage
+				.iter()
+				.filter(|entry| matches(entry))
+				.collect();
+		}
+
+		self.gather()
+			.entries
+			.iter()
+			.filter(|entry| matches(entry))
+			.cloned()
+			.collect()
+	}
+}
+
+
+impl Push for MemoryLogger {
+	fn push_line(&self, level: Level, target: &str, line: &str) {
+		self.push(level, target, line)
 	}
 }
 
@@ -184,3 +3109,149 @@ impl std::fmt::Debug for MemoryLogger {
 			.finish()
 	}
 }
+
+
+/// An independent receiver of log entries. See
+/// [`MemoryLogger::subscribe`](struct.MemoryLogger.html#method.subscribe).
+#[derive(Debug)]
+pub struct Subscription {
+	rx: Receiver<LogEntry>,
+}
+
+
+impl Subscription {
+	/// Gets an iterator to the entries received so far.
+	///
+	/// This iterator will consume the entries, but does not affect other subscriptions.
+	pub fn read(&self) -> impl Iterator<Item = LogEntry> + '_ {
+		self.rx.try_iter()
+	}
+
+
+	/// Wait for the next entry.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn recv(&self) -> LogEntry {
+		self.rx
+			.recv()
+			.expect("logger should never disconnect")
+	}
+
+
+	/// Gets a stream of the received entries, which waits for new entries as they are
+	/// logged. It never ends.
+	#[cfg(feature = "stream")]
+	pub fn stream(&self) -> impl Stream<Item = LogEntry> + Unpin + '_ {
+		self.rx.stream()
+	}
+}
+
+
+/// A guard of the entries logged during its lifetime, which are removed once it is
+/// dropped. See [`MemoryLogger::capture_guard`](struct.MemoryLogger.html#method.capture_guard).
+#[derive(Debug)]
+pub struct CaptureGuard<'a> {
+	logger: &'a MemoryLogger,
+	/// The sequence number of the first entry logged during the guard's lifetime.
+	start: u64,
+	/// The only thread whose entries are guarded, if any.
+	thread: Option<ThreadId>,
+}
+
+
+impl<'a> CaptureGuard<'a> {
+	/// Gets the entries logged so far during the guard's lifetime. The buffered contents
+	/// are kept.
+	pub fn entries(&self) -> Vec<LogEntry> {
+		let mut entries = self.logger.peek();
+
+		entries.retain(|entry| self.guards(entry));
+
+		entries
+	}
+
+
+	/// Remove and return the entries logged during the guard's lifetime.
+	pub fn take(self) -> Vec<LogEntry> {
+		self.logger.remove_where(|entry| self.guards(entry))
+	}
+
+
+	/// Whether the entry is guarded.
+	fn guards(&self, entry: &LogEntry) -> bool {
+		entry.sequence >= self.start
+			&& self.thread.map_or(
+				true,
+				|current| entry.thread().is_some_and(|thread| thread.id() == current)
+			)
+	}
+}
+
+
+impl<'a> Drop for CaptureGuard<'a> {
+	fn drop(&mut self) {
+		for entry in self.logger.remove_where(|entry| self.guards(entry)) {
+			self.logger.logger.recycle(entry.line);
+		}
+	}
+}
+
+
+/// The retained entries, followed by the entries in the channel.
+#[cfg(feature = "stream")]
+struct EntryStream<'a> {
+	retained: VecDeque<LogEntry>,
+	pending: RecvStream<'a, Packet>,
+	removed: &'a AtomicU64,
+}
+
+
+#[cfg(feature = "stream")]
+impl<'a> Stream for EntryStream<'a> {
+	type Item = LogEntry;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		loop {
+			if let Some(entry) = self.retained.pop_front() {
+				self.removed.fetch_add(1, Ordering::Release);
+				return Poll::Ready(Some(entry));
+			}
+
+			match Pin::new(&mut self.pending).poll_next(cx) {
+				Poll::Ready(Some(packet)) => self.retained.extend(packet),
+				Poll::Ready(None) => return Poll::Ready(None),
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+	}
+}
+
+
+#[cfg(feature = "http")]
+impl http::Serve for MemoryLogger {
+	fn serve_http(&self, query: &str) -> http::Response {
+		MemoryLogger::serve_http(self, query)
+	}
+}
+
+
+#[cfg(all(feature = "control", unix))]
+impl control::Control for MemoryLogger {
+	fn dump(&self, writer: &mut dyn Write) -> io::Result<()> {
+		MemoryLogger::dump(self, writer)
+	}
+
+
+	fn clear(&self) {
+		MemoryLogger::clear(self)
+	}
+
+
+	fn level(&self) -> Level {
+		MemoryLogger::level(self)
+	}
+
+
+	fn set_level(&self, level: Level) {
+		MemoryLogger::set_level(self, level)
+	}
+}
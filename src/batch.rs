@@ -0,0 +1,139 @@
+//! Per-thread batches of entries, so that logging threads send entries through the channel
+//! together instead of one by one.
+
+use std::{
+	cell::RefCell,
+	iter::Chain,
+	option,
+	sync::{Arc, Mutex, MutexGuard},
+	vec,
+};
+
+use crate::LogEntry;
+
+
+/// What is sent through the channel: a single entry, or a batch of entries, oldest first.
+#[derive(Debug)]
+pub(crate) enum Packet {
+	Entry(LogEntry),
+	Batch(Vec<LogEntry>),
+}
+
+
+impl Packet {
+	/// The number of entries in the packet.
+	pub(crate) fn len(&self) -> usize {
+		match self {
+			Self::Entry(_) => 1,
+			Self::Batch(entries) => entries.len(),
+		}
+	}
+
+
+	/// The last entry in the packet, if any.
+	#[cfg(feature = "tokio")]
+	pub(crate) fn last(&self) -> Option<&LogEntry> {
+		match self {
+			Self::Entry(entry) => Some(entry),
+			Self::Batch(entries) => entries.last(),
+		}
+	}
+}
+
+
+impl IntoIterator for Packet {
+	type Item = LogEntry;
+	type IntoIter = Chain<option::IntoIter<LogEntry>, vec::IntoIter<LogEntry>>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		let (entry, batch) = match self {
+			Self::Entry(entry) => (Some(entry), Vec::new()),
+			Self::Batch(entries) => (None, entries),
+		};
+
+		entry
+			.into_iter()
+			.chain(batch)
+	}
+}
+
+
+type Batch = Arc<Mutex<Vec<LogEntry>>>;
+
+
+#[derive(Debug)]
+pub(crate) struct Batches {
+	/// How many entries a batch accumulates before it is sent.
+	size: usize,
+	/// The batch of each thread that has logged.
+	threads: Mutex<Vec<Batch>>,
+}
+
+
+impl Batches {
+	pub(crate) fn new(size: usize) -> Self {
+		Self {
+			size,
+			threads: Mutex::default(),
+		}
+	}
+
+
+	/// Add an entry to the current thread's batch, returning the batch's entries once full.
+	pub(crate) fn push(&self, entry: LogEntry) -> Option<Vec<LogEntry>> {
+		thread_local! {
+			static BATCH: RefCell<Option<Batch>> = const { RefCell::new(None) };
+		}
+
+		let mut entry = Some(entry);
+
+		let full = BATCH.try_with(
+			|local| {
+				let mut local = local.borrow_mut();
+				let batch = local.get_or_insert_with(|| self.register());
+				let mut entries = lock(batch);
+
+				entries.extend(entry.take());
+
+				(entries.len() >= self.size).then(|| std::mem::take(&mut *entries))
+			}
+		);
+
+		// Threads that are exiting send their entries on their own.
+		full.unwrap_or_else(|_| entry.map(|entry| vec![entry]))
+	}
+
+
+	/// Take the entries of all batches, ordered by sequence number.
+	pub(crate) fn take(&self) -> Vec<LogEntry> {
+		let mut threads = lock(&self.threads);
+		let mut entries = Vec::new();
+
+		for batch in threads.iter() {
+			entries.append(&mut lock(batch));
+		}
+
+		// The batches of exited threads are only referenced here.
+		threads.retain(|batch| Arc::strong_count(batch) > 1);
+
+		entries.sort_unstable_by_key(|entry| entry.sequence);
+
+		entries
+	}
+
+
+	fn register(&self) -> Batch {
+		let batch = Batch::default();
+
+		lock(&self.threads).push(batch.clone());
+
+		batch
+	}
+}
+
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex
+		.lock()
+		.expect("batch lock poisoned")
+}
@@ -1,188 +1,3113 @@
 use std::{
-	io::{self, Write},
-	fmt::Write as _,
+	collections::HashMap,
+	fmt::Display,
+	io::{self, Read, Write},
+	panic::{self, AssertUnwindSafe},
+	path::Path,
 	ops::Deref,
-	sync::{Mutex, MutexGuard},
+	sync::{
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+		Arc,
+	},
+	thread::ThreadId,
+	time::SystemTime,
 };
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::{
+	net::{SocketAddr, ToSocketAddrs},
+	time::Duration,
+};
+
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+#[cfg(feature = "parking_lot")]
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+#[cfg(all(feature = "target", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::time::Instant;
+
 use log::{Level, Log, Metadata, Record, SetLoggerError};
 
 #[cfg(feature = "target")]
 use regex::Regex;
 
+use crate::{
+	arena::{Arena, Slice},
+	capture,
+	entry::Details,
+	file,
+	format,
+	intern::intern,
+	metrics::{Counters, Tally},
+	persist,
+	Config,
+	Expectation,
+	Format,
+	LogEntry,
+	LogWriter,
+	MemoryLog,
+	MemoryUsage,
+	Metrics,
+	query::{Criteria, Source},
+	retention::Retention,
+	Hit,
+	Query,
+	Schema,
+	Stats,
+	Summary,
+	View,
+	writer::Push,
+};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::{tail, Pipeline, Worker};
+
+#[cfg(all(feature = "control", unix))]
+use crate::control;
+
+#[cfg(feature = "crypto")]
+use crate::crypto::Key;
+
+#[cfg(feature = "framing")]
+use crate::framing;
+
+#[cfg(feature = "http")]
+use crate::http;
+
+#[cfg(all(feature = "journald", unix))]
+use crate::journald;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+use crate::console;
+
+#[cfg(feature = "sentry")]
+use crate::sentry;
+
+#[cfg(feature = "log4rs")]
+use crate::MemoryAppender;
+
+#[cfg(feature = "snapshot")]
+use crate::Normalizer;
+
+#[cfg(feature = "target")]
+use crate::{Routes, Routing};
+
+#[cfg(feature = "tracing")]
+use crate::{layer, BridgeError};
+
+#[cfg(feature = "tokio")]
+use tokio::sync::watch;
+
+#[cfg(feature = "tokio")]
+use crate::updates::announce;
+
+
+/// Metadata of a buffered entry, and the location of its line in the arena.
+#[derive(Debug)]
+struct Span {
+	line: Slice,
+	sequence: u64,
+	level: Level,
+	target: Arc<str>,
+	timestamp: SystemTime,
+	/// Offset of the message in the line.
+	message: usize,
+	details: Option<Box<Details>>,
+}
+
+
+impl Span {
+	/// Build an owned entry from this span and its line.
+	fn to_entry(&self, line: &str) -> LogEntry {
+		LogEntry {
+			sequence: self.sequence,
+			level: self.level,
+			target: self.target.clone(),
+			timestamp: self.timestamp,
+			line: line.trim_end_matches('\n').into(),
+			message: self.message,
+			details: self.details.clone(),
+		}
+	}
+
+
+	/// The message of this span in its line.
+	fn message<'a>(&self, line: &'a str) -> &'a str {
+		let line = line.trim_end_matches('\n');
+
+		let suffix = self.details
+			.as_ref()
+			.map_or(0, |details| details.suffix);
+
+		&line[self.message .. line.len() - suffix]
+	}
+}
+
+
+#[derive(Debug, Default)]
+struct Buffer {
+	spans: Vec<Span>,
+	/// The lines of the entries.
+	arena: Arena,
+	/// The total length of the lines.
+	bytes: usize,
+	/// How many entries have been removed from the buffer. Adding this to an entry's
+	/// position in `spans` gives an index that is stable across clears.
+	discarded: usize,
+	/// Counts of the buffered entries.
+	tally: Tally,
+	/// The stable index of the next entry when each mark was set.
+	marks: HashMap<Box<str>, usize>,
+	/// The sequence number of the next entry.
+	sequence: u64,
+	/// Incremented whenever entries are buffered or removed.
+	version: u64,
+}
+
+
+impl Buffer {
+	/// Iterate over the entries' metadata and lines, including the trailing newline.
+	fn entries(&self) -> impl Iterator<Item = (&Span, &str)> + '_ {
+		self.spans
+			.iter()
+			.map(move |span| (span, self.arena.get(&span.line)))
+	}
+
+
+	fn next_sequence(&mut self) -> u64 {
+		let sequence = self.sequence;
+		self.sequence += 1;
+		sequence
+	}
+
+
+	/// Append an entry that has already been rendered.
+	fn push(&mut self, entry: &LogEntry) {
+		self.append(
+			&entry.line,
+			Span {
+				line: Slice::default(),
+				sequence: entry.sequence,
+				level: entry.level,
+				target: entry.target.clone(),
+				timestamp: entry.timestamp,
+				message: entry.message,
+				details: entry.details.clone(),
+			}
+		);
+	}
+
+
+	/// Append a line, without the trailing newline, and its span, whose line is set to it.
+	fn append(&mut self, line: &str, mut span: Span) -> &Span {
+		span.line = self.arena.push(line);
+
+		self.bytes += span.line.len();
+		self.tally.record(span.level, &span.target);
+		self.version += 1;
+
+		self.spans.push(span);
+
+		self.spans
+			.last()
+			.expect("a span was pushed")
+	}
+
+
+	/// The stable index of the next entry.
+	fn end(&self) -> usize {
+		self.discarded + self.spans.len()
+	}
+
+
+	/// The entries from the given stable index onwards. If such entry has been discarded,
+	/// all entries are returned.
+	fn entries_since(&self, index: usize) -> impl Iterator<Item = (&Span, &str)> + '_ {
+		self.entries()
+			.skip(index.saturating_sub(self.discarded))
+	}
+
+
+	/// The text of the entries from the given stable index onwards. If such entry has
+	/// been discarded, the whole text is returned.
+	fn text_since(&self, index: usize) -> String {
+		self.entries_since(index)
+			.map(|(_, line)| line)
+			.collect()
+	}
+
+
+	/// The concatenated lines.
+	fn text(&self) -> String {
+		self.text_since(self.discarded)
+	}
+
+
+	fn stats(&self) -> Stats {
+		Stats {
+			counts: self.tally.snapshot(),
+			first: self.spans.first().map(|span| span.timestamp),
+			last: self.spans.last().map(|span| span.timestamp),
+		}
+	}
+
+
+	/// Evict the oldest entries if the lines exceed the budget. Entries are evicted down to
+	/// seven eighths of the budget, so that the spans are not shifted for every entry.
+	fn evict(&mut self, budget: usize) {
+		if self.bytes <= budget {
+			return;
+		}
+
+		let mut excess = self.bytes - (budget - budget / 8);
+
+		let count = self.spans
+			.iter()
+			.take_while(
+				|span| {
+					let evicted = excess > 0;
+					excess = excess.saturating_sub(span.line.len());
+					evicted
+				}
+			)
+			.count();
+
+		self.discard(count);
+	}
+
+
+	/// Remove the given number of oldest entries.
+	fn discard(&mut self, count: usize) {
+		for span in self.spans.drain(.. count) {
+			self.bytes -= span.line.len();
+			self.tally.remove(span.level, &span.target);
+		}
+
+		self.arena.release(self.spans.first().map(|span| &span.line));
+		self.discarded += count;
+		self.version += 1;
+	}
+
+
+	/// Remove the entries from the given stable index onwards. If such entry has been
+	/// discarded, all entries are removed. The stable indices of the removed entries are
+	/// given to the next entries.
+	fn truncate(&mut self, index: usize) {
+		let start = index.saturating_sub(self.discarded);
+
+		let first = match self.spans.get(start) {
+			Some(span) => span.line,
+			None => return,
+		};
+
+		self.arena.truncate(&first);
+
+		for span in self.spans.drain(start ..) {
+			self.bytes -= span.line.len();
+			self.tally.remove(span.level, &span.target);
+		}
+
+		self.version += 1;
+	}
+
+
+	/// Remove the entries from the given stable index onwards that match the predicate.
+	/// The entries after them are pushed again, taking their stable indices.
+	fn remove_since<F>(&mut self, index: usize, mut remove: F)
+	where
+		F: FnMut(&Span) -> bool
+	{
+		let kept: Vec<_> = self
+			.entries_since(index)
+			.filter(|(span, _)| !remove(span))
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		self.truncate(index);
+
+		for entry in &kept {
+			self.push(entry);
+		}
+	}
+
+
+	/// Take the spans and their arena, replacing the spans with an empty list of the same
+	/// capacity, and clear the buffer.
+	fn take(&mut self) -> (Vec<Span>, Arena) {
+		let capacity = self.spans.capacity();
+		let spans = std::mem::replace(&mut self.spans, Vec::with_capacity(capacity));
+		let arena = std::mem::take(&mut self.arena);
+
+		self.discarded += spans.len();
+		self.clear();
+
+		(spans, arena)
+	}
+
+
+	/// Put entries taken by `take` back at the front of the buffer, before the entries
+	/// buffered since, giving them back their stable indices.
+	fn restore(&mut self, spans: Vec<Span>, arena: Arena) {
+		let newer: Vec<_> = self
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		self.clear();
+		self.discarded -= spans.len() + newer.len();
+
+		for span in &spans {
+			self.push(&span.to_entry(arena.get(&span.line)));
+		}
+
+		for entry in &newer {
+			self.push(entry);
+		}
+	}
+
+
+	fn clear(&mut self) {
+		self.version += 1;
+		self.discarded += self.spans.len();
+		self.bytes = 0;
+		self.spans.clear();
+		self.arena.release(None);
+		self.tally = Tally::default();
+	}
+}
+
+
+/// Entries rendered outside of the buffer's lock, waiting to be merged into the buffer.
+#[derive(Debug)]
+struct Shards {
+	shards: Box<[Mutex<Vec<LogEntry>>]>,
+	/// The total length of the sharded lines, which may briefly include lines that are
+	/// being sharded.
+	bytes: AtomicUsize,
+	/// The sequence number of the next entry.
+	sequence: AtomicU64,
+	/// How many threads are waiting for entries to be buffered.
+	waiters: AtomicUsize,
+}
+
+
+impl Shards {
+	fn new(count: usize, sequence: u64) -> Self {
+		Self {
+			shards: (0 .. count)
+				.map(|_| Mutex::new(Vec::new()))
+				.collect(),
+			bytes: AtomicUsize::new(0),
+			sequence: AtomicU64::new(sequence),
+			waiters: AtomicUsize::new(0),
+		}
+	}
+
+
+	/// The shard used by the current thread.
+	fn local(&self) -> &Mutex<Vec<LogEntry>> {
+		static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+		thread_local! {
+			static INDEX: usize = THREADS.fetch_add(1, Ordering::Relaxed);
+		}
+
+		let index = INDEX
+			.try_with(|index| *index)
+			.unwrap_or(0);
+
+		&self.shards[index % self.shards.len()]
+	}
+}
+
+
+/// Marks the current thread as waiting for entries until dropped.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct Waiting<'a>(&'a AtomicUsize);
+
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<'a> Drop for Waiting<'a> {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+
+#[cfg(not(feature = "parking_lot"))]
+fn acquire<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex
+		.lock()
+		.expect("inner lock poisoned")
+}
+
+
+#[cfg(feature = "parking_lot")]
+fn acquire<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex.lock()
+}
+
+
+#[derive(Debug)]
+struct Logger {
+	config: Config,
+
+	/// The current level, which starts at the configured one.
+	level: AtomicUsize,
+
+	counters: Option<Counters>,
+
+	retention: Option<Retention>,
+
+	buffer: Mutex<Buffer>,
+
+	shards: Option<Shards>,
+
+	/// Signaled whenever an entry is buffered.
+	logged: Condvar,
+
+	/// Announces how many entries have been logged.
+	#[cfg(feature = "tokio")]
+	updates: watch::Sender<u64>,
+}
+
+
+impl Logger {
+	/// Lock the buffer, merging the sharded entries into it.
+	fn lock(&self) -> MutexGuard<'_, Buffer> {
+		let mut buffer = acquire(&self.buffer);
+
+		self.merge(&mut buffer);
+
+		buffer
+	}
+
+
+	/// Lock the buffer for a dump or a drain, buffering the pending retention markers first.
+	fn lock_flushed(&self) -> MutexGuard<'_, Buffer> {
+		if let Some(retention) = &self.retention {
+			let mut observed = Vec::new();
+
+			retention.flush_pending(
+				|| self.next_sequence(),
+				|entry| {
+					self.push(&entry);
+
+					if self.config.observes(entry.level) {
+						observed.push(entry);
+					}
+				}
+			);
+
+			for entry in observed {
+				self.config.observe(&entry);
+			}
+		}
+
+		self.lock()
+	}
+
+
+	/// Move the sharded entries into the buffer, ordered by sequence number.
+	fn merge(&self, buffer: &mut Buffer) {
+		let shards = match &self.shards {
+			Some(shards) => shards,
+			None => return,
+		};
+
+		let mut entries = Vec::new();
+
+		for shard in shards.shards.iter() {
+			entries.append(&mut acquire(shard));
+		}
+
+		if entries.is_empty() {
+			return;
+		}
+
+		let bytes = entries
+			.iter()
+			.map(|entry| entry.line.len())
+			.sum();
+
+		shards.bytes.fetch_sub(bytes, Ordering::Relaxed);
+
+		entries.sort_unstable_by_key(|entry| entry.sequence);
+
+		for entry in &entries {
+			buffer.push(entry);
+		}
+
+		if let Some(budget) = self.config.max_bytes {
+			buffer.evict(budget);
+		}
+	}
+
+
+	fn next_sequence(&self) -> u64 {
+		match &self.shards {
+			Some(shards) => shards.sequence.fetch_add(1, Ordering::Relaxed),
+			None => self.lock().next_sequence(),
+		}
+	}
+
+
+	/// Mark the current thread as waiting for entries, so that sharded entries are
+	/// signaled.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn waiting(&self) -> Option<Waiting<'_>> {
+		self.shards
+			.as_ref()
+			.map(
+				|shards| {
+					shards.waiters.fetch_add(1, Ordering::SeqCst);
+					Waiting(&shards.waiters)
+				}
+			)
+	}
+
+
+	/// Wait for an entry to be logged, up to the timeout if any.
+	#[cfg(all(not(feature = "parking_lot"), not(all(target_arch = "wasm32", target_os = "unknown"))))]
+	fn wait<'a>(&self, buffer: MutexGuard<'a, Buffer>, timeout: Option<Duration>) -> MutexGuard<'a, Buffer> {
+		match timeout {
+			Some(timeout) => self.logged
+				.wait_timeout(buffer, timeout)
+				.expect("inner lock poisoned")
+				.0,
+
+			None => self.logged
+				.wait(buffer)
+				.expect("inner lock poisoned"),
+		}
+	}
+
+
+	/// Wait for an entry to be logged, up to the timeout if any.
+	#[cfg(all(feature = "parking_lot", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+	fn wait<'a>(&self, mut buffer: MutexGuard<'a, Buffer>, timeout: Option<Duration>) -> MutexGuard<'a, Buffer> {
+		match timeout {
+			Some(timeout) => {
+				self.logged.wait_for(&mut buffer, timeout);
+			}
+
+			None => self.logged.wait(&mut buffer),
+		}
+
+		buffer
+	}
+
+
+	/// Wait until an entry is buffered past the given position.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn wait_past(&self, position: usize) -> MutexGuard<'_, Buffer> {
+		let _waiting = self.waiting();
+		let mut buffer = self.lock();
+
+		while buffer.end() <= position {
+			buffer = self.wait(buffer, None);
+			self.merge(&mut buffer);
+		}
+
+		buffer
+	}
+
+
+	/// Wait until an entry is buffered, or the timeout elapses.
+	#[cfg(all(feature = "target", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+	fn wait_timeout<'a>(&'a self, mut buffer: MutexGuard<'a, Buffer>, timeout: Duration) -> MutexGuard<'a, Buffer> {
+		let _waiting = self.waiting();
+		let end = buffer.end();
+
+		// Entries may have been sharded before this thread was marked as waiting.
+		self.merge(&mut buffer);
+
+		if buffer.end() == end {
+			buffer = self.wait(buffer, Some(timeout));
+			self.merge(&mut buffer);
+		}
+
+		buffer
+	}
+
+
+	/// Buffer an entry that has already been rendered.
+	fn push(&self, entry: &LogEntry) {
+		{
+			let buffer = &mut *self.lock();
+
+			buffer.push(entry);
+
+			if let Some(budget) = self.config.max_bytes {
+				buffer.evict(budget);
+			}
+
+			#[cfg(feature = "tokio")]
+			announce(&self.updates, entry.sequence);
+		}
+
+		self.logged.notify_all();
+	}
+
+
+	/// Capture a record, regardless of the level and target filters.
+	fn capture(&self, record: &Record) {
+		if let Some(counters) = &self.counters {
+			counters.record(record.level(), format::record_target(record));
+		}
+
+		let timestamp = self.config.now();
+		let target = format::record_target(record);
+
+		if let Some(retention) = &self.retention {
+			if !retention.sample(record.level()) {
+				return;
+			}
+
+			let entry = LogEntry::render_into(
+				String::new(),
+				&self.config.format,
+				self.next_sequence(),
+				record.level(),
+				target,
+				self.config.details(record),
+				self.config.message(record),
+				timestamp
+			);
+
+			let mut observed = Vec::new();
+
+			retention.admit(
+				entry,
+				|| self.next_sequence(),
+				|entry| {
+					self.push(&entry);
+
+					if self.config.observes(entry.level) {
+						observed.push(entry);
+					}
+				}
+			);
+
+			// Observed once the retention lock is released, as the callbacks may log.
+			for entry in observed {
+				self.config.observe(&entry);
+			}
+
+			return;
+		}
+
+		if let Some(shards) = &self.shards {
+			let entry = LogEntry::render_into(
+				String::new(),
+				&self.config.format,
+				shards.sequence.fetch_add(1, Ordering::Relaxed),
+				record.level(),
+				target,
+				self.config.details(record),
+				self.config.message(record),
+				timestamp
+			);
+
+			self.config.observe(&entry);
+
+			#[cfg(feature = "tokio")]
+			let sequence = entry.sequence;
+
+			// Counted before pushing, so that merging never makes the count wrap.
+			let len = entry.line.len();
+			let sharded = shards.bytes.fetch_add(len, Ordering::Relaxed) + len;
+
+			acquire(shards.local()).push(entry);
+
+			#[cfg(feature = "tokio")]
+			announce(&self.updates, sequence);
+
+			// Sharded entries are bounded by the budget too, even if the buffer is never
+			// read. Locking merges them, evicting the oldest entries.
+			if self.config.max_bytes.is_some_and(|budget| sharded > budget / 8) {
+				drop(self.lock());
+			}
+
+			// Waiting threads merge the entry once signaled.
+			if shards.waiters.load(Ordering::SeqCst) > 0 {
+				drop(acquire(&self.buffer));
+				self.logged.notify_all();
+			}
+
+			return;
+		}
+
+		// The line is rendered before locking, so that the lock is only held to copy it
+		// into the arena.
+		let observed = format::with_scratch(
+			|line| {
+				let details = self.config.details(record);
+
+				let (message, suffix) = self.config.format.write_parts(
+					line,
+					record.level(),
+					target,
+					details.as_ref(),
+					self.config.message(record),
+					timestamp
+				);
+
+				let span = Span {
+					line: Slice::default(),
+					sequence: 0,
+					level: record.level(),
+					target: intern(target),
+					timestamp,
+					message,
+					details: details.map(|details| Box::new(Details { suffix, ..details })),
+				};
+
+				let buffer = &mut *self.lock();
+
+				let sequence = buffer.next_sequence();
+				let span = buffer.append(
+					line,
+					Span {
+						sequence,
+						..span
+					}
+				);
+
+				let observed = self.config
+					.observes(record.level())
+					.then(|| span.to_entry(line));
+
+				if let Some(budget) = self.config.max_bytes {
+					buffer.evict(budget);
+				}
+
+				#[cfg(feature = "tokio")]
+				announce(&self.updates, sequence);
+
+				observed
+			}
+		);
+
+		self.logged.notify_all();
+
+		if let Some(entry) = observed {
+			self.config.observe(&entry);
+		}
+	}
+}
+
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		#[cfg(feature = "target")]
+		{
+			if !self.config.target.is_match(metadata.target()) {
+				return false;
+			}
+		}
+
+		metadata.level() as usize <= self.level.load(Ordering::Relaxed)
+	}
+
+
+	fn log(&self, record: &Record) {
+		if self.enabled(record.metadata()) {
+			self.capture(record);
+		}
+	}
+
+
+	fn flush(&self) { }
+}
+
+
+/// A reference to the buffered data.
+/// Note that this locks the logger, causing logging to block.
+///
+/// This type implements `Deref` for `str`, allowing access to the contents. Entries are
+/// stored separately, so their lines are concatenated when the guard is created.
+///
+/// Helpers query the lines of the entries, such as in tests. They are named apart from the
+/// `str` methods, which query the concatenated contents:
+///
+/// ```
+/// # use memory_logger::blocking::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// log::warn!(target: "mycrate", "Connection refused, retrying.");
+/// log::warn!(target: "mycrate", "Connection refused, retrying.");
+/// log::info!(target: "mycrate", "Connected:\nport 8080");
+///
+/// let contents = logger.read();
+///
+/// assert!(contents.contains_line("retrying"));
+/// assert_eq!(contents.count_matches("Connection refused"), 2);
+/// assert_eq!(contents.entry_lines().last(), Some("[mycrate] INFO  | Connected:\nport 8080"));
+/// assert_eq!(contents.lines().last(), Some("port 8080"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BufferLockGuard<'a> {
+	/// Held so that logging blocks while the contents are referenced.
+	buffer: MutexGuard<'a, Buffer>,
+	text: String,
+}
+
+
+impl<'a> BufferLockGuard<'a> {
+	/// The lines of the entries, oldest first, without the trailing newline. Unlike
+	/// `str::lines`, multi-line messages are not split.
+	pub fn entry_lines(&self) -> impl Iterator<Item = &str> + '_ {
+		self.buffer
+			.entries()
+			.map(|(_, line)| &line[.. line.len() - 1])
+	}
+
+
+	/// Whether any entry's line contains the given pattern.
+	pub fn contains_line(&self, pattern: &str) -> bool {
+		self.entry_lines()
+			.any(|line| line.contains(pattern))
+	}
+
+
+	/// The number of entries whose line contains the given pattern.
+	pub fn count_matches(&self, pattern: &str) -> usize {
+		self.entry_lines()
+			.filter(|line| line.contains(pattern))
+			.count()
+	}
+}
+
+
+impl<'a> Deref for BufferLockGuard<'a> {
+	type Target = str;
+
+	fn deref(&self) -> &Self::Target {
+		&self.text
+	}
+}
+
+
+/// A blocking memory logger. Logging and read operations may block.
+///
+/// You should have only a single instance of this in your program.
+#[derive(Debug)]
+pub struct MemoryLogger(Logger);
+
+
+impl MemoryLogger {
+	/// Initializes the global logger with a new MemoryLogger instance.
+	/// This function should only be called once.
+	///
+	/// The `target` parameter is only available with the `target` feature.
+	/// Only log records that match such target are enabled.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let target = Regex::new("^mycrate::my_module")?; // optional
+	/// let logger = MemoryLogger::setup(log::Level::Info, target)?;
+	///
+	/// log::info!("This is a info.");
+	/// log::warn!("This is a warning.");
+	/// # // Dirty hack to fix the record target defined by cargo test:
+	/// # log::info!(target: "mycrate::my_module", "This is a info.");
+	/// # log::warn!(target: "mycrate::my_module", "This is a warning.");
+	///
+	/// let contents = logger.read();
+	///
+	/// assert!(contents.contains("This is a info."));
+	/// assert!(contents.contains("This is a warning."));
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance.
+	pub fn setup(
+		level: Level,
+		#[cfg(feature = "target")]
+		target: Regex,
+	) -> Result<&'static Self, SetLoggerError> {
+		Self::setup_with(
+			Config::new(
+				level,
+				#[cfg(feature = "target")]
+				target,
+			)
+		)
+	}
+
+
+	/// Initializes the global logger with a new MemoryLogger instance, using the given
+	/// configuration. This function should only be called once.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config, Format, Syslog};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let target = Regex::new("^mycrate::my_module")?; // optional
+	/// let config = Config::new(log::Level::Info, target)
+	///     .format(Format::Syslog(Syslog::new("myapp")));
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::warn!(target: "mycrate::my_module", "This is a warning.");
+	///
+	/// let contents = logger.read();
+	///
+	/// assert!(contents.starts_with("<12>1 "));
+	/// assert!(contents.contains(" - myapp "));
+	/// assert!(contents.contains("[mycrate::my_module] This is a warning."));
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Callbacks may be set to follow entries as they are captured:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # use std::sync::{Arc, Mutex};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let panel = Arc::new(Mutex::new(Vec::new()));
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .observer({
+	///         let panel = panel.clone();
+	///         move |entry| panel.lock().unwrap().push(entry.message().to_owned())
+	///     });
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::warn!(target: "mycrate", "This is a warning.");
+	///
+	/// assert_eq!(*panel.lock().unwrap(), ["This is a warning."]);
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Including for errors only, to react as soon as they are captured:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # use std::{sync::atomic::{AtomicUsize, Ordering}, time::Duration};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// static ALERTS: AtomicUsize = AtomicUsize::new(0);
+	///
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .on_error(|_| { ALERTS.fetch_add(1, Ordering::Relaxed); })
+	///     .debounce_errors(Duration::from_secs(60));
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::warn!(target: "mycrate", "Retrying.");
+	/// log::error!(target: "mycrate", "Connection lost.");
+	/// log::error!(target: "mycrate", "Connection lost.");
+	///
+	/// assert_eq!(ALERTS.load(Ordering::Relaxed), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Verbose entries may be sampled, keeping their share of the buffer in check:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Trace, Regex::new("^mycrate")?)
+	///     .sample(log::Level::Trace, 10);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// for i in 0 .. 100 {
+	///     log::trace!(target: "mycrate", "Polling {}.", i);
+	/// }
+	/// log::warn!(target: "mycrate", "Slow poll.");
+	///
+	/// assert_eq!(logger.stats().len(), 11);
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Sensitive data may be redacted before it is buffered, with the `redact` feature:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # #[cfg(feature = "redact")] {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .redact(Regex::new("password=\\S+")?, "password=***")
+	///     .redact(Regex::new("[\\w.]+@[\\w.]+")?, "<email>");
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "Login for jane@example.com, password=hunter2.");
+	///
+	/// assert_eq!(&*logger.read(), "[mycrate] INFO  | Login for <email>, password=***\n");
+	/// # }
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Or rewritten by an arbitrary transform:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .transform(|message| message.to_lowercase());
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "CONNECTED.");
+	///
+	/// assert_eq!(&*logger.read(), "[mycrate] INFO  | connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Or truncated, to bound the memory used by each entry:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).max_message_len(8);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "Response: {}", "x".repeat(1 << 20));
+	///
+	/// assert_eq!(&*logger.read(), "[mycrate] INFO  | Response\u{2026} (1048586 bytes)\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// The whole buffer may be bounded too, evicting the oldest entries:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).max_bytes(1024);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// for i in 0 .. 100 {
+	///     log::info!(target: "mycrate", "Entry {}.", i);
+	/// }
+	///
+	/// let contents = logger.read();
+	/// assert!(contents.len() <= 1024);
+	/// assert!(contents.ends_with("[mycrate] INFO  | Entry 99.\n"));
+	/// assert!(!contents.contains("Entry 0."));
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// A flight recorder keeps verbose entries out of the buffer, writing the newest ones
+	/// to a sink only when an error occurs:
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # use std::fs::{self, File};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let path = std::env::temp_dir().join("memory_logger_flight_recorder.log");
+	/// let config = Config::new(log::Level::Debug, Regex::new("^mycrate")?)
+	///     .flight_recorder(2, File::create(&path)?);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// for i in 0 .. 3 {
+	///     log::debug!(target: "mycrate", "Step {}.", i);
+	/// }
+	/// log::error!(target: "mycrate", "Failed.");
+	///
+	/// assert_eq!(&*logger.read(), "[mycrate] ERROR | Failed.\n");
+	/// assert_eq!(
+	///     fs::read_to_string(&path)?,
+	///     "[mycrate] DEBUG | Step 1.\n\
+	///      [mycrate] DEBUG | Step 2.\n\
+	///      [mycrate] ERROR | Failed.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance.
+	pub fn setup_with(config: Config) -> Result<&'static Self, SetLoggerError> {
+		Self::install(config, Vec::new())
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, also installing a global `tracing`
+	/// subscriber that forwards events through a [`MemoryLayer`](../struct.MemoryLayer.html),
+	/// so that events emitted through `tracing`, such as by dependencies, are buffered too.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?);
+	/// let logger = MemoryLogger::setup_with_tracing_bridge(config)?;
+	///
+	/// log::info!(target: "mycrate", "Through log.");
+	/// tracing::info!(target: "mycrate", "Through tracing.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump(&mut dump)?;
+	///
+	/// assert_eq!(
+	///     String::from_utf8(dump)?,
+	///     "[mycrate] INFO  | Through log.\n\
+	///      [mycrate] INFO  | Through tracing.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance. If the subscriber cannot be installed,
+	/// the logger remains installed.
+	#[cfg(feature = "tracing")]
+	pub fn setup_with_tracing_bridge(config: Config) -> Result<&'static Self, BridgeError> {
+		let logger = Self::setup_with(config)?;
+
+		layer::install_subscriber()?;
+
+		Ok(logger)
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, buffering the entries of the snapshot at
+	/// the given path, as written by `persist`. Restored entries are renumbered from zero,
+	/// and new entries are numbered after them. If the snapshot is missing or unreadable,
+	/// the logger starts empty.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let path = std::env::temp_dir().join("memory_logger_blocking_restore.snapshot");
+	/// # let _ = std::fs::remove_file(&path);
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?);
+	/// let logger = MemoryLogger::setup_with_restore(config, &path)?;
+	///
+	/// log::info!(target: "mycrate", "Started.");
+	///
+	/// // Restored by the next run.
+	/// logger.persist(&path)?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Returns the installed MemoryLogger instance.
+	pub fn setup_with_restore<P>(config: Config, path: P) -> Result<&'static Self, SetLoggerError>
+	where
+		P: AsRef<Path>
+	{
+		let restored = persist::read(path.as_ref()).unwrap_or_default();
+
+		Self::install(config, restored)
+	}
+
+
+	fn install(config: Config, restored: Vec<LogEntry>) -> Result<&'static Self, SetLoggerError> {
+		let level = config.level;
+
+		let logger = Self::build(config, restored);
+
+		log::set_logger(&logger.0)?;
+
+		log::set_max_level(
+			level.to_level_filter()
+		);
+
+		Ok(logger)
+	}
+
+
+	/// Create a new logger, without installing it.
+	fn build(config: Config, restored: Vec<LogEntry>) -> &'static Self {
+		let level = config.level;
+
+		let mut buffer = Buffer::default();
+
+		for mut entry in restored {
+			entry.sequence = buffer.next_sequence();
+			buffer.push(&entry);
+		}
+
+		if let Some(budget) = config.max_bytes {
+			buffer.evict(budget);
+		}
+
+		Box::leak(
+			Box::new(
+				Self(
+					Logger {
+						level: AtomicUsize::new(level as usize),
+
+						counters: config.metrics.then(Counters::default),
+
+						retention: Retention::new(&config),
+
+						shards: (config.shards > 0).then(|| Shards::new(config.shards, buffer.sequence)),
+
+						config,
+
+						buffer: Mutex::new(buffer),
+
+						logged: Condvar::new(),
+
+						#[cfg(feature = "tokio")]
+						updates: watch::Sender::new(0),
+					}
+				)
+			)
+		)
+	}
+
+
+	/// Setup a new MemoryLogger per route of the given routing, partitioning entries into
+	/// them by target, and install a logger routing records to them. See
+	/// [`Routing`](../struct.Routing.html).
+	///
+	/// Returns the new MemoryLogger instances, by route name.
+	#[cfg(feature = "target")]
+	pub fn setup_routed(routing: Routing) -> Result<Routes<Self>, SetLoggerError> {
+		routing.install(Self::setup_composable)
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, without installing it as the global
+	/// logger, along with a `log::Log` implementation buffering records in it, which may be
+	/// plugged into dispatchers such as fern's. See [`MemoryLog`](../struct.MemoryLog.html).
+	///
+	/// Returns the new MemoryLogger instance and its log.
+	pub fn setup_composable(config: Config) -> (&'static Self, MemoryLog) {
+		let logger = Self::build(config, Vec::new());
+
+		(logger, MemoryLog::new(&logger.0))
+	}
+
+
+	/// Setup a new MemoryLogger like `setup_with`, without installing it as the global
+	/// logger, along with a log4rs appender buffering records in it. See
+	/// [`MemoryAppender`](../struct.MemoryAppender.html).
+	///
+	/// Returns the new MemoryLogger instance and its appender.
+	#[cfg(feature = "log4rs")]
+	pub fn setup_appender(config: Config) -> (&'static Self, MemoryAppender) {
+		let logger = Self::build(config, Vec::new());
+
+		(logger, MemoryAppender::new(&logger.0))
+	}
+
+
+	/// The current level. Entries more verbose than this level are discarded.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::debug!(target: "mycrate", "Ignored.");
+	/// logger.set_level(log::Level::Debug);
+	/// log::debug!(target: "mycrate", "Captured.");
+	///
+	/// assert_eq!(logger.level(), log::Level::Debug);
+	/// assert_eq!(logger.len(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn level(&self) -> Level {
+		let level = self.0.level.load(Ordering::Relaxed);
+
+		Level::iter()
+			.find(|candidate| *candidate as usize == level)
+			.expect("level should be valid")
+	}
+
+
+	/// Change the level, such as to capture debug entries while investigating an issue.
+	/// This also sets the `log` crate's maximum level.
+	pub fn set_level(&self, level: Level) {
+		self.0.level.store(level as usize, Ordering::Relaxed);
+
+		log::set_max_level(
+			level.to_level_filter()
+		);
+	}
+
+
+	/// The configuration this logger was set up with.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// assert_eq!(logger.config().level(), log::Level::Info);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn config(&self) -> &Config {
+		&self.0.config
+	}
+
+
+	/// A snapshot of the counts of captured entries, by level and target. Counts are only
+	/// kept if enabled through [`Config::metrics`](../struct.Config.html#method.metrics),
+	/// otherwise they are all zero.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?).metrics(true);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::error!(target: "mycrate::db", "Connection lost.");
+	/// log::info!(target: "mycrate::db", "Reconnected.");
+	///
+	/// let metrics = logger.metrics();
+	///
+	/// assert_eq!(metrics.levels().get(log::Level::Error), 1);
+	/// assert_eq!(metrics.targets()["mycrate::db"].total(), 2);
+	/// assert!(
+	///     metrics
+	///         .to_prometheus()
+	///         .contains("memory_logger_entries_total{level=\"error\",target=\"mycrate::db\"} 1\n")
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn metrics(&self) -> Metrics {
+		self.0.counters
+			.as_ref()
+			.map(Counters::snapshot)
+			.unwrap_or_default()
+	}
+
+
+	/// Statistics of the buffered entries: counts by level and target, and the timestamps
+	/// of the oldest and newest entries. The statistics are kept up to date as entries
+	/// are logged, so this only locks the logger briefly.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::warn!(target: "mycrate::db", "Slow query.");
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let stats = logger.stats();
+	///
+	/// assert_eq!(stats.len(), 2);
+	/// assert_eq!(stats.levels().get(log::Level::Warn), 1);
+	/// assert_eq!(stats.targets()["mycrate::net"].get(log::Level::Info), 1);
+	/// assert!(stats.first() <= stats.last());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn stats(&self) -> Stats {
+		self.0.lock().stats()
+	}
+
+
+	/// The memory used by the buffered entries: the length of the buffered text, including
+	/// line terminators, and the number of entries.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let usage = logger.memory_usage();
+	///
+	/// assert_eq!(usage.bytes(), "[mycrate] INFO  | Connected.\n".len());
+	/// assert_eq!(usage.entries(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn memory_usage(&self) -> MemoryUsage {
+		let buffer = self.0.lock();
+
+		MemoryUsage {
+			bytes: buffer.bytes,
+			entries: buffer.spans.len(),
+		}
+	}
+
+
+	/// The number of buffered entries.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// assert!(logger.is_empty());
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// assert_eq!(logger.len(), 1);
+	/// assert!(!logger.is_empty());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn len(&self) -> usize {
+		self.0.lock().spans.len()
+	}
+
+
+	/// Whether there are no buffered entries.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+
+	/// A compact report of the buffered entries: total entries, counts per level, the `top`
+	/// noisiest targets, and the first and last errors.
+	/// The buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate::net", "Connecting.");
+	/// log::error!(target: "mycrate::db", "Connection lost.");
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let summary = logger.summary(1);
+	///
+	/// assert_eq!(
+	///     summary.to_string(),
+	///     "3 entries: 1 error, 0 warn, 2 info, 0 debug, 0 trace\n\
+	///      noisiest targets: mycrate::net (2)\n\
+	///      first error: [mycrate::db] ERROR | Connection lost.\n\
+	///      last error: [mycrate::db] ERROR | Connection lost."
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn summary(&self, top: usize) -> Summary {
+		let buffer = self.0.lock();
+
+		let mut errors = buffer
+			.entries()
+			.filter(|(span, _)| span.level == Level::Error);
+
+		let first_error = errors
+			.next()
+			.map(|(span, line)| span.to_entry(line));
+
+		let last_error = errors
+			.last()
+			.map(|(span, line)| span.to_entry(line))
+			.or_else(|| first_error.clone());
+
+		Summary::new(&buffer.tally.snapshot(), first_error, last_error, top)
+	}
+
+
+	/// Dump the contents to a writer, cleaning the buffered contents only if writing
+	/// succeeds. The buffer is only locked to take the entries, so logging threads are not
+	/// blocked while writing.
+	pub fn dump<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		self.config().write_header(&mut writer)?;
+
+		self.dump_with(|_, line| writer.write_all(line.as_bytes()))
+	}
+
+
+	/// Take the buffered entries and write them without holding the lock, so that logging
+	/// is not blocked by slow writers. If writing fails, the entries are put back at the
+	/// front of the buffer.
+	fn dump_with<F>(&self, mut write: F) -> io::Result<()>
+	where
+		F: FnMut(&Span, &str) -> io::Result<()>
+	{
+		let (spans, arena) = self.0.lock_flushed().take();
+
+		let result = spans
+			.iter()
+			.try_for_each(|span| write(span, arena.get(&span.line)));
+
+		if result.is_err() {
+			self.0.lock().restore(spans, arena);
+		}
+
+		result
+	}
+
+
+	/// Dump the contents to a writer like `dump`, rendering the entries in the given format
+	/// instead of the configured one.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config, Format};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .format(Format::Message);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_as(&mut dump, &Format::Text)?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_as<W>(&self, mut writer: W, format: &Format) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut rendered = String::new();
+
+		self.config().write_header(&mut writer)?;
+
+		self.dump_with(
+			|span, line| {
+				rendered.clear();
+				span.to_entry(line).write_as(format, &mut rendered);
+				rendered.push('\n');
+
+				writer.write_all(rendered.as_bytes())
+			}
+		)
+	}
+
+
+	/// Dump the contents to a writer in a versioned, machine-readable format, cleaning the
+	/// buffered contents only if writing succeeds. Such dumps can be read through
+	/// [`Schema::read`](../enum.Schema.html#method.read) by tooling built against other
+	/// versions of this crate. The process header, if any, is not written.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Schema};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_schema(&mut dump, Schema::Json)?;
+	///
+	/// assert!(dump.starts_with(b"{\"schema\":\"memory_logger\",\"version\":1}\n"));
+	///
+	/// let entries = Schema::read(&dump[..])?;
+	///
+	/// assert_eq!(entries[0].message(), "Connected.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_schema<W>(&self, mut writer: W, schema: Schema) -> io::Result<()>
+	where
+		W: Write
+	{
+		schema.write_header(&mut writer)?;
+
+		self.dump_with(|span, line| schema.write_entry(&mut writer, &span.to_entry(line)))
+	}
+
+
+	/// Dump the contents to a writer, keeping the buffered contents, so that they may be
+	/// dumped again later.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let mut first = Vec::new();
+	/// logger.dump_keep(&mut first)?;
+	///
+	/// let mut second = Vec::new();
+	/// logger.dump(&mut second)?;
+	///
+	/// assert_eq!(first, second);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_keep<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let text = self.0.lock_flushed().text();
+
+		self.config().write_header(&mut writer)?;
+
+		writer.write_all(
+			text.as_bytes()
+		)
+	}
+
+
+	/// Dump the contents to a writer, normalizing volatile fields, such as timestamps, to
+	/// placeholders, so that the dump can be committed as a snapshot. The buffered contents
+	/// are kept. The process header, if any, is not written.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Normalizer};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let buffer = [0u8; 16];
+	/// log::info!(target: "mycrate", "Allocated a buffer at {:p}.", &buffer);
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_normalized(&mut dump, &Normalizer::new())?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] INFO  | Allocated a buffer at [pointer].\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "snapshot")]
+	pub fn dump_normalized<W>(&self, mut writer: W, normalizer: &Normalizer) -> io::Result<()>
+	where
+		W: Write
+	{
+		let text = self.0.lock_flushed().text();
+
+		writer.write_all(
+			normalizer
+				.normalize(&text)
+				.as_bytes()
+		)
+	}
+
+
+	/// Dump the entries with the given level or above to a writer, removing them from the
+	/// buffered contents only if writing succeeds. Entries below the given level are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Debug, Regex::new("^mycrate")?)?;
+	/// log::debug!(target: "mycrate", "Polling.");
+	/// log::error!(target: "mycrate", "Connection lost.");
+	/// log::debug!(target: "mycrate", "Polling.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_filtered(&mut dump, log::Level::Warn)?;
+	///
+	/// assert_eq!(String::from_utf8(dump)?, "[mycrate] ERROR | Connection lost.\n");
+	/// assert_eq!(logger.len(), 2);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_filtered<W>(&self, mut writer: W, level: Level) -> io::Result<()>
+	where
+		W: Write
+	{
+		let buffer = &mut self.0.lock_flushed();
+
+		self.config().write_header(&mut writer)?;
+
+		for (span, line) in buffer.entries() {
+			if span.level <= level {
+				writer.write_all(line.as_bytes())?;
+			}
+		}
+
+		let start = buffer.discarded;
+		buffer.remove_since(start, |span| span.level <= level);
+
+		Ok(())
+	}
+
+
+	/// Dump the contents to several writers in a single pass, such as a file and stderr.
+	/// The buffered contents are cleaned only if writing to all of them succeeds.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let (mut file, mut stderr) = (Vec::new(), Vec::new());
+	/// logger.dump_all(&mut [&mut file, &mut stderr])?;
+	///
+	/// assert_eq!(String::from_utf8(file)?, "[mycrate] INFO  | Connected.\n");
+	/// assert_eq!(String::from_utf8(stderr)?, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_all(&self, writers: &mut [&mut dyn Write]) -> io::Result<()> {
+		for writer in writers.iter_mut() {
+			self.config().write_header(writer)?;
+		}
+
+		self.dump_with(
+			|_, line| writers
+				.iter_mut()
+				.try_for_each(|writer| writer.write_all(line.as_bytes()))
+		)
+	}
+
+
+	/// Dump the contents to the file at the given path like `dump`, appending to it. The file
+	/// is created if it does not exist.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::fs;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_blocking_dump.log");
+	/// # let _ = fs::remove_file(&path);
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// logger.dump_to_path(&path)?;
+	///
+	/// log::info!(target: "mycrate", "Disconnected.");
+	/// logger.dump_to_path(&path)?;
+	///
+	/// assert_eq!(
+	///     fs::read_to_string(&path)?,
+	///     "[mycrate] INFO  | Connected.\n\
+	///      [mycrate] INFO  | Disconnected.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_to_path<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		file::append(path.as_ref(), |writer| self.dump(writer))
+	}
+
+
+	/// Dump the contents to the file at the given path like `dump_to_path`, rotating it
+	/// first if it has reached `max_size` bytes. Rotated files are named by appending a
+	/// number to the path, from `.1` for the newest, and at most `keep` of them are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::fs;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_blocking_rotation.log");
+	/// # let _ = fs::remove_file(&path);
+	///
+	/// for i in 0 .. 3 {
+	///     log::info!(target: "mycrate", "Dump {}.", i);
+	///     logger.dump_to_rotating_path(&path, 1, 1)?;
+	/// }
+	///
+	/// assert_eq!(fs::read_to_string(&path)?, "[mycrate] INFO  | Dump 2.\n");
+	/// assert_eq!(fs::read_to_string(path.with_extension("log.1"))?, "[mycrate] INFO  | Dump 1.\n");
+	/// assert!(!path.with_extension("log.2").exists());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_to_rotating_path<P>(&self, path: P, max_size: u64, keep: usize) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		let path = path.as_ref();
+
+		file::rotate(path, max_size, keep)?;
+
+		self.dump_to_path(path)
+	}
+
+
+	/// Serve a request for the buffered entries, described by its query string, such as
+	/// `level=warn&since=42`. The buffered contents are kept. See the
+	/// [`http`](../http/index.html) module for the parameters and response format.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// log::warn!(target: "mycrate", "Retrying.");
+	///
+	/// let response = logger.serve_http("level=warn");
+	/// let body = std::str::from_utf8(response.body())?;
+	///
+	/// assert_eq!(response.status(), 200);
+	/// assert!(body.starts_with(r#"{"entries":[{"sequence":1,"#));
+	/// assert!(body.ends_with(r#""message":"Retrying.","line":"[mycrate] WARN  | Retrying."}],"next":2}"#));
+	/// assert_eq!(logger.serve_http("level=loud").status(), 400);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "http")]
+	pub fn serve_http(&self, query: &str) -> http::Response {
+		let entries: Vec<LogEntry> = self.0
+			.lock()
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		http::serve(query, &entries)
+	}
+
+
+	/// Listen on the given address for plain TCP clients, such as `nc`, sending each of them
+	/// the buffered entries followed by a live tail of new entries. The buffered contents
+	/// are kept. Each client is served by its own thread, and is disconnected if writing to
+	/// it blocks for 10 seconds. Entries evicted before a slow client reads them are skipped.
+	///
+	/// Returns the bound address, which is useful when binding to port `0`.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::io::{BufRead, BufReader};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Started.");
+	///
+	/// let address = logger.serve_tcp("127.0.0.1:0")?;
+	/// let mut client = BufReader::new(std::net::TcpStream::connect(address)?);
+	/// let mut line = String::new();
+	///
+	/// client.read_line(&mut line)?;
+	/// assert_eq!(line, "[mycrate] INFO  | Started.\n");
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// line.clear();
+	/// client.read_line(&mut line)?;
+	/// assert_eq!(line, "[mycrate] INFO  | Connected.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn serve_tcp<A>(&'static self, address: A) -> io::Result<SocketAddr>
+	where
+		A: ToSocketAddrs
+	{
+		tail::serve_tcp(address, move |stream| self.follow(stream))
+	}
+
+
+	/// Write the buffered entries to the writer, followed by new entries as they are logged,
+	/// until writing fails.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn follow<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut buffer = self.0.lock();
+		let mut position = buffer.discarded;
+
+		loop {
+			let text = buffer.text_since(position);
+
+			position = buffer.end();
+			drop(buffer);
+
+			writer.write_all(text.as_bytes())?;
+
+			buffer = self.0.wait_past(position);
+		}
+	}
+
+
+	/// Listen on the given Unix domain socket path for control commands, such as `dump`,
+	/// `clear` and `set-level debug`. Each client is served by its own thread. See the
+	/// [`control`](../control/index.html) module for the protocol.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::{io::{BufRead, BufReader, Write}, os::unix::net::UnixStream};
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_blocking.sock");
+	///
+	/// logger.serve_uds(&path)?;
+	///
+	/// let mut client = UnixStream::connect(&path)?;
+	/// client.write_all(b"set-level debug\n")?;
+	///
+	/// let mut response = String::new();
+	/// BufReader::new(client).read_line(&mut response)?;
+	///
+	/// assert_eq!(response, "ok\n");
+	/// assert_eq!(logger.level(), log::Level::Debug);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "control", unix))]
+	pub fn serve_uds<P>(&'static self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		control::serve_uds(path.as_ref(), self)
+	}
+
+
+	/// Write a snapshot of the buffered entries to the given path, to be restored by
+	/// `setup_with_restore`. The buffered contents are kept.
+	///
+	/// Snapshots keep the line, level, target and timestamp of entries, but not their
+	/// location, thread or key-value pairs. Key-value pairs rendered as JSON are restored as
+	/// part of the message.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let path = std::env::temp_dir().join("memory_logger_blocking_persist.snapshot");
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// logger.persist(&path)?;
+	///
+	/// assert_eq!(logger.len(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn persist<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>
+	{
+		let entries: Vec<LogEntry> = self.0
+			.lock()
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		persist::write(path.as_ref(), &entries)
+	}
+
+
+	/// Spawn a background worker exporting the buffered entries to the pipeline's sinks
+	/// at its interval, cleaning the buffered contents. The worker is stopped when the returned handle is dropped.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, LogEntry, Pipeline};
+	/// # use regex::Regex;
+	/// # use std::time::Duration;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let (tx, rx) = std::sync::mpsc::channel();
+	/// let pipeline = Pipeline::new(Duration::from_secs(1))
+	///     .sink(
+	///         move |entries: &[LogEntry]| {
+	///             for entry in entries {
+	///                 tx.send(entry.message().to_owned()).unwrap();
+	///             }
+	///             Ok(())
+	///         }
+	///     );
+	/// let worker = logger.export(pipeline)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// worker.stop();
+	///
+	/// assert_eq!(rx.try_iter().collect::<Vec<_>>(), ["Connected."]);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn export(&'static self, pipeline: Pipeline) -> io::Result<Worker> {
+		pipeline.spawn(move || self.drain())
+	}
+
+
+	/// Dump the contents to a writer like `dump`, but printing the target only once for
+	/// consecutive entries that share it. Repeated targets are replaced by a ditto mark.
+	/// This only affects the default format. Other formats are dumped as-is.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// log::info!(target: "mycrate::net", "Connecting.");
+	/// log::warn!(target: "mycrate::net", "Connection refused, retrying.");
+	/// log::info!(target: "mycrate::net", "Connected.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_elided(&mut dump)?;
+	///
+	/// assert_eq!(
+	///     String::from_utf8(dump)?,
+	///     "[mycrate::net] INFO  | Connecting.\n\
+	///      \" WARN  | Connection refused, retrying.\n\
+	///      \" INFO  | Connected.\n"
+	/// );
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn dump_elided<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut previous = None;
+
+		self.dump_with(
+			|span, line| {
+				match self.0.config.format.strip_target(&span.target, line) {
+					Some(rest) if previous.as_ref() == Some(&span.target) => {
+						writer.write_all(b"\"")?;
+						writer.write_all(rest.as_bytes())?;
+					}
+
+					_ => writer.write_all(line.as_bytes())?,
+				}
+
+				previous = Some(span.target.clone());
+
+				Ok(())
+			}
+		)
+	}
+
+
+	/// Dump the contents to the systemd journal, cleaning the buffered contents.
+	///
+	/// Each entry is sent with its message, and the `PRIORITY` and `CODE_MODULE` fields
+	/// set from its level and target. If sending fails, the entries sent so far are
+	/// removed, and the rest are kept.
+	///
+	/// ```no_run
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// log::info!("Shutting down.");
+	///
+	/// logger.dump_to_journal()?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "journald", unix))]
+	pub fn dump_to_journal(&self) -> io::Result<()> {
+		let mut journal = journald::Journal::connect()?;
+
+		let buffer = &mut self.0.lock_flushed();
+		let mut sent = 0;
+
+		let result = buffer
+			.entries()
+			.try_for_each(
+				|(span, line)| {
+					journal.send(span.level, &span.target, span.message(line))?;
+					sent += 1;
+					Ok(())
+				}
+			);
+
+		buffer.discard(sent);
+
+		result
+	}
+
+
+	/// Dump the contents to a writer as frames, cleaning the buffered contents. See the
+	/// [`framing`](../framing/index.html) module for the format.
+	#[cfg(feature = "framing")]
+	pub fn dump_framed<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut frame = Vec::new();
+
+		self.dump_with(
+			|span, line| {
+				frame.clear();
+
+				framing::encode_entry(
+					span.level,
+					&span.target,
+					span.message(line),
+					span.timestamp,
+					|bytes| frame.extend_from_slice(bytes)
+				);
+
+				writer.write_all(&frame)
+			}
+		)
+	}
+
+
+	/// Dump the contents to a writer encrypted with the given key, cleaning the buffered
+	/// contents. Each entry's line is sealed separately, so dumps may be appended to the same
+	/// file. See the [`crypto`](../crypto/index.html) module for the format.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, crypto::Key};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let key = Key::new([7; 32]);
+	///
+	/// log::info!(target: "mycrate", "User alice@example.com logged in.");
+	///
+	/// let mut dump = Vec::new();
+	/// logger.dump_encrypted(&mut dump, &key)?;
+	///
+	/// assert!(!dump.windows(5).any(|window| window == b"alice"));
+	/// assert_eq!(key.decrypt(&dump)?, "[mycrate] INFO  | User alice@example.com logged in.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "crypto")]
+	pub fn dump_encrypted<W>(&self, mut writer: W, key: &Key) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut frame = Vec::new();
+
+		self.dump_with(
+			|_, line| {
+				frame.clear();
+				key.seal(line.as_bytes(), &mut frame);
+				writer.write_all(&frame)
+			}
+		)
+	}
+
+
+	/// Dump the contents to the browser console, cleaning the buffered contents. Each entry
+	/// is written through the console function matching its level, such as `console.warn`
+	/// for warnings, so that devtools may filter them.
+	///
+	/// ```ignore
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// log::info!("Rendered.");
+	///
+	/// logger.dump_to_console();
+	/// ```
+	#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+	pub fn dump_to_console(&self) {
+		let buffer = &mut self.0.lock_flushed();
+
+		for (span, line) in buffer.entries() {
+			console::write(span.level, line.trim_end_matches('\n'));
+		}
+
+		buffer.clear();
+	}
+
+
+	/// Add the most recent entries as breadcrumbs to the current Sentry hub, so that error
+	/// reports include the log context leading up to the failure.
+	///
+	/// At most `limit` entries are added, oldest first.
+	/// The buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+	///
+	/// log::warn!("Retrying.");
+	///
+	/// logger.add_breadcrumbs(100);
+	/// sentry_core::capture_message("Failed to connect", sentry_core::Level::Error);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "sentry")]
+	pub fn add_breadcrumbs(&self, limit: usize) {
+		let buffer = self.0.lock();
+		let skip = buffer.spans.len().saturating_sub(limit);
+
+		for (span, line) in buffer.entries().skip(skip) {
+			sentry_core::add_breadcrumb(
+				sentry::breadcrumb(
+					span.level,
+					&span.target,
+					span.message(line),
+					span.timestamp
+				)
+			);
+		}
+	}
+
+
+	/// Takes the buffered entries, cleaning the buffered contents.
+	pub fn drain(&self) -> Vec<LogEntry> {
+		let buffer = &mut self.0.lock_flushed();
+
+		let entries = buffer
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		buffer.clear();
+
+		entries
+	}
+
+
+	/// Gets a reference to the buffered data.
+	/// Note that this locks the logger, causing logging to block.
+	pub fn read(&self) -> BufferLockGuard<'_> {
+		let buffer = self.0.lock_flushed();
+
+		BufferLockGuard {
+			text: buffer.text(),
+			buffer,
+		}
+	}
+
+
+	/// Takes the buffered data, cleaning the buffered contents. The entries are swapped
+	/// with an empty list of the same capacity, so the logger is only locked briefly.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// assert_eq!(logger.take(), "[mycrate] INFO  | Connected.\n");
+	/// assert!(logger.is_empty());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn take(&self) -> String {
+		let (spans, arena) = self.0.lock_flushed().take();
+
+		spans
+			.iter()
+			.map(|span| arena.get(&span.line))
+			.collect()
+	}
+
+
+	/// Gets a copy of the buffered data. Unlike `read`, the logger is only locked while
+	/// copying, and the buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	/// let snapshot = logger.snapshot();
+	/// log::info!(target: "mycrate", "Disconnected.");
+	///
+	/// assert_eq!(snapshot, "[mycrate] INFO  | Connected.\n");
+	/// assert_eq!(logger.len(), 2);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn snapshot(&self) -> String {
+		self.0.lock().text()
+	}
+
+
+	/// Gets the newest `n` buffered entries, oldest first. The buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// for i in 0 .. 10 {
+	///     log::info!(target: "mycrate", "Step {}.", i);
+	/// }
+	///
+	/// let tail = logger.tail(2);
+	///
+	/// assert_eq!(tail.len(), 2);
+	/// assert_eq!(tail[0].message(), "Step 8.");
+	/// assert_eq!(tail[1].message(), "Step 9.");
+	/// assert_eq!(tail[1].sequence(), 9);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn tail(&self, n: usize) -> Vec<LogEntry> {
+		let buffer = self.0.lock();
+		let skip = buffer.spans.len().saturating_sub(n);
+
+		buffer
+			.entries()
+			.skip(skip)
+			.map(|(span, line)| span.to_entry(line))
+			.collect()
+	}
+
+
+	/// Set a mark at the current end of the buffer, so that the entries logged after this
+	/// point may be read with `read_since_mark`. Setting an existing mark moves it.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Starting.");
+	///
+	/// logger.mark("phase-1");
+	/// log::info!(target: "mycrate", "Loading.");
+	/// logger.mark("phase-2");
+	/// log::info!(target: "mycrate", "Running.");
+	///
+	/// let phase_1 = logger.read_since_mark("phase-1").unwrap();
+	///
+	/// assert_eq!(phase_1.len(), 2);
+	/// assert_eq!(phase_1[0].message(), "Loading.");
+	/// assert_eq!(logger.read_since_mark("phase-2").unwrap().len(), 1);
+	/// assert!(logger.read_since_mark("phase-3").is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn mark(&self, name: &str) {
+		let buffer = &mut *self.0.lock();
+		let end = buffer.end();
+
+		buffer.marks.insert(name.into(), end);
+	}
+
+
+	/// Gets the buffered entries logged since the given mark was set, or `None` if there
+	/// is no such mark. Entries cleared since then are skipped. The buffered contents are
+	/// kept.
+	pub fn read_since_mark(&self, name: &str) -> Option<Vec<LogEntry>> {
+		let buffer = self.0.lock();
+		let &index = buffer.marks.get(name)?;
+
+		Some(
+			buffer
+				.entries_since(index)
+				.map(|(span, line)| span.to_entry(line))
+				.collect()
+		)
+	}
+
+
+	/// Gets a receiver announcing how many entries have been logged, so that async
+	/// consumers may await new entries instead of polling the buffer, such as by pairing
+	/// it with a [`cursor`](#method.cursor).
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let mut updates = logger.updates();
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	///
+	/// // In an async context, `updates.changed().await` waits for this.
+	/// assert!(updates.has_changed()?);
+	/// assert_eq!(*updates.borrow_and_update(), 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(feature = "tokio")]
+	pub fn updates(&self) -> watch::Receiver<u64> {
+		self.0.updates.subscribe()
+	}
+
+
+	/// Gets a copy of the buffered entries, along with the version of the buffer they
+	/// reflect. The buffered contents are kept. See [`View`](../struct.View.html).
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connected.");
+	///
+	/// let view = logger.view();
+	/// assert_eq!(view.get(0).unwrap().message(), "Connected.");
+	///
+	/// // Every frame:
+	/// if logger.version() != view.version() {
+	///     unreachable!("nothing was logged");
+	/// }
+	///
+	/// log::info!(target: "mycrate", "Disconnected.");
+	/// assert_ne!(logger.version(), view.version());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn view(&self) -> View {
+		let buffer = self.0.lock();
+
+		let entries = buffer
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		View::new(entries, buffer.version)
+	}
+
+
+	/// The version of the buffer, which changes whenever entries are buffered or removed.
+	/// This is cheap, so that user interfaces may poll it every frame, only taking a new
+	/// [`view`](#method.view) once it changes.
+	pub fn version(&self) -> u64 {
+		self.0.lock().version
+	}
+
+
+	/// Gets a cursor over the buffer, which remembers how far it has read. Several
+	/// components may each read the entries logged since they last looked, without clearing
+	/// the buffered contents for everyone else.
+	///
+	/// The cursor starts at the oldest buffered entry.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// let mut panel = logger.cursor();
+	/// let mut dumper = logger.cursor();
+	///
+	/// log::info!(target: "mycrate", "First.");
+	///
+	/// assert_eq!(panel.read().len(), 1);
+	///
+	/// log::info!(target: "mycrate", "Second.");
+	///
+	/// assert_eq!(panel.read()[0].message(), "Second.");
+	/// assert_eq!(dumper.read().len(), 2);
+	/// assert!(panel.read().is_empty());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn cursor(&self) -> Cursor<'_> {
+		Cursor {
+			logger: self,
+			position: self.0.lock().discarded,
+		}
+	}
+
+
+	/// Gets the buffered entries at or above the given level, such as only warnings and
+	/// errors. The buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "This is a info.");
+	/// log::warn!(target: "mycrate", "This is a warning.");
+	///
+	/// let mut warnings = logger.read_at_least(log::Level::Warn);
+	///
+	/// assert!(warnings.next().unwrap().contains("This is a warning."));
+	/// assert!(warnings.next().is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn read_at_least(&self, level: Level) -> impl Iterator<Item = LogEntry> {
+		let entries: Vec<_> = self.0
+			.lock()
+			.entries()
+			.filter(|(span, _)| span.level <= level)
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		entries.into_iter()
+	}
+
+
+	/// Gets the buffered entries logged from the calling thread. The buffered contents are
+	/// kept. As `cargo test` runs tests in parallel threads, this keeps assertions on logs
+	/// deterministic.
+	///
+	/// Entries are only bucketed by thread if thread capture is enabled through
+	/// [`Config::thread`](../struct.Config.html#method.thread). Otherwise, no entries are
+	/// returned.
+	///
+	/// ```
+	/// # use memory_logger::{blocking::MemoryLogger, Config};
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+	///     .thread(true);
+	/// let logger = MemoryLogger::setup_with(config)?;
+	///
+	/// std::thread::spawn(|| log::info!(target: "mycrate", "From another test."))
+	///     .join()
+	///     .unwrap();
+	/// log::info!(target: "mycrate", "From this test.");
+	///
+	/// let entries = logger.read_current_thread();
+	///
+	/// assert_eq!(entries.len(), 1);
+	/// assert_eq!(entries[0].message(), "From this test.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn read_current_thread(&self) -> Vec<LogEntry> {
+		let current = std::thread::current().id();
 
-#[derive(Debug)]
-struct Logger {
-	level: Level,
+		self.0
+			.lock()
+			.entries()
+			.filter(
+				|(span, _)| span.details
+					.as_ref()
+					.and_then(|details| details.thread.as_ref())
+					.is_some_and(|thread| thread.id == current)
+			)
+			.map(|(span, line)| span.to_entry(line))
+			.collect()
+	}
 
-	#[cfg(feature = "target")]
-	target: Regex,
 
-	buffer: Mutex<String>,
-}
+	/// Query the buffered entries by level range, target, message and time range. The
+	/// buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::warn!(target: "mycrate::db", "Slow query: 120ms.");
+	/// log::warn!(target: "mycrate::net", "Slow handshake: 300ms.");
+	/// log::info!(target: "mycrate::db", "Slow start.");
+	///
+	/// let entries = logger
+	///     .query()
+	///     .levels(log::Level::Error ..= log::Level::Warn)
+	///     .target(Regex::new("::db$")?)
+	///     .contains("Slow")
+	///     .run();
+	///
+	/// assert_eq!(entries.len(), 1);
+	/// assert_eq!(entries[0].message(), "Slow query: 120ms.");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn query(&self) -> Query<'_> {
+		Query::new(self)
+	}
 
 
-impl Logger {
-	fn lock(&self) -> MutexGuard<String> {
-		self.buffer
-			.lock()
-			.expect("inner lock poisoned")
+	/// Search the buffered entries' lines for a pattern, returning the matching entries with
+	/// their indices and the positions of the matches, so that a debugging UI can jump
+	/// between hits. An empty pattern matches nothing. The buffered contents are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// log::info!(target: "mycrate", "Connecting.");
+	/// log::warn!(target: "mycrate", "Timeout, timeout.");
+	///
+	/// let hits = logger.search("imeout");
+	///
+	/// assert_eq!(hits.len(), 1);
+	/// assert_eq!(hits[0].index(), 1);
+	/// assert_eq!(hits[0].matches(), [19 .. 25, 28 .. 34]);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn search(&self, pattern: &str) -> Vec<Hit> {
+		let buffer = self.0.lock();
+
+		buffer
+			.entries()
+			.enumerate()
+			.filter(|(_, (_, line))| line.contains(pattern))
+			.filter_map(
+				|(position, (span, line))| Hit::search(
+					buffer.discarded + position,
+					span.to_entry(line),
+					pattern
+				)
+			)
+			.collect()
 	}
-}
 
 
-impl Log for Logger {
-	fn enabled(&self, metadata: &Metadata) -> bool {
-		#[cfg(feature = "target")]
-		{
-			if !self.target.is_match(metadata.target()) {
-				return false;
+	/// Wait until an entry matching the pattern is buffered, or the timeout expires. The
+	/// buffered entries are searched first, so entries logged before the call are found
+	/// too. The buffered contents are kept.
+	///
+	/// This is meant for tests that assert on entries logged by background threads.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # use std::time::Duration;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	///
+	/// std::thread::spawn(|| log::info!(target: "mycrate", "Listening on port 8080."));
+	///
+	/// let entry = logger.wait_for(&Regex::new("port \\d+")?, Duration::from_secs(5));
+	///
+	/// assert_eq!(entry.unwrap().message(), "Listening on port 8080.");
+	/// assert!(logger.wait_for(&Regex::new("Stopped")?, Duration::from_millis(10)).is_none());
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(all(feature = "target", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+	pub fn wait_for(&self, pattern: &Regex, timeout: Duration) -> Option<LogEntry> {
+		let deadline = Instant::now() + timeout;
+
+		let mut buffer = self.0.lock();
+		let mut next = buffer.discarded;
+
+		loop {
+			let found = buffer
+				.entries_since(next)
+				.find(|(_, line)| pattern.is_match(line.trim_end_matches('\n')));
+
+			if let Some((span, line)) = found {
+				return Some(span.to_entry(line));
 			}
+
+			next = buffer.end();
+
+			let remaining = deadline.checked_duration_since(Instant::now())?;
+
+			buffer = self.0.wait_timeout(buffer, remaining);
 		}
+	}
+
+
+	/// Run a function, appending the entries captured while it runs to the panic message
+	/// if it panics. See the [`fail_with_logs`](../macro.fail_with_logs.html) macro.
+	#[track_caller]
+	pub fn fail_with_logs<F, T>(&self, f: F) -> T
+	where
+		F: FnOnce() -> T
+	{
+		let start = self.0.lock().end();
 
-		metadata.level() <= self.level
+		match panic::catch_unwind(AssertUnwindSafe(f)) {
+			Ok(value) => value,
+			Err(payload) => panic!(
+				"{}",
+				capture::report(
+					capture::panic_message(&*payload),
+					&self.0.lock().text_since(start)
+				)
+			),
+		}
 	}
 
 
-	fn log(&self, record: &Record) {
-		if self.enabled(record.metadata()) {
-			let target =
-				if record.target().is_empty() {
-					record
-						.module_path()
-						.unwrap_or("?")
-				} else {
-					record.target()
-				};
+	/// Assert that an entry with the given level, whose message contains the pattern, was
+	/// logged. See the [`assert_logged`](../macro.assert_logged.html) macro.
+	#[track_caller]
+	pub fn assert_logged(&self, level: Level, pattern: &str) {
+		let entries: Vec<_> = self.0
+			.lock()
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
 
-			let mut buffer = self.lock();
+		if let Err(report) = capture::check_logged(&entries, level, pattern) {
+			panic!("{}", report);
+		}
+	}
 
-			writeln!(
-				buffer,
-				"[{}] {:<5} | {}",
-				target,
-				record.level(),
-				record.args()
-			)
-				.expect("std::fmt::Write should never fail for String");
+
+	/// Assert that no errors were logged. See the
+	/// [`assert_no_errors`](../macro.assert_no_errors.html) macro.
+	#[track_caller]
+	pub fn assert_no_errors(&self) {
+		let entries: Vec<_> = self.0
+			.lock()
+			.entries()
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		if let Err(report) = capture::check_no_errors(&entries) {
+			panic!("{}", report);
 		}
 	}
 
 
-	fn flush(&self) { }
-}
+	/// Start expectations on a snapshot of the buffered entries, checked by
+	/// [`Expectation::verify`](../struct.Expectation.html#method.verify).
+	pub fn expect(&self) -> Expectation {
+		Expectation::new(
+			self.0
+				.lock()
+				.entries()
+				.map(|(span, line)| span.to_entry(line))
+				.collect()
+		)
+	}
 
 
-/// A reference to the buffered data.
-/// Note that this locks the logger, causing logging to block.
-///
-/// This type implements `Deref` for `str`, allowing access to the contents.
-#[derive(Debug)]
-pub struct BufferLockGuard<'a>(MutexGuard<'a, String>);
+	/// Run a fallible function, appending the entries captured while it runs to the error
+	/// message if it fails or panics. This is meant for custom test harnesses, such as
+	/// `libtest-mimic`, where tests report failures through a `Result`:
+	///
+	/// ```ignore
+	/// Trial::test("connects", move || Ok(logger.with_logs(|| connect())?))
+	/// ```
+	pub fn with_logs<F, T, E>(&self, f: F) -> Result<T, String>
+	where
+		F: FnOnce() -> Result<T, E>,
+		E: Display,
+	{
+		let start = self.0.lock().end();
 
+		let message = match panic::catch_unwind(AssertUnwindSafe(f)) {
+			Ok(Ok(value)) => return Ok(value),
+			Ok(Err(error)) => error.to_string(),
+			Err(payload) => capture::panic_message(&*payload).to_owned(),
+		};
 
-impl<'a> Deref for BufferLockGuard<'a> {
-	type Target = str;
+		Err(
+			capture::report(message, &self.0.lock().text_since(start))
+		)
+	}
 
-	fn deref(&self) -> &Self::Target {
-		self.0.as_ref()
+
+	/// Clears the log buffer.
+	/// Note that this locks the logger, causing logging to block.
+	pub fn clear(&self) {
+		self.0.lock().clear()
 	}
-}
 
 
-/// A blocking memory logger. Logging and read operations may block.
-///
-/// You should have only a single instance of this in your program.
-#[derive(Debug)]
-pub struct MemoryLogger(Logger);
+	/// Buffer a synthetic entry, such as a marker between requests, or an event from a
+	/// subsystem that doesn't log through the `log` facade. The entry is rendered and
+	/// handled like a logged record, but regardless of the level and target filters.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// logger.push(log::Level::Info, "mycrate", "==== request 1234 start ====");
+	/// log::info!(target: "mycrate", "Handling.");
+	///
+	/// let entry = memory_logger::LogEntry::parse("[modem] WARN  | Signal lost.").unwrap();
+	/// logger.push_entry(entry);
+	///
+	/// let entries = logger.drain();
+	///
+	/// assert_eq!(entries[0].message(), "==== request 1234 start ====");
+	/// assert_eq!(entries[2].as_str(), "[modem] WARN  | Signal lost.");
+	/// assert_eq!(entries[2].sequence(), 2);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn push<M>(&self, level: Level, target: &str, message: M)
+	where
+		M: Display
+	{
+		self.0.capture(
+			&Record::builder()
+				.level(level)
+				.target(target)
+				.args(format_args!("{}", message))
+				.build()
+		);
+	}
 
 
-impl MemoryLogger {
-	/// Initializes the global logger with a new MemoryLogger instance.
-	/// This function should only be called once.
+	/// Buffer an entry that has already been built, such as one parsed from a dump, as
+	/// with `push`. The entry's line is kept as is, and it gets the next sequence number.
+	pub fn push_entry(&self, mut entry: LogEntry) {
+		entry.sequence = self.0.next_sequence();
+
+		self.0.config.observe(&entry);
+		self.0.push(&entry);
+	}
+
+
+	/// Import the entries of a previously written dump, appending them to the buffer as
+	/// with `push_entry`, such as to aggregate the logs of multiple runs or processes.
+	/// Returns the number of imported entries.
 	///
-	/// The `target` parameter is only available with the `target` feature.
-	/// Only log records that match such target are enabled.
+	/// The dump may be a schema dump in either format, JSON Lines of entry objects, or text
+	/// lines in the default or syslog format. Text lines that can't be parsed are appended
+	/// to the preceding entry, as continuations of multi-line messages, or skipped if there
+	/// is none, such as the process header. Returns an error of kind `InvalidData` if a
+	/// JSON dump is malformed or of a newer version.
 	///
 	/// ```
 	/// # use memory_logger::blocking::MemoryLogger;
 	/// # use regex::Regex;
 	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-	/// let target = Regex::new("^mycrate::my_module")?; // optional
-	/// let logger = MemoryLogger::setup(log::Level::Info, target)?;
+	/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+	/// let dump = "header\n[worker] WARN  | Retrying:\n  connection refused\n[worker] INFO  | Done.\n";
 	///
-	/// log::info!("This is a info.");
-	/// log::warn!("This is a warning.");
-	/// # // Dirty hack to fix the record target defined by cargo test:
-	/// # log::info!(target: "mycrate::my_module", "This is a info.");
-	/// # log::warn!(target: "mycrate::my_module", "This is a warning.");
+	/// assert_eq!(logger.import(dump.as_bytes())?, 2);
 	///
-	/// let contents = logger.read();
+	/// let entries = logger.drain();
 	///
-	/// assert!(contents.contains("This is a info."));
-	/// assert!(contents.contains("This is a warning."));
+	/// assert_eq!(entries[0].message(), "Retrying:\n  connection refused");
+	/// assert_eq!(entries[1].target(), "worker");
 	/// # Ok(())
 	/// # }
 	/// ```
+	pub fn import<R>(&self, reader: R) -> io::Result<usize>
+	where
+		R: Read
+	{
+		let entries = Schema::import(reader)?;
+		let count = entries.len();
+
+		for entry in entries {
+			self.push_entry(entry);
+		}
+
+		Ok(count)
+	}
+
+
+	/// Gets an `io::Write` and `fmt::Write` adapter recording each written line as an entry
+	/// with the given level and target, such as to capture the output of a child process or
+	/// to stream a report built with `write!`. See [`LogWriter`](../struct.LogWriter.html).
+	pub fn as_writer(&self, level: Level, target: &str) -> LogWriter<'_> {
+		LogWriter::new(self, level, target)
+	}
+
+
+	/// Gets a guard of the entries logged during its lifetime, which are removed once it is
+	/// dropped, so that sequential tests are independent without explicit `clear` calls.
+	/// Entries logged before the guard are kept.
+	///
+	/// ```
+	/// # use memory_logger::blocking::MemoryLogger;
+	/// # use regex::Regex;
+	/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+	/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
 	///
-	/// Returns the installed MemoryLogger instance.
-	pub fn setup(
-		level: Level,
-		#[cfg(feature = "target")]
-		target: Regex,
-	) -> Result<&'static Self, SetLoggerError> {
-		let logger = Box::leak(
-			Box::new(
-				Self(
-					Logger {
-						level,
+	/// log::info!(target: "mycrate", "Starting.");
+	///
+	/// {
+	///     let guard = logger.capture_guard();
+	///     log::info!(target: "mycrate", "Testing.");
+	///
+	///     assert_eq!(guard.entries()[0].message(), "Testing.");
+	/// }
+	///
+	/// let guard = logger.capture_guard();
+	/// log::info!(target: "mycrate", "Testing again.");
+	///
+	/// assert_eq!(guard.take()[0].message(), "Testing again.");
+	/// assert_eq!(&*logger.read(), "[mycrate] INFO  | Starting.\n");
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn capture_guard(&self) -> CaptureGuard<'_> {
+		CaptureGuard {
+			logger: self,
+			start: self.0.lock().end(),
+			thread: None,
+		}
+	}
 
-						buffer: Mutex::new(String::new()),
 
-						#[cfg(feature = "target")]
-						target
-					}
+	/// Like [`capture_guard`](#method.capture_guard), but only guards the entries logged
+	/// from the current thread. Requires [`Config::thread`](../struct.Config.html#method.thread).
+	#[cfg(feature = "macros")]
+	pub(crate) fn capture_thread_guard(&self) -> CaptureGuard<'_> {
+		CaptureGuard {
+			thread: Some(std::thread::current().id()),
+			..self.capture_guard()
+		}
+	}
+}
+
+
+/// A reader that remembers how far it has read. See
+/// [`MemoryLogger::cursor`](struct.MemoryLogger.html#method.cursor).
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+	logger: &'a MemoryLogger,
+	/// The stable index of the next entry to read.
+	position: usize,
+}
+
+
+impl<'a> Cursor<'a> {
+	/// Gets the entries logged since the last read, advancing the cursor. Entries cleared
+	/// before being read are skipped.
+	pub fn read(&mut self) -> Vec<LogEntry> {
+		let buffer = self.logger.0.lock();
+
+		let entries = buffer
+			.entries_since(self.position)
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		self.position = buffer.end();
+
+		entries
+	}
+
+
+	/// The stable index of the next entry to read. Indices keep increasing across clears.
+	pub fn position(&self) -> usize {
+		self.position
+	}
+}
+
+
+/// A guard of the entries logged during its lifetime, which are removed once it is
+/// dropped. See [`MemoryLogger::capture_guard`](struct.MemoryLogger.html#method.capture_guard).
+#[derive(Debug)]
+pub struct CaptureGuard<'a> {
+	logger: &'a MemoryLogger,
+	/// The stable index of the first entry logged during the guard's lifetime.
+	start: usize,
+	/// The only thread whose entries are guarded, if any.
+	thread: Option<ThreadId>,
+}
+
+
+impl<'a> CaptureGuard<'a> {
+	/// Gets the entries logged so far during the guard's lifetime. The buffered contents
+	/// are kept.
+	pub fn entries(&self) -> Vec<LogEntry> {
+		self.logger.0
+			.lock()
+			.entries_since(self.start)
+			.filter(|(span, _)| self.guards(span))
+			.map(|(span, line)| span.to_entry(line))
+			.collect()
+	}
+
+
+	/// Remove and return the entries logged during the guard's lifetime.
+	pub fn take(self) -> Vec<LogEntry> {
+		let mut buffer = self.logger.0.lock();
+
+		let entries = buffer
+			.entries_since(self.start)
+			.filter(|(span, _)| self.guards(span))
+			.map(|(span, line)| span.to_entry(line))
+			.collect();
+
+		buffer.remove_since(self.start, |span| self.guards(span));
+
+		entries
+	}
+
+
+	/// Whether the entry is guarded.
+	fn guards(&self, span: &Span) -> bool {
+		self.thread.map_or(
+			true,
+			|current| span.details
+				.as_ref()
+				.and_then(|details| details.thread.as_ref())
+				.is_some_and(|thread| thread.id == current)
+		)
+	}
+}
+
+
+impl<'a> Drop for CaptureGuard<'a> {
+	fn drop(&mut self) {
+		let guard = &*self;
+
+		guard.logger.0
+			.lock()
+			.remove_since(guard.start, |span| guard.guards(span))
+	}
+}
+
+
+impl Source for MemoryLogger {
+	fn select(&self, criteria: &Criteria) -> Vec<LogEntry> {
+		self.0
+			.lock()
+			.entries()
+			.filter(
+				|(span, line)| criteria.matches(
+					span.level,
+					&span.target,
+					span.message(line),
+					span.timestamp
 				)
 			)
-		);
-
-		log::set_logger(&logger.0)?;
+			.map(|(span, line)| span.to_entry(line))
+			.collect()
+	}
+}
 
-		log::set_max_level(
-			level.to_level_filter()
-		);
 
-		Ok(logger)
+impl Push for MemoryLogger {
+	fn push_line(&self, level: Level, target: &str, line: &str) {
+		self.push(level, target, line)
 	}
+}
 
 
-	/// Dump the contents to a writer, cleaning the buffered contents.
-	pub fn dump<W>(&self, mut writer: W) -> io::Result<()>
-	where
-		W: Write
-	{
-		let buffer = &mut self.0.lock();
+#[cfg(feature = "http")]
+impl http::Serve for MemoryLogger {
+	fn serve_http(&self, query: &str) -> http::Response {
+		MemoryLogger::serve_http(self, query)
+	}
+}
 
-		writer.write_all(
-			buffer.as_bytes()
-		)?;
 
-		buffer.clear();
+#[cfg(all(feature = "control", unix))]
+impl control::Control for MemoryLogger {
+	fn dump(&self, writer: &mut dyn Write) -> io::Result<()> {
+		MemoryLogger::dump(self, writer)
+	}
 
-		Ok(())
+
+	fn clear(&self) {
+		MemoryLogger::clear(self)
 	}
 
 
-	/// Gets a reference to the buffered data.
-	/// Note that this locks the logger, causing logging to block.
-	pub fn read(&self) -> BufferLockGuard {
-		BufferLockGuard(self.0.lock())
+	fn level(&self) -> Level {
+		MemoryLogger::level(self)
 	}
 
 
-	/// Clears the log buffer.
-	/// Note that this locks the logger, causing logging to block.
-	pub fn clear(&self) {
-		self.0.lock().clear()
+	fn set_level(&self, level: Level) {
+		MemoryLogger::set_level(self, level)
 	}
 }
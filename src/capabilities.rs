@@ -0,0 +1,109 @@
+/// A description of what the compiled feature set supports, allowing downstream libraries
+/// to adapt at runtime instead of duplicating cfg logic against this crate's features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+	/// Whether the `blocking` flavor is available.
+	pub blocking: bool,
+	/// Whether the `asynchronous` flavor is available.
+	pub asynchronous: bool,
+	/// Whether log records can be filtered by target.
+	pub target: bool,
+	/// Whether entries record when they were logged.
+	pub timestamps: bool,
+	/// Whether entries capture structured key-value pairs.
+	pub kv: bool,
+	/// Whether retention policies, such as capacity caps, are available.
+	pub retention: bool,
+	/// Whether the logger can be controlled over a Unix domain socket.
+	pub control: bool,
+	/// Whether entries can be encrypted.
+	pub crypto: bool,
+	/// Whether entries can be streamed as frames.
+	pub framing: bool,
+	/// Whether entries can be exported to Graylog.
+	pub gelf: bool,
+	/// Whether the fixed-capacity `heapless` logger is available.
+	pub heapless: bool,
+	/// Whether entries can be served over HTTP.
+	pub http: bool,
+	/// Whether entries can be exported to the systemd journal.
+	pub journald: bool,
+	/// Whether records can be captured by a log4rs appender.
+	pub log4rs: bool,
+	/// Whether entries can be exported to Grafana Loki.
+	pub loki: bool,
+	/// Whether entries can be kept in a crash-safe ring file.
+	pub mmap: bool,
+	/// Whether entries can be exported to OpenTelemetry.
+	pub otel: bool,
+	/// Whether entries can be added as Sentry breadcrumbs.
+	pub sentry: bool,
+	/// Whether entries can be serialized through `serde`.
+	pub serde: bool,
+	/// Whether `slog` records can be captured.
+	pub slog: bool,
+	/// Whether dumps can be normalized for snapshot testing.
+	pub snapshot: bool,
+	/// Whether entries can be stored in an SQLite database.
+	pub sqlite: bool,
+	/// Whether entries can be streamed as Server-Sent Events.
+	pub sse: bool,
+	/// Whether entries can be consumed as an async stream.
+	pub stream: bool,
+	/// Whether new entries are announced through a tokio watch channel.
+	pub tokio: bool,
+	/// Whether `tracing` events can be captured.
+	pub tracing: bool,
+	/// Whether entries can be rendered by a ratatui widget.
+	pub tui: bool,
+	/// Whether entries can be written to the browser console.
+	pub wasm: bool,
+	/// Whether entries can be pushed to WebSocket viewers.
+	pub ws: bool,
+}
+
+
+/// Describe what the compiled feature set supports.
+///
+/// ```
+/// let capabilities = memory_logger::capabilities();
+///
+/// if capabilities.target {
+///     // Restrict captures to our own records.
+/// }
+/// # assert!(capabilities.timestamps);
+/// ```
+pub const fn capabilities() -> Capabilities {
+	Capabilities {
+		blocking: cfg!(feature = "blocking"),
+		asynchronous: cfg!(feature = "asynchronous"),
+		target: cfg!(feature = "target"),
+		timestamps: true,
+		kv: cfg!(feature = "kv"),
+		retention: true,
+		control: cfg!(all(feature = "control", unix)),
+		crypto: cfg!(feature = "crypto"),
+		framing: cfg!(feature = "framing"),
+		gelf: cfg!(feature = "gelf"),
+		heapless: cfg!(feature = "heapless"),
+		http: cfg!(feature = "http"),
+		journald: cfg!(all(feature = "journald", unix)),
+		log4rs: cfg!(feature = "log4rs"),
+		loki: cfg!(feature = "loki"),
+		mmap: cfg!(feature = "mmap"),
+		otel: cfg!(feature = "otel"),
+		sentry: cfg!(feature = "sentry"),
+		serde: cfg!(feature = "serde"),
+		slog: cfg!(feature = "slog"),
+		snapshot: cfg!(feature = "snapshot"),
+		sqlite: cfg!(feature = "sqlite"),
+		sse: cfg!(feature = "sse"),
+		stream: cfg!(feature = "stream"),
+		tokio: cfg!(feature = "tokio"),
+		tracing: cfg!(feature = "tracing"),
+		tui: cfg!(feature = "tui"),
+		wasm: cfg!(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown")),
+		ws: cfg!(feature = "ws"),
+	}
+}
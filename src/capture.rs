@@ -0,0 +1,359 @@
+use std::{
+	any::Any,
+	fmt::{Display, Write},
+};
+
+use log::Level;
+
+use crate::LogEntry;
+
+
+/// Run a block, appending the entries captured while it runs to the panic message if it
+/// panics. This is useful in tests, so that failure output includes the relevant logs
+/// without any manual dump calls.
+///
+/// The first argument is the memory logger, of either flavor.
+///
+/// ```should_panic
+/// # use memory_logger::fail_with_logs;
+/// # #[cfg(feature = "blocking")]
+/// # use memory_logger::blocking::MemoryLogger;
+/// # #[cfg(not(feature = "blocking"))]
+/// # use memory_logger::asynchronous::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+///
+/// fail_with_logs!(logger, {
+///     log::warn!(target: "mycrate", "Connection refused.");
+///     assert!(false, "should have connected");
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fail_with_logs {
+	($logger:expr, $($body:tt)+) => {
+		$logger.fail_with_logs(|| { $($body)+ })
+	};
+}
+
+
+/// Extract the message from a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+	if let Some(message) = payload.downcast_ref::<&str>() {
+		message
+	} else if let Some(message) = payload.downcast_ref::<String>() {
+		message
+	} else {
+		"Box<dyn Any>"
+	}
+}
+
+
+/// Append the captured logs to a failure message.
+pub(crate) fn report<M>(message: M, logs: &str) -> String
+where
+	M: Display
+{
+	format!("{}\n\ncaptured logs:\n{}", message, logs)
+}
+
+
+/// Assert that an entry with the given level, whose message contains the given pattern,
+/// was logged. The buffered contents are kept. On failure, the panic message shows the
+/// entries containing the pattern at other levels, or the most recent entries otherwise.
+///
+/// The first argument is the memory logger, of either flavor, or a
+/// [`TestCapture`](struct.TestCapture.html).
+///
+/// ```
+/// # use memory_logger::assert_logged;
+/// # #[cfg(feature = "blocking")]
+/// # use memory_logger::blocking::MemoryLogger;
+/// # #[cfg(not(feature = "blocking"))]
+/// # use memory_logger::asynchronous::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+///
+/// log::warn!(target: "mycrate", "Connection refused, retrying.");
+///
+/// assert_logged!(logger, log::Level::Warn, "retrying");
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_logged {
+	($logger:expr, $level:expr, $pattern:expr $(,)?) => {
+		$logger.assert_logged($level, $pattern)
+	};
+}
+
+
+/// Assert that no errors were logged. The buffered contents are kept. On failure, the panic
+/// message shows the first error, along with the entries logged around it.
+///
+/// The argument is the memory logger, of either flavor, or a
+/// [`TestCapture`](struct.TestCapture.html).
+///
+/// ```should_panic
+/// # use memory_logger::assert_no_errors;
+/// # #[cfg(feature = "blocking")]
+/// # use memory_logger::blocking::MemoryLogger;
+/// # #[cfg(not(feature = "blocking"))]
+/// # use memory_logger::asynchronous::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+///
+/// log::info!(target: "mycrate", "Connecting.");
+/// log::error!(target: "mycrate", "Connection lost.");
+///
+/// assert_no_errors!(logger);
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_no_errors {
+	($logger:expr $(,)?) => {
+		$logger.assert_no_errors()
+	};
+}
+
+
+/// The number of entries shown before and after the relevant entry of a failed assertion.
+const NEARBY: usize = 5;
+
+
+/// Check that an entry with the given level, whose message contains the pattern, is among
+/// the entries, describing the failure otherwise.
+pub(crate) fn check_logged(entries: &[LogEntry], level: Level, pattern: &str) -> Result<(), String> {
+	let matching = |entry: &&LogEntry| entry.message().contains(pattern);
+
+	if entries.iter().filter(matching).any(|entry| entry.level == level) {
+		return Ok(());
+	}
+
+	let mut report = format!("expected a {} entry containing {:?}, but none was logged", level, pattern);
+
+	let others: Vec<_> = entries
+		.iter()
+		.filter(matching)
+		.collect();
+
+	if others.is_empty() {
+		write_last(&mut report, entries);
+	} else {
+		report.push_str("\n\nentries containing the pattern at other levels:");
+
+		for entry in others {
+			let _ = write!(report, "\n  {}", entry.as_str());
+		}
+	}
+
+	Err(report)
+}
+
+
+/// Append the most recent entries to a failure report.
+fn write_last(report: &mut String, entries: &[LogEntry]) {
+	let start = entries.len().saturating_sub(2 * NEARBY);
+
+	let _ = write!(report, "\n\nlast {} of {} entries:", entries.len() - start, entries.len());
+
+	for entry in &entries[start ..] {
+		let _ = write!(report, "\n  {}", entry.as_str());
+	}
+}
+
+
+/// Check that no errors are among the entries, describing the failure otherwise.
+pub(crate) fn check_no_errors(entries: &[LogEntry]) -> Result<(), String> {
+	let errors = entries
+		.iter()
+		.filter(|entry| entry.level == Level::Error)
+		.count();
+
+	let first = match entries.iter().position(|entry| entry.level == Level::Error) {
+		Some(first) => first,
+		None => return Ok(()),
+	};
+
+	let mut report = format!(
+		"expected no errors, but {} {} logged\n\nfirst error and nearby entries:",
+		errors,
+		if errors == 1 { "was" } else { "were" },
+	);
+
+	let start = first.saturating_sub(NEARBY);
+	let end = entries.len().min(first + NEARBY + 1);
+
+	for (index, entry) in entries[start .. end].iter().enumerate() {
+		let marker = if start + index == first { '>' } else { ' ' };
+		let _ = write!(report, "\n{} {}", marker, entry.as_str());
+	}
+
+	Err(report)
+}
+
+
+/// Expectations on the captured entries, checked together by [`verify`](#method.verify),
+/// which reports every unmet expectation at once. Created through the `expect` method of
+/// the memory logger, of either flavor, or of a [`TestCapture`](struct.TestCapture.html),
+/// from a snapshot of the buffered entries. The buffered contents are kept.
+///
+/// ```
+/// # #[cfg(feature = "blocking")]
+/// # use memory_logger::blocking::MemoryLogger;
+/// # #[cfg(not(feature = "blocking"))]
+/// # use memory_logger::asynchronous::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+///
+/// log::warn!(target: "mycrate", "Connection refused, retrying.");
+/// log::warn!(target: "mycrate", "Connection refused, retrying.");
+/// log::info!(target: "mycrate", "Connected.");
+///
+/// logger
+///     .expect()
+///     .warnings(2)
+///     .errors(0)
+///     .matching("retrying")
+///     .verify();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "expectations are only checked by `verify`"]
+pub struct Expectation {
+	entries: Vec<LogEntry>,
+	counts: Vec<(Level, usize)>,
+	patterns: Vec<String>,
+}
+
+
+impl Expectation {
+	/// Create expectations on the given entries.
+	pub(crate) fn new(entries: Vec<LogEntry>) -> Self {
+		Self {
+			entries,
+			counts: Vec::new(),
+			patterns: Vec::new(),
+		}
+	}
+
+
+	/// Expect exactly the given number of entries with the given level.
+	pub fn count(mut self, level: Level, count: usize) -> Self {
+		self.counts.push((level, count));
+		self
+	}
+
+
+	/// Expect exactly the given number of errors.
+	pub fn errors(self, count: usize) -> Self {
+		self.count(Level::Error, count)
+	}
+
+
+	/// Expect exactly the given number of warnings.
+	pub fn warnings(self, count: usize) -> Self {
+		self.count(Level::Warn, count)
+	}
+
+
+	/// Expect exactly the given number of info entries.
+	pub fn infos(self, count: usize) -> Self {
+		self.count(Level::Info, count)
+	}
+
+
+	/// Expect exactly the given number of debug entries.
+	pub fn debugs(self, count: usize) -> Self {
+		self.count(Level::Debug, count)
+	}
+
+
+	/// Expect exactly the given number of trace entries.
+	pub fn traces(self, count: usize) -> Self {
+		self.count(Level::Trace, count)
+	}
+
+
+	/// Expect at least one entry whose message contains the given pattern.
+	pub fn matching<S>(mut self, pattern: S) -> Self
+	where
+		S: Into<String>
+	{
+		self.patterns.push(pattern.into());
+		self
+	}
+
+
+	/// Check the expectations.
+	///
+	/// # Panics
+	///
+	/// Panics if any expectation is unmet, listing the unmet expectations and the most
+	/// recent entries.
+	#[track_caller]
+	pub fn verify(self) {
+		if let Err(report) = self.check() {
+			panic!("{}", report);
+		}
+	}
+
+
+	/// Check the expectations, describing the unmet ones otherwise.
+	fn check(&self) -> Result<(), String> {
+		let mut failures = Vec::new();
+
+		for &(level, expected) in &self.counts {
+			let count = self.entries
+				.iter()
+				.filter(|entry| entry.level == level)
+				.count();
+
+			if count != expected {
+				failures.push(
+					format!(
+						"expected {} {} {}, but {} {} logged",
+						expected,
+						level,
+						if expected == 1 { "entry" } else { "entries" },
+						count,
+						if count == 1 { "was" } else { "were" },
+					)
+				);
+			}
+		}
+
+		for pattern in &self.patterns {
+			if !self.entries.iter().any(|entry| entry.message().contains(pattern.as_str())) {
+				failures.push(
+					format!("expected an entry containing {:?}, but none was logged", pattern)
+				);
+			}
+		}
+
+		if failures.is_empty() {
+			return Ok(());
+		}
+
+		let mut report = format!(
+			"{} of {} expectations failed:",
+			failures.len(),
+			self.counts.len() + self.patterns.len(),
+		);
+
+		for failure in failures {
+			let _ = write!(report, "\n  {}", failure);
+		}
+
+		write_last(&mut report, &self.entries);
+
+		Err(report)
+	}
+}
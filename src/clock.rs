@@ -0,0 +1,138 @@
+use std::{
+	fmt,
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime},
+};
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use std::time::UNIX_EPOCH;
+
+
+/// A source of the current time, for timestamping entries. A clock may be set through
+/// [`Config::clock`](struct.Config.html#method.clock), so that tests can freeze or step
+/// time and produce byte-identical dumps. Functions returning the time are clocks.
+pub trait Clock: Send + Sync {
+	/// The current time.
+	fn now(&self) -> SystemTime;
+}
+
+
+impl<F> Clock for F
+where
+	F: Fn() -> SystemTime + Send + Sync
+{
+	fn now(&self) -> SystemTime {
+		self()
+	}
+}
+
+
+/// A clock that is frozen at a given time, and only moves when set or advanced. Clones
+/// share the same time, so that a test may step the clock given to the logger.
+///
+/// ```
+/// use memory_logger::{Clock, ManualClock};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let clock = ManualClock::new(UNIX_EPOCH);
+/// clock.advance(Duration::from_secs(1));
+///
+/// assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+	time: Arc<Mutex<SystemTime>>,
+}
+
+
+impl ManualClock {
+	/// Create a clock frozen at the given time.
+	pub fn new(time: SystemTime) -> Self {
+		Self {
+			time: Arc::new(Mutex::new(time)),
+		}
+	}
+
+
+	/// Move the clock to the given time, which may precede the current one.
+	pub fn set(&self, time: SystemTime) {
+		*self.lock() = time;
+	}
+
+
+	/// Move the clock forward by the given duration.
+	pub fn advance(&self, duration: Duration) {
+		*self.lock() += duration;
+	}
+
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, SystemTime> {
+		self.time
+			.lock()
+			.expect("clock lock poisoned")
+	}
+}
+
+
+impl Clock for ManualClock {
+	fn now(&self) -> SystemTime {
+		*self.lock()
+	}
+}
+
+
+/// A clock shared by the configuration.
+#[derive(Clone)]
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) struct SharedClock(pub(crate) Arc<dyn Clock>);
+
+
+impl fmt::Debug for SharedClock {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Clock")
+	}
+}
+
+
+/// The current time, for timestamping entries.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn now() -> SystemTime {
+	SystemTime::now()
+}
+
+
+/// The current time, for timestamping entries. The system clock is not available in the
+/// browser, so the JavaScript clock is used instead.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) fn now() -> SystemTime {
+	UNIX_EPOCH + Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+
+/// A monotonic clock reading, for measuring intervals.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) type Instant = std::time::Instant;
+
+
+/// A clock reading, for measuring intervals. The monotonic clock is not available in the
+/// browser, so the JavaScript clock is used instead, and intervals are clamped to zero if
+/// it goes backwards.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Instant(SystemTime);
+
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Instant {
+	pub(crate) fn now() -> Self {
+		Self(now())
+	}
+
+
+	pub(crate) fn duration_since(&self, earlier: Self) -> Duration {
+		self.0
+			.duration_since(earlier.0)
+			.unwrap_or_default()
+	}
+}
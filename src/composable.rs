@@ -0,0 +1,74 @@
+use std::fmt;
+
+use log::{Log, Metadata, Record};
+
+
+/// A `log::Log` implementation buffering records in a memory logger, without being
+/// installed as the global logger itself. It may be plugged into dispatchers such as
+/// `fern::Dispatch::chain` or `multi_log`, as one of several outputs.
+///
+/// The log is created along with its logger through `setup_composable`. The logger's
+/// level and target filters apply to the records it receives.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, Config};
+/// let (logger, log) = MemoryLogger::setup_composable(
+///     Config::new(log::Level::Info, Regex::new("^mycrate")?)
+/// );
+///
+/// // As one of the outputs of a dispatcher:
+/// let outputs: Vec<Box<dyn log::Log>> = vec![Box::new(log)];
+///
+/// for output in &outputs {
+///     output.log(
+///         &log::Record::builder()
+///             .target("mycrate")
+///             .args(format_args!("Connected."))
+///             .build()
+///     );
+/// }
+///
+/// assert_eq!(&*logger.read(), "[mycrate] INFO  | Connected.\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct MemoryLog {
+	logger: &'static dyn Log,
+}
+
+
+impl MemoryLog {
+	pub(crate) fn new(logger: &'static dyn Log) -> Self {
+		Self { logger }
+	}
+}
+
+
+impl fmt::Debug for MemoryLog {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MemoryLog")
+			.finish_non_exhaustive()
+	}
+}
+
+
+impl Log for MemoryLog {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		self.logger.enabled(metadata)
+	}
+
+
+	fn log(&self, record: &Record) {
+		self.logger.log(record);
+	}
+
+
+	fn flush(&self) {
+		self.logger.flush();
+	}
+}
@@ -0,0 +1,783 @@
+use std::{
+	cell::Cell,
+	fmt,
+	io::{self, Write},
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime},
+};
+
+use log::{Level, Record};
+
+#[cfg(any(feature = "target", feature = "redact"))]
+use regex::Regex;
+
+#[cfg(feature = "redact")]
+use std::borrow::Cow;
+
+use crate::{
+	clock::{self, Instant, SharedClock},
+	format::{Message, Truncate},
+	metrics,
+	storage::Backend,
+	Clock,
+	Format,
+	entry::Details,
+	Location,
+	LogEntry,
+	Process,
+	Storage,
+	ThreadInfo,
+};
+
+
+/// Flight recorder parameters: how many verbose entries to keep, and where to flush them.
+#[derive(Clone)]
+pub(crate) struct FlightRecorder {
+	pub(crate) capacity: usize,
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) sink: Arc<Mutex<dyn Write + Send>>,
+}
+
+
+impl fmt::Debug for FlightRecorder {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("FlightRecorder")
+			.field("capacity", &self.capacity)
+			.finish()
+	}
+}
+
+
+/// A callback invoked for every captured entry.
+#[derive(Clone)]
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) struct Observer(Arc<dyn Fn(&LogEntry) + Send + Sync>);
+
+
+impl fmt::Debug for Observer {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Observer")
+	}
+}
+
+
+/// A function rewriting messages before they are buffered.
+#[derive(Clone)]
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) struct Transform(Arc<dyn Fn(String) -> String + Send + Sync>);
+
+
+impl fmt::Debug for Transform {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Transform")
+	}
+}
+
+
+thread_local! {
+	/// Whether the current thread is running the callbacks.
+	static OBSERVING: Cell<bool> = const { Cell::new(false) };
+}
+
+
+/// A mark of the current thread running the callbacks, which is cleared once dropped, even
+/// if a callback panics.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+struct Observing;
+
+
+impl Observing {
+	/// Mark the current thread, unless it is already running the callbacks.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	fn enter() -> Option<Self> {
+		if OBSERVING.with(|observing| observing.replace(true)) {
+			None
+		} else {
+			Some(Self)
+		}
+	}
+}
+
+
+impl Drop for Observing {
+	fn drop(&mut self) {
+		OBSERVING.with(|observing| observing.set(false));
+	}
+}
+
+
+/// A callback invoked for error entries, at most once per debounce interval.
+#[derive(Debug, Clone)]
+pub(crate) struct Alert {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	callback: Observer,
+	/// When the callback last fired.
+	fired: Arc<Mutex<Option<Instant>>>,
+}
+
+
+impl Alert {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	fn fire(&self, entry: &LogEntry, debounce: Duration) {
+		{
+			let mut fired = self.fired
+				.lock()
+				.expect("alert lock poisoned");
+
+			let now = Instant::now();
+
+			if fired.is_some_and(|fired| now.duration_since(fired) < debounce) {
+				return;
+			}
+
+			*fired = Some(now);
+		}
+
+		(self.callback.0)(entry);
+	}
+}
+
+
+/// Logger configuration, shared by both flavors.
+///
+/// The `target` parameter is only available with the `target` feature.
+/// Only log records that match such target are enabled.
+#[derive(Debug, Clone)]
+pub struct Config {
+	pub(crate) level: Level,
+
+	#[cfg(feature = "target")]
+	pub(crate) target: Regex,
+
+	pub(crate) format: Format,
+
+	pub(crate) pool: usize,
+
+	pub(crate) metrics: bool,
+
+	pub(crate) retain: usize,
+
+	pub(crate) observer: Option<Observer>,
+
+	pub(crate) alert: Option<Alert>,
+
+	pub(crate) debounce: Duration,
+
+	pub(crate) recorder: Option<FlightRecorder>,
+
+	pub(crate) window: Option<Duration>,
+
+	pub(crate) sampling: [u32; 5],
+
+	pub(crate) rate_limit: Option<u32>,
+
+	pub(crate) deduplicate: bool,
+
+	#[cfg(feature = "redact")]
+	pub(crate) redactions: Vec<(Regex, String)>,
+
+	pub(crate) transform: Option<Transform>,
+
+	pub(crate) max_message_len: Option<usize>,
+
+	pub(crate) max_bytes: Option<usize>,
+
+	pub(crate) shards: usize,
+
+	pub(crate) batch: usize,
+
+	pub(crate) source: bool,
+
+	pub(crate) thread: bool,
+
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) json_key_values: bool,
+
+	pub(crate) header: Option<String>,
+
+	pub(crate) storage: Option<Backend>,
+
+	pub(crate) clock: Option<SharedClock>,
+}
+
+
+impl Config {
+	/// Create a configuration with the given level and the default format.
+	pub fn new(
+		level: Level,
+		#[cfg(feature = "target")]
+		target: Regex,
+	) -> Self {
+		Self {
+			level,
+
+			#[cfg(feature = "target")]
+			target,
+
+			format: Format::default(),
+
+			pool: 0,
+
+			metrics: false,
+
+			retain: 0,
+
+			observer: None,
+
+			alert: None,
+
+			debounce: Duration::ZERO,
+
+			recorder: None,
+
+			window: None,
+
+			sampling: [1; 5],
+
+			rate_limit: None,
+
+			deduplicate: false,
+
+			#[cfg(feature = "redact")]
+			redactions: Vec::new(),
+
+			transform: None,
+
+			max_message_len: None,
+
+			max_bytes: None,
+
+			shards: 0,
+
+			batch: 0,
+
+			source: false,
+
+			thread: false,
+
+			json_key_values: false,
+
+			header: None,
+
+			storage: None,
+
+			clock: None,
+		}
+	}
+
+
+	/// The maximum enabled level.
+	pub fn level(&self) -> Level {
+		self.level
+	}
+
+
+	/// The target filter. Only log records that match such target are enabled.
+	#[cfg(feature = "target")]
+	pub fn target(&self) -> &Regex {
+		&self.target
+	}
+
+
+	/// The format used to render entries.
+	pub fn entry_format(&self) -> &Format {
+		&self.format
+	}
+
+
+	/// How many entry buffers are kept for reuse.
+	pub fn pool_size(&self) -> usize {
+		self.pool
+	}
+
+
+	/// Whether entries are counted by level and target.
+	pub fn metrics_enabled(&self) -> bool {
+		self.metrics
+	}
+
+
+	/// How many entries are retained by the background drain thread, or zero if it is
+	/// disabled.
+	pub fn retain_capacity(&self) -> usize {
+		self.retain
+	}
+
+
+	/// Set the format used to render entries.
+	pub fn format(mut self, format: Format) -> Self {
+		self.format = format;
+		self
+	}
+
+
+	/// Set how many entry buffers are kept for reuse per thread. Defaults to zero, which
+	/// disables pooling.
+	///
+	/// This only affects the asynchronous flavor, which allocates a buffer per entry. The
+	/// buffers of dumped entries are returned to the dumping thread's pool, and reused for
+	/// the entries it logs, reducing allocator pressure for high-rate capture. Buffers are
+	/// shrunk to 1 KiB before being pooled. Entries obtained through `read` are owned by
+	/// the caller, and are not recycled.
+	pub fn pool(mut self, size: usize) -> Self {
+		self.pool = size;
+		self
+	}
+
+
+	/// Set whether entries are counted by level and target, allowing the logger's
+	/// `metrics` to be queried. Defaults to false.
+	///
+	/// Counting takes a short lock, so it is disabled by default to keep the asynchronous
+	/// flavor non-blocking.
+	pub fn metrics(mut self, enabled: bool) -> Self {
+		self.metrics = enabled;
+		self
+	}
+
+
+	/// Set a callback invoked for every captured entry, enabling live updates or custom
+	/// side-channels without replacing the logger.
+	///
+	/// Only buffered entries are observed: entries dropped by sampling, rate limiting,
+	/// deduplication or the flight recorder are not, while the markers buffered in their
+	/// place are.
+	///
+	/// The callback runs on the logging thread, once the entry is rendered, outside of any
+	/// lock. Entries logged from the callback are captured, but not observed. If the
+	/// callback panics, the panic reaches the logging call, and later entries are still
+	/// observed.
+	pub fn observer<F>(mut self, observer: F) -> Self
+	where
+		F: Fn(&LogEntry) + Send + Sync + 'static
+	{
+		self.observer = Some(Observer(Arc::new(observer)));
+		self
+	}
+
+
+	/// Set a callback invoked for every captured error, so that applications may trigger
+	/// notifications or mark health checks degraded as soon as an error is buffered.
+	///
+	/// The callback runs like an [`observer`](#method.observer), under the same mark of the
+	/// logging thread, which is cleared even if either callback panics. It may be debounced
+	/// through `debounce_errors`.
+	pub fn on_error<F>(mut self, callback: F) -> Self
+	where
+		F: Fn(&LogEntry) + Send + Sync + 'static
+	{
+		self.alert = Some(
+			Alert {
+				callback: Observer(Arc::new(callback)),
+				fired: Arc::default(),
+			}
+		);
+		self
+	}
+
+
+	/// Set the minimum interval between invocations of the `on_error` callback. Errors
+	/// captured within the interval since the last invocation do not fire it. Defaults to
+	/// zero, which fires the callback for every error.
+	pub fn debounce_errors(mut self, interval: Duration) -> Self {
+		self.debounce = interval;
+		self
+	}
+
+
+	/// Whether the asynchronous flavor keeps entries after reads.
+	#[cfg_attr(not(feature = "asynchronous"), allow(dead_code))]
+	pub(crate) fn retaining(&self) -> bool {
+		self.retain > 0 || self.max_bytes.is_some() || self.storage.is_some()
+	}
+
+
+	/// Whether an entry with the given level must be built for the callbacks.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn observes(&self, level: Level) -> bool {
+		self.observer.is_some() || (level == Level::Error && self.alert.is_some())
+	}
+
+
+	/// Invoke the callbacks, if any.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn observe(&self, entry: &LogEntry) {
+		if !self.observes(entry.level) {
+			return;
+		}
+
+		let _observing = match Observing::enter() {
+			Some(observing) => observing,
+			None => return,
+		};
+
+		if let Some(Observer(observer)) = &self.observer {
+			observer(entry);
+		}
+
+		if let Some(alert) = &self.alert {
+			if entry.level == Level::Error {
+				alert.fire(entry, self.debounce);
+			}
+		}
+	}
+
+
+	/// Keep only the newest `capacity` debug and trace entries, flushing them to the given
+	/// sink when an error is logged. This allows cheap always-on verbose capture, with
+	/// detail only around failures.
+	///
+	/// Verbose entries are not buffered, but kept by the flight recorder. When an error is
+	/// logged, the recorded entries are written to the sink, followed by the error, and the
+	/// recorder starts over. Errors writing to the sink are ignored.
+	pub fn flight_recorder<W>(mut self, capacity: usize, sink: W) -> Self
+	where
+		W: Write + Send + 'static
+	{
+		self.recorder = Some(
+			FlightRecorder {
+				capacity,
+				sink: Arc::new(Mutex::new(sink)),
+			}
+		);
+		self
+	}
+
+
+	/// Only retain entries logged within the given window around an error. Entries are held
+	/// back, and silently dropped once older than the window, unless an error is logged in
+	/// the meantime. In such case, the whole window is buffered, as are the entries logged
+	/// within the window after the error.
+	///
+	/// This suits high-traffic servers that only care about the context around failures.
+	pub fn retain_on_error(mut self, window: Duration) -> Self {
+		self.window = Some(window);
+		self
+	}
+
+
+	/// Keep only one in `rate` entries of the given level, so that high-frequency verbose
+	/// entries don't dominate the buffer. Defaults to one, which keeps all entries.
+	///
+	/// Warnings and errors are never sampled, so setting a rate for such levels has no
+	/// effect.
+	pub fn sample(mut self, level: Level, rate: u32) -> Self {
+		if level > Level::Warn {
+			self.sampling[metrics::index(level)] = rate;
+		}
+		self
+	}
+
+
+	/// Limit each target to at most `per_second` entries per second, so that a chatty
+	/// module can't flood the buffer and drown out everything else.
+	///
+	/// Entries above the limit are suppressed. The next entry admitted for the target is
+	/// preceded by a warning stating how many entries were suppressed. Dumps, drains and
+	/// reads buffer that warning too, so that the count is reported even if the target
+	/// goes quiet.
+	pub fn rate_limit(mut self, per_second: u32) -> Self {
+		self.rate_limit = Some(per_second);
+		self
+	}
+
+
+	/// Set whether runs of identical entries are collapsed. Defaults to false.
+	///
+	/// When enabled, an entry with the same level, target and message as the previous
+	/// entry is not buffered. Once a different entry is logged, a warning stating how many
+	/// times the last message was repeated is buffered before it. Dumps and drains buffer
+	/// that warning too, so that a run still in progress is not lost. This keeps retry loops
+	/// from filling the buffer.
+	pub fn deduplicate(mut self, enabled: bool) -> Self {
+		self.deduplicate = enabled;
+		self
+	}
+
+
+	/// Add a redaction rule, replacing matches of the pattern in messages, and in the values
+	/// of key-value pairs, before they are buffered, so that sensitive data never lands in
+	/// the buffer or in dumps. Rules are applied in the order they were added.
+	///
+	/// The replacement may refer to capture groups, as in
+	/// [`Regex::replace_all`](https://docs.rs/regex/1/regex/struct.Regex.html#method.replace_all).
+	#[cfg(feature = "redact")]
+	pub fn redact<S>(mut self, pattern: Regex, replacement: S) -> Self
+	where
+		S: Into<String>
+	{
+		self.redactions.push((pattern, replacement.into()));
+		self
+	}
+
+
+	/// Set a transform applied to each message, and to the values of key-value pairs, before
+	/// they are buffered, for domain-specific scrubbing or normalization. It runs after the
+	/// redaction rules, if any.
+	pub fn transform<F>(mut self, transform: F) -> Self
+	where
+		F: Fn(String) -> String + Send + Sync + 'static
+	{
+		self.transform = Some(Transform(Arc::new(transform)));
+		self
+	}
+
+
+	/// Truncate messages longer than `limit` bytes, so that an accidental huge message
+	/// doesn't blow up memory. Truncated messages end with an ellipsis and their original
+	/// length, such as `… (1048576 bytes)`.
+	///
+	/// The limit applies after redaction and transforms.
+	pub fn max_message_len(mut self, limit: usize) -> Self {
+		self.max_message_len = Some(limit);
+		self
+	}
+
+
+	/// Limit the total length of the buffered lines to `budget` bytes, evicting the oldest
+	/// entries once exceeded, so that the logger's worst-case memory footprint is known.
+	///
+	/// The blocking flavor evicts entries in batches, down to seven eighths of the budget,
+	/// so that the buffer is not shifted for every entry. The asynchronous flavor applies
+	/// the budget to the ring buffer of [`retain`](#method.retain), which it enables.
+	pub fn max_bytes(mut self, budget: usize) -> Self {
+		self.max_bytes = Some(budget);
+		self
+	}
+
+
+	/// Append entries to `count` shards, each used by a subset of the logging threads,
+	/// instead of a single buffer. Defaults to zero, which disables sharding.
+	///
+	/// This only affects the blocking flavor, which otherwise renders every entry while
+	/// holding the buffer's lock, serializing the logging threads. Sharded entries are
+	/// rendered outside of any lock, and merged into the buffer by sequence number when it
+	/// is read, or once they exceed an eighth of the [`max_bytes`](#method.max_bytes) budget,
+	/// if any. Entries logged concurrently with a read may be merged after newer ones.
+	pub fn shards(mut self, count: usize) -> Self {
+		self.shards = count;
+		self
+	}
+
+
+	/// Accumulate up to `size` entries per logging thread before sending them through the
+	/// channel together, as a single message, reducing the per-entry overhead of high-rate
+	/// logging. Defaults to zero, which disables batching.
+	///
+	/// This only affects the asynchronous flavor. Reads send the partial batches of all
+	/// threads first, as does flushing the logger through `log::logger().flush()`. Waiting
+	/// reads, such as `recv_timeout`, only see entries of full or flushed batches. Entries
+	/// of different threads may be received out of sequence order.
+	pub fn batch(mut self, size: usize) -> Self {
+		self.batch = size;
+		self
+	}
+
+
+	/// Set whether entries record where they were logged: the source file, line and
+	/// module path, available through [`LogEntry::location`](struct.LogEntry.html#method.location).
+	/// The location is rendered before the message, as in `src/main.rs:12 | message`.
+	/// Defaults to false.
+	pub fn source(mut self, enabled: bool) -> Self {
+		self.source = enabled;
+		self
+	}
+
+
+	/// Set whether entries record the thread they were logged on, available through
+	/// [`LogEntry::thread`](struct.LogEntry.html#method.thread). The thread's name, or its id
+	/// if unnamed, is rendered before the message, as in `worker-1 | message`. Defaults to
+	/// false.
+	pub fn thread(mut self, enabled: bool) -> Self {
+		self.thread = enabled;
+		self
+	}
+
+
+	/// Set whether the key-value pairs of entries are appended to the line as a JSON
+	/// object, as in `message {"user":"42"}`, instead of preceding the message as in
+	/// `user=42 | message`, so that plain-text dumps can be parsed by downstream tools.
+	/// Values are rendered as strings. Defaults to false.
+	#[cfg(feature = "kv")]
+	pub fn json_key_values(mut self, enabled: bool) -> Self {
+		self.json_key_values = enabled;
+		self
+	}
+
+
+	/// Write a header describing the process, such as its id and the application version,
+	/// before the entries of each text dump: `dump`, `dump_as`, `dump_keep`,
+	/// `dump_filtered`, `dump_all` and the dumps to paths. The header is rendered once, at
+	/// setup. See [`Process`](struct.Process.html). Defaults to none.
+	pub fn process(mut self, process: Process) -> Self {
+		self.header = Some(format!("{}\n", process));
+		self
+	}
+
+
+	/// Set the clock timestamping entries, instead of the system clock, such as a
+	/// [`ManualClock`](struct.ManualClock.html) that tests can freeze or step, so that
+	/// timestamped dumps are byte-identical across runs. Defaults to the system clock.
+	pub fn clock<C>(mut self, clock: C) -> Self
+	where
+		C: Clock + 'static
+	{
+		self.clock = Some(SharedClock(Arc::new(clock)));
+		self
+	}
+
+
+	/// The current time, according to the configured clock.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn now(&self) -> SystemTime {
+		match &self.clock {
+			Some(SharedClock(clock)) => clock.now(),
+			None => clock::now(),
+		}
+	}
+
+
+	/// Write the process header to a dump, if enabled.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn write_header(&self, writer: &mut dyn Write) -> io::Result<()> {
+		match &self.header {
+			Some(header) => writer.write_all(header.as_bytes()),
+			None => Ok(()),
+		}
+	}
+
+
+	/// The details of a record: its location and thread, if enabled, and its key-value
+	/// pairs, if any.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn details(&self, record: &Record) -> Option<Details> {
+		#[cfg(feature = "kv")]
+		let key_values = {
+			let mut key_values = Details::key_values_of(record);
+
+			if self.rewrites() {
+				for (_, value) in &mut key_values {
+					*value = self.rewrite(std::mem::take(value));
+				}
+			}
+
+			key_values
+		};
+		#[cfg(not(feature = "kv"))]
+		let key_values = Vec::new();
+
+		let details = Details {
+			location: self.source.then(|| Location::of(record)),
+			thread: self.thread.then(ThreadInfo::current),
+			key_values,
+			json: self.json_key_values,
+			suffix: 0,
+		};
+
+		if details.is_empty() {
+			None
+		} else {
+			Some(details)
+		}
+	}
+
+
+	/// The message of a record, rewritten by the redaction rules and transform, if any.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn message<'a>(&self, record: &'a Record) -> Message<'a> {
+		let rewrites = self.rewrites();
+
+		let mut message = match self.max_message_len {
+			None if !rewrites => return Message::Args(record.args()),
+
+			// Avoid rendering oversized messages in full if they are not rewritten.
+			Some(limit) if !rewrites => return Message::Owned(truncate(limit, record.args())),
+
+			_ => record.args().to_string(),
+		};
+
+		if rewrites {
+			message = self.rewrite(message);
+		}
+
+		if let Some(limit) = self.max_message_len {
+			message = truncate(limit, &message);
+		}
+
+		Message::Owned(message)
+	}
+
+
+	/// Whether there are redaction rules or a transform.
+	fn rewrites(&self) -> bool {
+		#[cfg(feature = "redact")]
+		let redacts = !self.redactions.is_empty();
+		#[cfg(not(feature = "redact"))]
+		let redacts = false;
+
+		redacts || self.transform.is_some()
+	}
+
+
+	/// Rewrite a text by the redaction rules and transform, if any.
+	fn rewrite(&self, mut text: String) -> String {
+		#[cfg(feature = "redact")]
+		for (pattern, replacement) in &self.redactions {
+			let redacted = pattern.replace_all(&text, replacement.as_str());
+
+			if let Cow::Owned(redacted) = redacted {
+				text = redacted;
+			}
+		}
+
+		if let Some(Transform(transform)) = &self.transform {
+			text = transform(text);
+		}
+
+		text
+	}
+
+
+	/// Retain up to `capacity` entries in a ring buffer, filled by a background thread.
+	/// Defaults to zero, which disables retention.
+	///
+	/// This only affects the asynchronous flavor. When enabled, a background thread
+	/// continuously drains logged entries into the ring buffer, evicting the oldest entries
+	/// once full. Reads and dumps then become non-consuming: they yield the retained
+	/// entries, which stay available for subsequent reads.
+	pub fn retain(mut self, capacity: usize) -> Self {
+		self.retain = capacity;
+		self
+	}
+
+
+	/// Keep the retained entries in the given storage, instead of an in-memory ring buffer.
+	/// This enables retention, bounded by [`retain`](#method.retain) and
+	/// [`max_bytes`](#method.max_bytes) if set.
+	///
+	/// This only affects the asynchronous flavor: the blocking flavor ignores the storage,
+	/// keeping entries in its own buffer. The storage is moved into the logger when it is
+	/// set up, so it is not shared by loggers set up from clones of this configuration.
+	pub fn storage<S>(mut self, storage: S) -> Self
+	where
+		S: Storage + 'static
+	{
+		self.storage = Some(Backend::new(storage));
+		self
+	}
+}
+
+
+/// Truncate a message to at most `limit` bytes.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+fn truncate<M>(limit: usize, message: M) -> String
+where
+	M: fmt::Display
+{
+	let mut truncate = Truncate::new(limit);
+
+	fmt::Write::write_fmt(&mut truncate, format_args!("{}", message))
+		.expect("std::fmt::Write should never fail for String");
+
+	truncate.finish()
+}
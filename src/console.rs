@@ -0,0 +1,19 @@
+use log::Level;
+
+use js_sys::JsString;
+
+use web_sys::console;
+
+
+/// Write a line to the browser console, through the function matching the level, so that
+/// devtools may filter it.
+pub(crate) fn write(level: Level, line: &str) {
+	let line = JsString::from(line).into();
+
+	match level {
+		Level::Error => console::error_1(&line),
+		Level::Warn => console::warn_1(&line),
+		Level::Info => console::info_1(&line),
+		Level::Debug | Level::Trace => console::debug_1(&line),
+	}
+}
@@ -0,0 +1,130 @@
+//! A control interface over a Unix domain socket, so that operators may interact with the
+//! logger of a running daemon, such as through `socat - UNIX-CONNECT:/run/app.sock`.
+//!
+//! Clients send one command per line, and each response ends with an `ok` line, or an
+//! `error: ...` line if the command failed:
+//!
+//! - `dump`: write the buffered entries, cleaning the buffered contents.
+//! - `clear`: discard the buffered entries.
+//! - `level`: write the current level.
+//! - `set-level <level>`: change the level, such as `set-level debug`.
+//! - `help`: write the available commands.
+
+use std::{
+	fs,
+	io::{self, BufRead, BufReader, Write},
+	os::unix::{
+		fs::FileTypeExt,
+		net::{UnixListener, UnixStream},
+	},
+	path::Path,
+	thread,
+};
+
+use log::Level;
+
+
+const HELP: &str = "\
+dump: write the buffered entries, cleaning the buffered contents
+clear: discard the buffered entries
+level: write the current level
+set-level <level>: change the level
+help: write the available commands
+";
+
+
+/// A logger that can be controlled through the socket. This is implemented by both
+/// flavors.
+pub(crate) trait Control: Sync {
+	fn dump(&self, writer: &mut dyn Write) -> io::Result<()>;
+
+	fn clear(&self);
+
+	fn level(&self) -> Level;
+
+	fn set_level(&self, level: Level);
+}
+
+
+/// Listen on the given socket path, serving each client from its own thread. A stale socket
+/// left at the path by a previous run is replaced.
+pub(crate) fn serve_uds<C>(path: &Path, logger: &'static C) -> io::Result<()>
+where
+	C: Control
+{
+	let stale = fs::symlink_metadata(path)
+		.is_ok_and(|metadata| metadata.file_type().is_socket());
+
+	if stale {
+		fs::remove_file(path)?;
+	}
+
+	let listener = UnixListener::bind(path)?;
+
+	thread::Builder::new()
+		.name("memory_logger_uds".into())
+		.spawn(
+			move || {
+				for stream in listener.incoming().flatten() {
+					let _ = thread::Builder::new()
+						.name("memory_logger_uds_client".into())
+						.spawn(move || serve(stream, logger));
+				}
+			}
+		)?;
+
+	Ok(())
+}
+
+
+/// Serve a client until it disconnects.
+fn serve<C>(stream: UnixStream, logger: &C) -> io::Result<()>
+where
+	C: Control
+{
+	let mut writer = stream.try_clone()?;
+
+	for line in BufReader::new(stream).lines() {
+		let line = line?;
+		let mut words = line.split_whitespace();
+
+		let result = match (words.next(), words.next(), words.next()) {
+			(None, _, _) => continue,
+
+			(Some("dump"), None, _) => logger.dump(&mut writer),
+
+			(Some("clear"), None, _) => {
+				logger.clear();
+				Ok(())
+			}
+
+			(Some("level"), None, _) => writeln!(writer, "{}", logger.level().as_str().to_lowercase()),
+
+			(Some("set-level"), Some(level), None) => match level.parse() {
+				Ok(level) => {
+					logger.set_level(level);
+					Ok(())
+				}
+
+				Err(_) => Err(invalid(format!("invalid level: {}", level))),
+			},
+
+			(Some("help"), None, _) => writer.write_all(HELP.as_bytes()),
+
+			_ => Err(invalid(format!("invalid command: {}", line.trim()))),
+		};
+
+		match result {
+			Ok(()) => writer.write_all(b"ok\n")?,
+			Err(error) if error.kind() == io::ErrorKind::InvalidInput => writeln!(writer, "error: {}", error)?,
+			Err(error) => return Err(error),
+		}
+	}
+
+	Ok(())
+}
+
+
+fn invalid(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidInput, message)
+}
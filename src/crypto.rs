@@ -0,0 +1,223 @@
+//! Encryption of entries with AES-256-GCM, for logs captured on machines where they must
+//! not be readable without a key.
+//!
+//! Encrypted data is a sequence of frames, each sealing a single entry:
+//!
+//! - frame length (4 bytes, little endian), excluding this field.
+//! - nonce (12 bytes), random for each frame.
+//! - ciphertext, followed by the authentication tag (16 bytes).
+
+use std::{
+	collections::VecDeque,
+	fmt,
+	io,
+};
+
+use aes_gcm::{
+	aead::{Aead, AeadCore, KeyInit, OsRng},
+	Aes256Gcm,
+	Nonce,
+};
+
+use crate::{record, LogEntry, Storage};
+
+
+const NONCE: usize = 12;
+
+
+/// An AES-256-GCM key, used to encrypt entries and decrypt them back.
+///
+/// ```
+/// # use memory_logger::crypto::Key;
+/// let key = Key::new([7; 32]);
+/// assert_eq!(key.decrypt(&[]).unwrap(), "");
+/// ```
+#[derive(Clone)]
+pub struct Key(Aes256Gcm);
+
+
+impl Key {
+	/// Create a key from its bytes.
+	pub fn new(bytes: [u8; 32]) -> Self {
+		Self(Aes256Gcm::new(&bytes.into()))
+	}
+
+
+	/// Generate a random key. Such key only lives as long as the process, unless stored
+	/// elsewhere, and is therefore only useful for data encrypted at rest in memory.
+	pub fn generate() -> Self {
+		Self(Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng)))
+	}
+
+
+	/// Decrypt the lines of entries dumped with this key, such as through
+	/// `dump_encrypted`.
+	pub fn decrypt(&self, mut bytes: &[u8]) -> io::Result<String> {
+		let mut text = String::new();
+
+		while !bytes.is_empty() {
+			let (frame, rest) = record::peek_len(bytes)
+				.and_then(|len| bytes[4 ..].split_at_checked(len))
+				.ok_or_else(|| invalid("truncated frame"))?;
+
+			let line = self.open(frame)
+				.and_then(|line| String::from_utf8(line).ok())
+				.ok_or_else(|| invalid("frame failed to decrypt"))?;
+
+			text.push_str(&line);
+			bytes = rest;
+		}
+
+		Ok(text)
+	}
+
+
+	/// Encrypt the plaintext as a frame, appending it to `out`.
+	pub(crate) fn seal(&self, plaintext: &[u8], out: &mut Vec<u8>) {
+		let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+		let ciphertext = self.0
+			.encrypt(&nonce, plaintext)
+			.expect("plaintext should not exceed the AES-GCM limit");
+
+		out.extend_from_slice(&((NONCE + ciphertext.len()) as u32).to_le_bytes());
+		out.extend_from_slice(&nonce);
+		out.extend_from_slice(&ciphertext);
+	}
+
+
+	/// Decrypt a frame, excluding its length.
+	fn open(&self, frame: &[u8]) -> Option<Vec<u8>> {
+		let (nonce, ciphertext) = frame.split_at_checked(NONCE)?;
+
+		self.0
+			.decrypt(Nonce::from_slice(nonce), ciphertext)
+			.ok()
+	}
+}
+
+
+impl fmt::Debug for Key {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Key(..)")
+	}
+}
+
+
+fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+/// A sealed entry.
+#[derive(Debug)]
+struct Sealed {
+	frame: Box<[u8]>,
+	/// The length of the entry's line.
+	bytes: usize,
+}
+
+
+/// A storage keeping entries encrypted in memory, decrypting them only when read. Entries
+/// with targets longer than 64 KiB are skipped.
+///
+/// Only the line, level, target and timestamp of entries are encrypted: read entries have
+/// no location, thread or key-value pairs, and key-value pairs rendered as JSON are read
+/// back as part of the message.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "asynchronous")] {
+/// # use memory_logger::{asynchronous::MemoryLogger, crypto::{EncryptedStorage, Key}, Config};
+/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+///     .storage(EncryptedStorage::new(Key::generate()));
+/// let logger = MemoryLogger::setup_with(config)?;
+///
+/// log::info!(target: "mycrate", "User alice@example.com logged in.");
+///
+/// assert_eq!(logger.peek()[0].message(), "User alice@example.com logged in.");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EncryptedStorage {
+	key: Key,
+	entries: VecDeque<Sealed>,
+	bytes: usize,
+}
+
+
+impl EncryptedStorage {
+	/// Create a storage encrypting entries with the given key.
+	pub fn new(key: Key) -> Self {
+		Self {
+			key,
+			entries: VecDeque::new(),
+			bytes: 0,
+		}
+	}
+}
+
+
+impl Storage for EncryptedStorage {
+	fn append(&mut self, entry: LogEntry) {
+		let len = match record::len(&entry) {
+			Some(len) => len,
+			None => return,
+		};
+
+		let mut plaintext = vec![0; len];
+		record::encode(&entry, &mut plaintext);
+
+		let mut frame = Vec::new();
+		self.key.seal(&plaintext, &mut frame);
+
+		self.bytes += entry.line.len();
+		self.entries.push_back(
+			Sealed {
+				frame: frame.into(),
+				bytes: entry.line.len(),
+			}
+		);
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		Box::new(
+			self.entries
+				.iter()
+				.filter_map(
+					move |sealed| self.key
+						.open(&sealed.frame[4 ..])
+						.and_then(|plaintext| record::decode(&plaintext))
+				)
+		)
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		let count = count.min(self.entries.len());
+
+		for sealed in self.entries.drain(.. count) {
+			self.bytes -= sealed.bytes;
+		}
+	}
+
+
+	fn clear(&mut self) {
+		self.entries.clear();
+		self.bytes = 0;
+	}
+
+
+	fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.bytes
+	}
+}
@@ -0,0 +1,102 @@
+use std::fmt::{self, Write};
+
+use slog::{Drain, Key, Never, OwnedKVList, Record, Serializer, KV};
+
+
+/// A `slog` drain that forwards records to the installed memory logger, so that codebases
+/// mixing `slog` and `log` get a single in-memory history.
+///
+/// Records are logged with their module as target, and their key-value pairs, followed by
+/// those of the logger, are appended to the message, as in `Logged in. user=bob`. Critical
+/// records are logged as errors. The logger's level and target filters apply to records
+/// as they do to `log` records.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, MemoryDrain};
+/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+/// let root = slog::Logger::root(MemoryDrain::new(), slog::o!("version" => "1.2.0"));
+///
+/// slog::info!(root, "Logged in."; "user" => "bob");
+///
+/// assert!(logger.read().ends_with("INFO  | Logged in. user=bob version=1.2.0\n"));
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryDrain {
+	_private: (),
+}
+
+
+impl MemoryDrain {
+	/// Create a drain forwarding records to the installed logger.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+
+impl Drain for MemoryDrain {
+	type Ok = ();
+	type Err = Never;
+
+	fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+		let metadata = log::Metadata::builder()
+			.level(log_level(record.level()))
+			.target(record.module())
+			.build();
+
+		let logger = log::logger();
+
+		if !logger.enabled(&metadata) {
+			return Ok(());
+		}
+
+		let mut message = Fields(record.msg().to_string());
+
+		let _ = record
+			.kv()
+			.serialize(record, &mut message)
+			.and_then(|()| values.serialize(record, &mut message));
+
+		logger.log(
+			&log::Record::builder()
+				.metadata(metadata)
+				.args(format_args!("{}", message.0))
+				.module_path_static(Some(record.module()))
+				.file_static(Some(record.file()))
+				.line(Some(record.line()))
+				.build()
+		);
+
+		Ok(())
+	}
+}
+
+
+/// A message, to which key-value pairs are appended as ` key=value`.
+struct Fields(String);
+
+
+impl Serializer for Fields {
+	fn emit_arguments(&mut self, key: Key, value: &fmt::Arguments) -> slog::Result {
+		let _ = write!(self.0, " {}={}", key, value);
+		Ok(())
+	}
+}
+
+
+/// The `log` level matching a `slog` level.
+fn log_level(level: slog::Level) -> log::Level {
+	match level {
+		slog::Level::Critical | slog::Level::Error => log::Level::Error,
+		slog::Level::Warning => log::Level::Warn,
+		slog::Level::Info => log::Level::Info,
+		slog::Level::Debug => log::Level::Debug,
+		slog::Level::Trace => log::Level::Trace,
+	}
+}
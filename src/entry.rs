@@ -0,0 +1,597 @@
+use std::{
+	fmt,
+	ops::Deref,
+	sync::Arc,
+	thread::{self, ThreadId},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{Level, Record};
+
+use crate::{format, intern::intern, Format};
+
+#[cfg(feature = "kv")]
+use crate::json;
+
+#[cfg(feature = "serde")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use std::borrow::Cow;
+
+
+/// Where a record was logged. This is only captured if enabled through
+/// [`Config::source`](struct.Config.html#method.source).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Location {
+	pub(crate) module_path: Option<Arc<str>>,
+	pub(crate) file: Option<Arc<str>>,
+	pub(crate) line: Option<u32>,
+}
+
+
+impl Location {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn of(record: &Record) -> Self {
+		Self {
+			module_path: record.module_path().map(intern),
+			file: record.file().map(intern),
+			line: record.line(),
+		}
+	}
+
+
+	/// The module path of the logging code.
+	pub fn module_path(&self) -> Option<&str> {
+		self.module_path.as_deref()
+	}
+
+
+	/// The source file of the logging code.
+	pub fn file(&self) -> Option<&str> {
+		self.file.as_deref()
+	}
+
+
+	/// The line of the logging code in its source file.
+	pub fn line(&self) -> Option<u32> {
+		self.line
+	}
+}
+
+
+/// Renders the location as `file:line`, falling back to the module path if the file is
+/// unknown.
+impl fmt::Display for Location {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match (self.file(), self.line) {
+			(Some(file), Some(line)) => write!(f, "{}:{}", file, line),
+			(Some(file), None) => f.write_str(file),
+			(None, _) => f.write_str(self.module_path().unwrap_or("?")),
+		}
+	}
+}
+
+
+/// The thread a record was logged on. This is only captured if enabled through
+/// [`Config::thread`](struct.Config.html#method.thread).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadInfo {
+	pub(crate) id: ThreadId,
+	pub(crate) name: Option<Arc<str>>,
+}
+
+
+impl ThreadInfo {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn current() -> Self {
+		let thread = thread::current();
+
+		Self {
+			id: thread.id(),
+			name: thread.name().map(intern),
+		}
+	}
+
+
+	/// The id of the logging thread.
+	pub fn id(&self) -> ThreadId {
+		self.id
+	}
+
+
+	/// The name of the logging thread, if it was named.
+	pub fn name(&self) -> Option<&str> {
+		self.name.as_deref()
+	}
+}
+
+
+/// Renders the thread's name, falling back to its id for unnamed threads.
+impl fmt::Display for ThreadInfo {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.name() {
+			Some(name) => f.write_str(name),
+			None => write!(f, "{:?}", self.id),
+		}
+	}
+}
+
+
+/// Optional metadata of an entry, beyond its level, target and timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Details {
+	pub(crate) location: Option<Location>,
+	pub(crate) thread: Option<ThreadInfo>,
+	pub(crate) key_values: Vec<(Arc<str>, String)>,
+	/// Whether the key-value pairs are appended to the line as a JSON object.
+	pub(crate) json: bool,
+	/// Length of the text following the message in the line.
+	pub(crate) suffix: usize,
+}
+
+
+impl Details {
+	/// Capture the key-value pairs of a record, if any.
+	#[cfg(feature = "kv")]
+	pub(crate) fn key_values_of(record: &Record) -> Vec<(Arc<str>, String)> {
+		struct Visitor<'a>(&'a mut Vec<(Arc<str>, String)>);
+
+		impl<'kvs, 'a> log::kv::VisitSource<'kvs> for Visitor<'a> {
+			fn visit_pair(
+				&mut self,
+				key: log::kv::Key<'kvs>,
+				value: log::kv::Value<'kvs>,
+			) -> Result<(), log::kv::Error> {
+				self.0.push((intern(key.as_str()), value.to_string()));
+				Ok(())
+			}
+		}
+
+		let mut key_values = Vec::new();
+
+		let _ = record
+			.key_values()
+			.visit(&mut Visitor(&mut key_values));
+
+		key_values
+	}
+
+
+	/// Whether nothing was captured.
+	pub(crate) fn is_empty(&self) -> bool {
+		self.location.is_none() && self.thread.is_none() && self.key_values.is_empty()
+	}
+
+
+	/// Write the details as the segments preceding a message, as in
+	/// `worker-1 | src/main.rs:12 | user=42 | `.
+	pub(crate) fn write_segments<W>(&self, out: &mut W) -> fmt::Result
+	where
+		W: fmt::Write
+	{
+		if let Some(thread) = &self.thread {
+			write!(out, "{} | ", thread)?;
+		}
+
+		if let Some(location) = &self.location {
+			write!(out, "{} | ", location)?;
+		}
+
+		if !self.key_values.is_empty() && !self.json {
+			for (i, (key, value)) in self.key_values.iter().enumerate() {
+				if i > 0 {
+					out.write_char(' ')?;
+				}
+
+				write!(out, "{}={}", key, value)?;
+			}
+
+			out.write_str(" | ")?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Write the details following a message: the key-value pairs, if they are rendered as
+	/// JSON, as in ` {"user":"42"}`.
+	#[cfg(feature = "kv")]
+	pub(crate) fn write_suffix<W>(&self, out: &mut W) -> fmt::Result
+	where
+		W: fmt::Write
+	{
+		if self.key_values.is_empty() || !self.json {
+			return Ok(());
+		}
+
+		out.write_str(" {")?;
+
+		for (i, (key, value)) in self.key_values.iter().enumerate() {
+			if i > 0 {
+				out.write_char(',')?;
+			}
+
+			json::write_str(out, key)?;
+			out.write_char(':')?;
+			json::write_str(out, value)?;
+		}
+
+		out.write_char('}')
+	}
+}
+
+
+/// A log entry, holding both the rendered line and its metadata.
+///
+/// This type implements `Deref` for `str`, allowing access to the rendered line. With the
+/// `serde` feature, it implements `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+	pub(crate) sequence: u64,
+	pub(crate) level: Level,
+	pub(crate) target: Arc<str>,
+	pub(crate) timestamp: SystemTime,
+	pub(crate) line: String,
+	/// Offset of the message in the line.
+	pub(crate) message: usize,
+	pub(crate) details: Option<Box<Details>>,
+}
+
+
+impl LogEntry {
+	/// Build an entry from its parts, rendering the line in the given format into the
+	/// given buffer, which is cleared beforehand. If the buffer has no capacity, the line is
+	/// rendered into the thread-local scratch buffer instead, and copied into a buffer of
+	/// its exact length, so that it's allocated once.
+	#[allow(clippy::too_many_arguments)]
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn render_into<M>(
+		mut line: String,
+		format: &Format,
+		sequence: u64,
+		level: Level,
+		target: &str,
+		details: Option<Details>,
+		message: M,
+		timestamp: SystemTime,
+	) -> Self
+	where
+		M: fmt::Display
+	{
+		line.clear();
+
+		let (message, suffix) = if line.capacity() == 0 {
+			format::with_scratch(
+				|scratch| {
+					let parts = format.write_parts(
+						scratch,
+						level,
+						target,
+						details.as_ref(),
+						message,
+						timestamp
+					);
+					line.push_str(scratch);
+					parts
+				}
+			)
+		} else {
+			format.write_parts(
+				&mut line,
+				level,
+				target,
+				details.as_ref(),
+				message,
+				timestamp
+			)
+		};
+
+		Self {
+			sequence,
+			level,
+			target: intern(target),
+			timestamp,
+			line,
+			message,
+			details: details.map(|details| Box::new(Details { suffix, ..details })),
+		}
+	}
+
+
+	/// Parse a line rendered in the default format, `[target] LEVEL | message`, optionally
+	/// preceded by an RFC 3339 timestamp, or in the syslog format, so that entries may be
+	/// reconstructed from dumped text. Returns `None` if the line is in neither format.
+	///
+	/// The parsed entry's sequence number is zero, and its timestamp is the Unix epoch if the
+	/// line has none. Details, such as the thread, are kept in the message, as they can't be
+	/// told apart from it. Syslog lines don't distinguish trace entries, which are parsed as
+	/// debug entries.
+	///
+	/// ```
+	/// use memory_logger::LogEntry;
+	/// use std::time::{Duration, UNIX_EPOCH};
+	///
+	/// let entry = LogEntry::parse("[mycrate] WARN  | Connection refused, retrying.").unwrap();
+	///
+	/// assert_eq!(entry.level(), log::Level::Warn);
+	/// assert_eq!(entry.target(), "mycrate");
+	/// assert_eq!(entry.message(), "Connection refused, retrying.");
+	/// assert_eq!(entry.as_str(), "[mycrate] WARN  | Connection refused, retrying.");
+	///
+	/// let entry = LogEntry::parse("1970-01-01T00:00:01.500000Z [mycrate] INFO  | Connected.").unwrap();
+	///
+	/// assert_eq!(entry.timestamp(), UNIX_EPOCH + Duration::from_millis(1500));
+	/// assert_eq!(entry.message(), "Connected.");
+	///
+	/// let entry = LogEntry::parse("<11>1 1970-01-01T00:00:02.000000Z - myapp 42 - - [mycrate] Failed.").unwrap();
+	///
+	/// assert_eq!(entry.level(), log::Level::Error);
+	/// assert_eq!(entry.timestamp(), UNIX_EPOCH + Duration::from_secs(2));
+	/// assert_eq!(entry.message(), "Failed.");
+	///
+	/// assert!(LogEntry::parse("Not an entry.").is_none());
+	/// ```
+	pub fn parse(line: &str) -> Option<Self> {
+		let line = line.trim_end_matches(&['\r', '\n'][..]);
+		let parsed = format::parse_line(line)?;
+
+		Some(
+			Self {
+				sequence: 0,
+				level: parsed.level,
+				target: intern(parsed.target),
+				timestamp: parsed.timestamp.unwrap_or(UNIX_EPOCH),
+				line: line.to_owned(),
+				message: parsed.message,
+				details: None,
+			}
+		)
+	}
+
+
+	/// Build an entry from a rendered line and the message in it, which may be followed by
+	/// the key-value pairs rendered as JSON. Returns `None` if the message is not part of
+	/// the line.
+	pub(crate) fn from_line(
+		sequence: u64,
+		level: Level,
+		target: &str,
+		timestamp: SystemTime,
+		line: String,
+		message: &str,
+		details: Details,
+	) -> Option<Self> {
+		let offset = if line.ends_with(message) {
+			line.len() - message.len()
+		} else {
+			line.rfind(message)?
+		};
+
+		let suffix = line.len() - offset - message.len();
+
+		let details = Details {
+			json: suffix > 0,
+			suffix,
+			..details
+		};
+
+		Some(
+			Self {
+				sequence,
+				level,
+				target: intern(target),
+				timestamp,
+				line,
+				message: offset,
+				details: (!details.is_empty()).then(|| Box::new(details)),
+			}
+		)
+	}
+
+
+	/// Append a continuation line, such as of a multi-line message, to a parsed entry.
+	pub(crate) fn continue_line(&mut self, line: &str) {
+		self.line.push('\n');
+		self.line.push_str(line);
+	}
+
+
+	/// The entry's sequence number. Entries are numbered by the logger in logging order,
+	/// starting at zero, so consumers can detect gaps, order entries merged from multiple
+	/// sources, and read incrementally.
+	pub fn sequence(&self) -> u64 {
+		self.sequence
+	}
+
+
+	/// The entry's level.
+	pub fn level(&self) -> Level {
+		self.level
+	}
+
+
+	/// The entry's target.
+	pub fn target(&self) -> &str {
+		&self.target
+	}
+
+
+	/// When the entry was logged.
+	pub fn timestamp(&self) -> SystemTime {
+		self.timestamp
+	}
+
+
+	/// Where the entry was logged, if captured.
+	pub fn location(&self) -> Option<&Location> {
+		self.details
+			.as_ref()
+			.and_then(|details| details.location.as_ref())
+	}
+
+
+	/// The thread the entry was logged on, if captured.
+	pub fn thread(&self) -> Option<&ThreadInfo> {
+		self.details
+			.as_ref()
+			.and_then(|details| details.thread.as_ref())
+	}
+
+
+	/// The structured key-value pairs of the entry, in logging order. These are only
+	/// captured with the `kv` feature, from records logged as in
+	/// `log::info!(user = 42; "Logged in.")`.
+	pub fn key_values(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.details
+			.iter()
+			.flat_map(|details| details.key_values.iter())
+			.map(|(key, value)| (&**key, value.as_str()))
+	}
+
+
+	/// The logged message, without any formatting.
+	pub fn message(&self) -> &str {
+		let suffix = self.details
+			.as_ref()
+			.map_or(0, |details| details.suffix);
+
+		&self.line[self.message .. self.line.len() - suffix]
+	}
+
+
+	/// The rendered line, without a trailing newline.
+	pub fn as_str(&self) -> &str {
+		&self.line
+	}
+
+
+	/// Render the entry in the given format, regardless of the format it was logged in.
+	pub fn render(&self, format: &Format) -> String {
+		let mut line = String::new();
+		self.write_as(format, &mut line);
+		line
+	}
+
+
+	/// Append the entry, rendered in the given format, to a buffer.
+	pub(crate) fn write_as(&self, format: &Format, out: &mut String) {
+		format.write_parts(
+			out,
+			self.level,
+			&self.target,
+			self.details.as_deref(),
+			self.message(),
+			self.timestamp
+		);
+	}
+}
+
+
+impl Deref for LogEntry {
+	type Target = str;
+
+	fn deref(&self) -> &Self::Target {
+		self.as_str()
+	}
+}
+
+
+impl fmt::Display for LogEntry {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.line)
+	}
+}
+
+
+/// The serialized form of an entry, with the fields of the JSON served over HTTP.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "LogEntry")]
+struct Serialized<'a> {
+	sequence: u64,
+	timestamp: SystemTime,
+	level: Level,
+	target: Cow<'a, str>,
+	message: Cow<'a, str>,
+	line: Cow<'a, str>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	location: Option<Cow<'a, Location>>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	key_values: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+
+/// Serializes the entry's sequence number, timestamp, level, target, message, rendered line,
+/// location and key-value pairs, the latter as a sequence of pairs. The thread is not
+/// serialized, as thread ids are only meaningful within a process.
+///
+/// ```
+/// use memory_logger::LogEntry;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let entry = LogEntry::parse("[mycrate] WARN  | Connection refused, retrying.").unwrap();
+///
+/// let json = serde_json::to_string(&entry)?;
+/// let reloaded: LogEntry = serde_json::from_str(&json)?;
+///
+/// assert_eq!(reloaded, entry);
+/// assert_eq!(reloaded.message(), "Connection refused, retrying.");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+impl Serialize for LogEntry {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer
+	{
+		Serialized {
+			sequence: self.sequence,
+			timestamp: self.timestamp,
+			level: self.level,
+			target: Cow::Borrowed(&self.target),
+			message: Cow::Borrowed(self.message()),
+			line: Cow::Borrowed(&self.line),
+			location: self.location().map(Cow::Borrowed),
+			key_values: self
+				.key_values()
+				.map(|(key, value)| (Cow::Borrowed(key), Cow::Borrowed(value)))
+				.collect(),
+		}
+			.serialize(serializer)
+	}
+}
+
+
+/// Deserializes an entry serialized through `Serialize`. The message must be part of the
+/// line.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for LogEntry {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>
+	{
+		let entry = Serialized::deserialize(deserializer)?;
+
+		let details = Details {
+			location: entry.location.map(Cow::into_owned),
+			key_values: entry.key_values
+				.into_iter()
+				.map(|(key, value)| (intern(&key), value.into_owned()))
+				.collect(),
+			..Details::default()
+		};
+
+		Self::from_line(
+			entry.sequence,
+			entry.level,
+			&entry.target,
+			entry.timestamp,
+			entry.line.into_owned(),
+			&entry.message,
+			details
+		)
+			.ok_or_else(|| D::Error::custom("the message is not part of the line"))
+	}
+}
@@ -0,0 +1,61 @@
+use std::{
+	ffi::OsString,
+	fs::{self, File, OpenOptions},
+	io::{self, BufWriter, ErrorKind, Write},
+	path::{Path, PathBuf},
+};
+
+
+/// Append to the file at the given path, creating it if needed.
+pub(crate) fn append<F>(path: &Path, dump: F) -> io::Result<()>
+where
+	F: FnOnce(&mut BufWriter<File>) -> io::Result<()>
+{
+	let file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)?;
+
+	let mut writer = BufWriter::new(file);
+
+	dump(&mut writer)?;
+
+	writer.flush()
+}
+
+
+/// Rotate the file at the given path if it has reached `max_size` bytes: `path` is renamed
+/// to `path.1`, `path.1` to `path.2`, and so on, keeping at most `keep` rotated files.
+pub(crate) fn rotate(path: &Path, max_size: u64, keep: usize) -> io::Result<()> {
+	let size = match fs::metadata(path) {
+		Ok(metadata) => metadata.len(),
+		Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+		Err(error) => return Err(error),
+	};
+
+	if size < max_size {
+		return Ok(());
+	}
+
+	if keep == 0 {
+		return fs::remove_file(path);
+	}
+
+	for index in (1 .. keep).rev() {
+		let rotated = numbered(path, index);
+
+		if rotated.exists() {
+			fs::rename(rotated, numbered(path, index + 1))?;
+		}
+	}
+
+	fs::rename(path, numbered(path, 1))
+}
+
+
+/// The path of a rotated file.
+fn numbered(path: &Path, index: usize) -> PathBuf {
+	let mut name = OsString::from(path);
+	name.push(format!(".{}", index));
+	name.into()
+}
@@ -0,0 +1,500 @@
+use std::{
+	cell::Cell,
+	fmt::{self, Display, Write},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{Level, Record};
+
+use crate::entry::Details;
+
+
+/// The format used to render each log entry.
+#[derive(Debug, Clone, Default)]
+pub enum Format {
+	/// The default format: `[target] LEVEL | message`.
+	#[default]
+	Text,
+	/// RFC 5424 syslog messages, which may be replayed straight into syslog collectors.
+	Syslog(Syslog),
+	/// Only the message, skipping the rendering of a prefix when logging. Entries may be
+	/// rendered in another format once read, through
+	/// [`LogEntry::render`](struct.LogEntry.html#method.render) or the flavors' `dump_as`.
+	Message,
+}
+
+
+impl Format {
+	/// Render an entry from its parts, without a trailing newline. The details, if any,
+	/// precede the message, as in `worker-1 | src/main.rs:12 | user=42 | message`.
+	///
+	/// Returns the offset of the message in the rendered line, and the length of the text
+	/// following it.
+	pub(crate) fn write_parts<M>(
+		&self,
+		out: &mut String,
+		level: Level,
+		target: &str,
+		details: Option<&Details>,
+		message: M,
+		timestamp: SystemTime,
+	) -> (usize, usize)
+	where
+		M: Display
+	{
+		let start = out.len();
+
+		match self {
+			Self::Text => write!(out, "[{}] {:<5} | ", target, level),
+			Self::Syslog(syslog) => syslog.write_header(out, target, level, timestamp),
+			Self::Message => Ok(()),
+		}
+			.expect("std::fmt::Write should never fail for String");
+
+		if let Some(details) = details.filter(|_| !matches!(self, Self::Message)) {
+			details
+				.write_segments(out)
+				.expect("std::fmt::Write should never fail for String");
+		}
+
+		let offset = out.len() - start;
+
+		write!(out, "{}", message)
+			.expect("std::fmt::Write should never fail for String");
+
+		let end = out.len();
+
+		#[cfg(feature = "kv")]
+		if let Some(details) = details.filter(|_| !matches!(self, Self::Message)) {
+			details
+				.write_suffix(out)
+				.expect("std::fmt::Write should never fail for String");
+		}
+
+		(offset, out.len() - end)
+	}
+
+
+	/// The remainder of a rendered line after its target prefix, if this format starts
+	/// lines with the target.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn strip_target<'a>(&self, target: &str, line: &'a str) -> Option<&'a str> {
+		match self {
+			Self::Text => line.get(target.len() + 2 ..),
+			Self::Syslog(_) | Self::Message => None,
+		}
+	}
+}
+
+
+/// The message of an entry, either as logged or rewritten before buffering.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) enum Message<'a> {
+	Args(&'a fmt::Arguments<'a>),
+	Owned(String),
+}
+
+
+impl<'a> Display for Message<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Args(args) => args.fmt(f),
+			Self::Owned(message) => f.write_str(message),
+		}
+	}
+}
+
+
+/// A writer keeping at most `limit` bytes of a message, cut at a char boundary. Longer
+/// messages are marked with an ellipsis and their original length.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) struct Truncate {
+	message: String,
+	limit: usize,
+	len: usize,
+}
+
+
+impl Truncate {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn new(limit: usize) -> Self {
+		Self {
+			message: String::new(),
+			limit,
+			len: 0,
+		}
+	}
+
+
+	pub(crate) fn finish(mut self) -> String {
+		if self.len > self.limit {
+			write!(self.message, "\u{2026} ({} bytes)", self.len)
+				.expect("std::fmt::Write should never fail for String");
+		}
+
+		self.message
+	}
+}
+
+
+impl Write for Truncate {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.len += s.len();
+
+		let available = self.limit - self.message.len();
+		let mut end = available.min(s.len());
+
+		while !s.is_char_boundary(end) {
+			end -= 1;
+		}
+
+		self.message.push_str(&s[.. end]);
+
+		if end < s.len() {
+			// Stop at the first cut, so that a later shorter piece doesn't fill the gap.
+			self.limit = self.message.len();
+		}
+
+		Ok(())
+	}
+}
+
+
+/// RFC 5424 syslog format parameters.
+///
+/// Entries are rendered as `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID - - [target] message`.
+/// The timestamp is the time the record was logged, in UTC.
+#[derive(Debug, Clone)]
+pub struct Syslog {
+	app_name: String,
+	hostname: String,
+	facility: u8,
+	procid: u32,
+}
+
+
+impl Syslog {
+	/// The `user-level messages` facility, used by default.
+	pub const FACILITY_USER: u8 = 1;
+	/// The first locally defined facility (`local0`). Other local facilities follow up to
+	/// `local7` (23).
+	pub const FACILITY_LOCAL0: u8 = 16;
+
+
+	/// Create syslog parameters for the given app-name.
+	///
+	/// The hostname defaults to the nil value (`-`), and the facility to
+	/// `FACILITY_USER`.
+	pub fn new<S>(app_name: S) -> Self
+	where
+		S: Into<String>
+	{
+		Self {
+			app_name: app_name.into(),
+			hostname: String::new(),
+			facility: Self::FACILITY_USER,
+			procid: std::process::id(),
+		}
+	}
+
+
+	/// Set the hostname field.
+	pub fn hostname<S>(mut self, hostname: S) -> Self
+	where
+		S: Into<String>
+	{
+		self.hostname = hostname.into();
+		self
+	}
+
+
+	/// Set the facility code. Values above 23 are clamped.
+	pub fn facility(mut self, facility: u8) -> Self {
+		self.facility = facility.min(23);
+		self
+	}
+
+
+	fn write_header<W>(
+		&self,
+		out: &mut W,
+		target: &str,
+		level: Level,
+		timestamp: SystemTime,
+	) -> fmt::Result
+	where
+		W: Write
+	{
+		let priority = u32::from(self.facility) * 8 + severity(level);
+
+		write!(out, "<{}>1 ", priority)?;
+		write_rfc3339(out, timestamp)?;
+		write!(
+			out,
+			" {} {} {} - - [{}] ",
+			nil_or(&self.hostname),
+			nil_or(&self.app_name),
+			self.procid,
+			target,
+		)
+	}
+}
+
+
+/// Scratch buffers larger than this are not kept, so that a single huge message doesn't
+/// pin its memory.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+const SCRATCH_CAPACITY: usize = 64 * 1024;
+
+
+/// Run a function with an empty thread-local scratch buffer, which keeps its capacity
+/// across calls, so that rendering doesn't allocate once the buffer has grown. Reentrant
+/// calls, such as loggers called while rendering, get a new buffer.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn with_scratch<F, R>(f: F) -> R
+where
+	F: FnOnce(&mut String) -> R
+{
+	thread_local! {
+		static SCRATCH: Cell<String> = const { Cell::new(String::new()) };
+	}
+
+	let mut scratch = SCRATCH
+		.try_with(Cell::take)
+		.unwrap_or_default();
+
+	let result = f(&mut scratch);
+
+	if scratch.capacity() <= SCRATCH_CAPACITY {
+		scratch.clear();
+
+		let _ = SCRATCH.try_with(|cell| cell.set(scratch));
+	}
+
+	result
+}
+
+
+/// The record target, falling back to the module path when empty.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn record_target<'a>(record: &Record<'a>) -> &'a str {
+	if record.target().is_empty() {
+		record
+			.module_path()
+			.unwrap_or("?")
+	} else {
+		record.target()
+	}
+}
+
+
+fn nil_or(field: &str) -> &str {
+	if field.is_empty() { "-" } else { field }
+}
+
+
+/// The syslog severity for a log level.
+pub(crate) fn severity(level: Level) -> u32 {
+	match level {
+		Level::Error => 3,
+		Level::Warn => 4,
+		Level::Info => 6,
+		Level::Debug | Level::Trace => 7,
+	}
+}
+
+
+/// Write a UTC timestamp in RFC 3339 format, with microsecond precision.
+pub(crate) fn write_rfc3339<W>(out: &mut W, time: SystemTime) -> fmt::Result
+where
+	W: Write
+{
+	let duration = time
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default();
+
+	let secs = duration.as_secs();
+	let days = (secs / 86_400) as i64;
+	let secs_of_day = secs % 86_400;
+
+	// Civil from days, as described in http://howardhinnant.github.io/date_algorithms.html
+	let z = days + 719_468;
+	let era = z.div_euclid(146_097);
+	let doe = z.rem_euclid(146_097);
+	let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	write!(
+		out,
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+		year,
+		month,
+		day,
+		secs_of_day / 3600,
+		secs_of_day / 60 % 60,
+		secs_of_day % 60,
+		duration.subsec_micros()
+	)
+}
+
+
+/// The parts of a line parsed by `parse_line`.
+pub(crate) struct Parsed<'a> {
+	pub(crate) timestamp: Option<SystemTime>,
+	pub(crate) target: &'a str,
+	pub(crate) level: Level,
+	/// Offset of the message in the line.
+	pub(crate) message: usize,
+}
+
+
+/// Parse a line rendered in the syslog format, or in the text format, optionally preceded
+/// by an RFC 3339 timestamp.
+pub(crate) fn parse_line(line: &str) -> Option<Parsed<'_>> {
+	parse_syslog(line).or_else(|| parse_text(line))
+}
+
+
+/// Parse a line rendered as `[target] LEVEL | message`, optionally preceded by an RFC 3339
+/// timestamp and a space.
+fn parse_text(line: &str) -> Option<Parsed<'_>> {
+	let (timestamp, rest) = match line.split_once(' ') {
+		Some((prefix, rest)) if !prefix.starts_with('[') => (Some(parse_rfc3339(prefix)?), rest),
+		_ => (None, line),
+	};
+
+	let (target, rest) = rest
+		.strip_prefix('[')?
+		.split_once("] ")?;
+
+	let (level, message) = rest.split_once(" | ")?;
+
+	Some(
+		Parsed {
+			timestamp,
+			target,
+			level: level.trim_end().parse().ok()?,
+			message: line.len() - message.len(),
+		}
+	)
+}
+
+
+/// Parse a line rendered as `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID - - [target] message`.
+fn parse_syslog(line: &str) -> Option<Parsed<'_>> {
+	let (priority, rest) = line
+		.strip_prefix('<')?
+		.split_once(">1 ")?;
+
+	let priority: u32 = priority.parse().ok()?;
+
+	// The timestamp, hostname, app-name, procid and msgid, followed by the rest.
+	let mut fields = rest.splitn(6, ' ');
+
+	let timestamp = parse_rfc3339(fields.next()?)?;
+
+	let (target, message) = fields
+		.nth(4)?
+		.strip_prefix("- [")?
+		.split_once("] ")?;
+
+	let level = match priority % 8 {
+		0 ..= 3 => Level::Error,
+		4 => Level::Warn,
+		5 | 6 => Level::Info,
+		_ => Level::Debug,
+	};
+
+	Some(
+		Parsed {
+			timestamp: Some(timestamp),
+			target,
+			level,
+			message: line.len() - message.len(),
+		}
+	)
+}
+
+
+/// Parse an RFC 3339 timestamp, such as written by `write_rfc3339`. Fractions of a second
+/// beyond nanoseconds are truncated. Returns `None` for years beyond 9999.
+pub(crate) fn parse_rfc3339(text: &str) -> Option<SystemTime> {
+	let (date, time) = text.split_once('T')?;
+
+	let (time, offset) = match time.strip_suffix('Z') {
+		Some(time) => (time, 0),
+		None => {
+			let split = time.len().checked_sub(6)?;
+			let (time, offset) = (time.get(.. split)?, time.get(split ..)?);
+
+			let sign = match offset.as_bytes()[0] {
+				b'+' => 1,
+				b'-' => -1,
+				_ => return None,
+			};
+
+			let (hours, minutes) = offset[1 ..].split_once(':')?;
+			let (hours, minutes) = (hours.parse::<u8>().ok()?, minutes.parse::<u8>().ok()?);
+
+			if hours > 23 || minutes > 59 {
+				return None;
+			}
+
+			(time, sign * (i64::from(hours) * 3600 + i64::from(minutes) * 60))
+		}
+	};
+
+	let (time, fraction) = time
+		.split_once('.')
+		.unwrap_or((time, ""));
+
+	let mut date = date.splitn(3, '-');
+	let year: i64 = date.next()?.parse().ok()?;
+	let month: i64 = date.next()?.parse().ok()?;
+	let day: i64 = date.next()?.parse().ok()?;
+
+	let mut time = time.splitn(3, ':');
+	let hour: i64 = time.next()?.parse().ok()?;
+	let minute: i64 = time.next()?.parse().ok()?;
+	let second: i64 = time.next()?.parse().ok()?;
+
+	// Years are bounded as in RFC 3339, which also keeps the arithmetic below from
+	// overflowing.
+	if !(0 ..= 9999).contains(&year) || !(1 ..= 12).contains(&month) || !(1 ..= 31).contains(&day) {
+		return None;
+	}
+
+	if !(0 ..= 23).contains(&hour) || !(0 ..= 59).contains(&minute) || !(0 ..= 60).contains(&second) {
+		return None;
+	}
+
+	if !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+		return None;
+	}
+
+	let digits = fraction.get(.. 9).unwrap_or(fraction);
+	let nanos = match digits {
+		"" => 0,
+		digits => digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32),
+	};
+
+	// Days from civil, as described in http://howardhinnant.github.io/date_algorithms.html
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let yoe = year.rem_euclid(400);
+	let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	let days = era * 146_097 + doe - 719_468;
+
+	let secs = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+
+	if secs < 0 {
+		return None;
+	}
+
+	Some(UNIX_EPOCH + Duration::new(secs as u64, nanos))
+}
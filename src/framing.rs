@@ -0,0 +1,385 @@
+/*!
+A framing format for streaming entries over byte-oriented links, such as UART or RTT.
+
+Each entry is encoded as a frame, which is COBS-encoded and terminated by a zero byte,
+allowing a decoder to resynchronize after lost or corrupted bytes. The frame payload is:
+
+- format version (1 byte), currently `1`.
+- level (1 byte), from `1` (error) to `5` (trace).
+- timestamp, in microseconds since the unix epoch (LEB128), or `0` if unknown.
+- target length (LEB128), followed by the target bytes.
+- message bytes, up to the end of the frame.
+
+The [`encode`](fn.encode.html) function does not allocate and writes to an arbitrary sink,
+so it may be used by devices to stream their captures. The [`Decoder`](struct.Decoder.html)
+reconstructs entries on the host side.
+
+```
+# use memory_logger::framing::{self, Decoder};
+let mut stream = Vec::new();
+framing::encode(log::Level::Warn, "device::radio", "Link lost.", 0, |bytes| {
+    stream.extend_from_slice(bytes)
+});
+
+let mut decoder = Decoder::new();
+let entries: Vec<_> = decoder.feed(&stream).collect::<Result<_, _>>().unwrap();
+
+assert_eq!(entries[0].level(), log::Level::Warn);
+assert_eq!(entries[0].target(), "device::radio");
+assert_eq!(&*entries[0], "[device::radio] WARN  | Link lost.");
+```
+*/
+
+use std::{
+	error::Error,
+	fmt,
+	ops::Range,
+	str,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::Level;
+
+use crate::{Format, LogEntry};
+
+
+/// The current format version.
+const VERSION: u8 = 1;
+/// The frame delimiter.
+const DELIMITER: u8 = 0;
+/// The maximum COBS block size, including the code byte.
+const BLOCK: usize = 255;
+
+
+/// Encode an entry as a frame, including the delimiter, writing it to the given sink.
+/// The sink may be called multiple times for a single frame.
+///
+/// The timestamp is in microseconds since the unix epoch, or `0` if unknown.
+pub fn encode<F>(level: Level, target: &str, message: &str, timestamp: u64, sink: F)
+where
+	F: FnMut(&[u8])
+{
+	let mut encoder = CobsEncoder::new(sink);
+
+	encoder.write(&[VERSION, level as u8]);
+	encoder.write_varint(timestamp);
+	encoder.write_varint(target.len() as u64);
+	encoder.write(target.as_bytes());
+	encoder.write(message.as_bytes());
+
+	encoder.finish();
+}
+
+
+/// Encode an entry as a frame, including the delimiter, writing it to the given sink.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn encode_entry<F>(level: Level, target: &str, message: &str, timestamp: SystemTime, sink: F)
+where
+	F: FnMut(&[u8])
+{
+	let timestamp = timestamp
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_micros() as u64);
+
+	encode(level, target, message, timestamp, sink)
+}
+
+
+/// A streaming COBS encoder, using a single block of stack memory.
+struct CobsEncoder<F> {
+	sink: F,
+	block: [u8; BLOCK],
+	len: usize,
+}
+
+
+impl<F> CobsEncoder<F>
+where
+	F: FnMut(&[u8])
+{
+	fn new(sink: F) -> Self {
+		Self {
+			sink,
+			block: [0; BLOCK],
+			len: 1,
+		}
+	}
+
+
+	fn write(&mut self, bytes: &[u8]) {
+		for &byte in bytes {
+			if byte == DELIMITER {
+				self.flush_block();
+			} else {
+				self.block[self.len] = byte;
+				self.len += 1;
+
+				if self.len == BLOCK {
+					self.flush_block();
+				}
+			}
+		}
+	}
+
+
+	fn write_varint(&mut self, mut value: u64) {
+		loop {
+			let byte = (value & 0x7f) as u8;
+			value >>= 7;
+
+			if value == 0 {
+				self.write(&[byte]);
+				return;
+			}
+
+			self.write(&[byte | 0x80]);
+		}
+	}
+
+
+	fn flush_block(&mut self) {
+		self.block[0] = self.len as u8;
+		(self.sink)(&self.block[.. self.len]);
+		self.len = 1;
+	}
+
+
+	fn finish(mut self) {
+		self.flush_block();
+		(self.sink)(&[DELIMITER]);
+	}
+}
+
+
+/// An error decoding a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+	/// The frame is not valid COBS.
+	Cobs,
+	/// The frame ended before all fields were read.
+	Truncated,
+	/// The frame has an unsupported format version.
+	Version(u8),
+	/// The frame has an invalid level.
+	Level(u8),
+	/// The frame has a timestamp, in microseconds since the unix epoch, that can't be
+	/// represented.
+	Timestamp(u64),
+	/// The target or message is not valid UTF-8.
+	Utf8,
+}
+
+
+impl fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Cobs => write!(f, "invalid COBS frame"),
+			Self::Truncated => write!(f, "truncated frame"),
+			Self::Version(version) => write!(f, "unsupported frame version: {}", version),
+			Self::Level(level) => write!(f, "invalid level: {}", level),
+			Self::Timestamp(timestamp) => write!(f, "invalid timestamp: {}", timestamp),
+			Self::Utf8 => write!(f, "invalid UTF-8 in frame"),
+		}
+	}
+}
+
+
+impl Error for DecodeError { }
+
+
+/// A host-side decoder, reconstructing entries from a byte stream.
+///
+/// Bytes may be fed in arbitrary chunks. Incomplete frames are kept until the next feed, as
+/// are the complete frames not yet decoded when the iterator returned by a feed is dropped.
+/// Decoded entries are rendered in the given format, which defaults to the text format,
+/// and numbered in decoding order.
+#[derive(Debug, Default)]
+pub struct Decoder {
+	format: Format,
+	/// The bytes fed so far, of which those before `start` have been decoded.
+	buffer: Vec<u8>,
+	start: usize,
+	/// The sequence number of the next decoded entry.
+	sequence: u64,
+}
+
+
+impl Decoder {
+	/// Create a decoder rendering entries in the default format.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+
+	/// Create a decoder rendering entries in the given format.
+	pub fn with_format(format: Format) -> Self {
+		Self {
+			format,
+			buffer: Vec::new(),
+			start: 0,
+			sequence: 0,
+		}
+	}
+
+
+	/// Feed bytes to the decoder, iterating over the frames they complete.
+	///
+	/// An invalid frame yields an error, but does not affect subsequent frames.
+	///
+	/// ```
+	/// # use memory_logger::framing::{self, Decoder};
+	/// let mut stream = Vec::new();
+	/// for message in &["First.", "Second."] {
+	///     framing::encode(log::Level::Info, "device", message, 0, |bytes| {
+	///         stream.extend_from_slice(bytes)
+	///     });
+	/// }
+	///
+	/// let mut decoder = Decoder::new();
+	/// assert_eq!(decoder.feed(&stream).next().unwrap().unwrap().message(), "First.");
+	/// assert_eq!(decoder.feed(&[]).next().unwrap().unwrap().message(), "Second.");
+	/// ```
+	pub fn feed<'a>(&'a mut self, bytes: &[u8]) -> impl Iterator<Item = Result<LogEntry, DecodeError>> + 'a {
+		self.buffer.drain(.. self.start);
+		self.start = 0;
+		self.buffer.extend_from_slice(bytes);
+
+		std::iter::from_fn(move || self.next_frame())
+	}
+
+
+	/// Decode the next complete frame in the buffer, skipping empty ones.
+	fn next_frame(&mut self) -> Option<Result<LogEntry, DecodeError>> {
+		loop {
+			let len = self.buffer[self.start ..]
+				.iter()
+				.position(|&byte| byte == DELIMITER)?;
+
+			let frame = self.start .. self.start + len;
+			self.start += len + 1;
+
+			if !frame.is_empty() {
+				return Some(self.decode_frame(frame));
+			}
+		}
+	}
+
+
+	fn decode_frame(&mut self, frame: Range<usize>) -> Result<LogEntry, DecodeError> {
+		let payload = cobs_decode(&self.buffer[frame])?;
+		let mut reader = Reader(&payload);
+
+		let version = reader.byte()?;
+		if version != VERSION {
+			return Err(DecodeError::Version(version));
+		}
+
+		let level = reader.byte()?;
+		let level = match level {
+			1 => Level::Error,
+			2 => Level::Warn,
+			3 => Level::Info,
+			4 => Level::Debug,
+			5 => Level::Trace,
+			_ => return Err(DecodeError::Level(level)),
+		};
+
+		let timestamp = reader.varint()?;
+		let timestamp = UNIX_EPOCH
+			.checked_add(Duration::from_micros(timestamp))
+			.ok_or(DecodeError::Timestamp(timestamp))?;
+
+		let target_len = reader.varint()? as usize;
+		let target = reader.take(target_len)?;
+		let target = str::from_utf8(target).map_err(|_| DecodeError::Utf8)?;
+
+		let message = str::from_utf8(reader.0).map_err(|_| DecodeError::Utf8)?;
+
+		let sequence = self.sequence;
+		self.sequence += 1;
+
+		Ok(
+			LogEntry::render_into(
+				String::new(),
+				&self.format,
+				sequence,
+				level,
+				target,
+				None,
+				message,
+				timestamp
+			)
+		)
+	}
+}
+
+
+/// Decode a COBS frame, without its delimiter.
+fn cobs_decode(frame: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	let mut payload = Vec::with_capacity(frame.len());
+	let mut rest = frame;
+
+	while let Some((&code, tail)) = rest.split_first() {
+		let len = usize::from(code)
+			.checked_sub(1)
+			.ok_or(DecodeError::Cobs)?;
+
+		let block = tail
+			.get(.. len)
+			.ok_or(DecodeError::Cobs)?;
+
+		payload.extend_from_slice(block);
+		rest = &tail[len ..];
+
+		if usize::from(code) < BLOCK && !rest.is_empty() {
+			payload.push(DELIMITER);
+		}
+	}
+
+	Ok(payload)
+}
+
+
+/// A cursor over a frame payload.
+struct Reader<'a>(&'a [u8]);
+
+
+impl<'a> Reader<'a> {
+	fn byte(&mut self) -> Result<u8, DecodeError> {
+		let (&byte, rest) = self.0
+			.split_first()
+			.ok_or(DecodeError::Truncated)?;
+
+		self.0 = rest;
+
+		Ok(byte)
+	}
+
+
+	fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+		if len > self.0.len() {
+			return Err(DecodeError::Truncated);
+		}
+
+		let (bytes, rest) = self.0.split_at(len);
+		self.0 = rest;
+
+		Ok(bytes)
+	}
+
+
+	fn varint(&mut self) -> Result<u64, DecodeError> {
+		let mut value = 0u64;
+
+		for shift in (0 .. 64).step_by(7) {
+			let byte = self.byte()?;
+			value |= u64::from(byte & 0x7f) << shift;
+
+			if byte & 0x80 == 0 {
+				return Ok(value);
+			}
+		}
+
+		Err(DecodeError::Truncated)
+	}
+}
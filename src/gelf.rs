@@ -0,0 +1,225 @@
+use std::{
+	borrow::Borrow,
+	fmt::{self, Write},
+	io,
+	net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+	sync::atomic::{AtomicU64, Ordering},
+	time::UNIX_EPOCH,
+};
+
+use crate::{format, json, LogEntry, Sink};
+
+
+/// Magic bytes that start every chunk of a chunked GELF message.
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// Size of the chunk header: magic, message id, sequence number and sequence count.
+const CHUNK_HEADER: usize = 12;
+/// Maximum number of chunks accepted by Graylog for a single message.
+const MAX_CHUNKS: usize = 128;
+
+
+/// An exporter that ships log entries as GELF 1.1 datagrams to a Graylog UDP endpoint.
+///
+/// Messages larger than the chunk size are split into GELF chunks.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, GelfExporter};
+/// # let graylog = std::net::UdpSocket::bind("127.0.0.1:0")?;
+/// # let endpoint = graylog.local_addr()?;
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// let exporter = GelfExporter::new(endpoint, "myhost")?;
+///
+/// log::info!(target: "mycrate", "This is a info.");
+///
+/// exporter.export(logger.drain())?;
+/// # let mut datagram = [0; 1024];
+/// # let size = graylog.recv(&mut datagram)?;
+/// # let payload = std::str::from_utf8(&datagram[.. size])?;
+/// # assert!(payload.starts_with(r#"{"version":"1.1","host":"myhost","short_message":"This is a info.","#));
+/// # assert!(payload.ends_with(r#","level":6,"_target":"mycrate"}"#));
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct GelfExporter {
+	socket: UdpSocket,
+	host: String,
+	chunk_size: usize,
+	message_id: AtomicU64,
+}
+
+
+impl GelfExporter {
+	/// The default chunk size, suited for WAN links.
+	pub const DEFAULT_CHUNK_SIZE: usize = 1420;
+
+
+	/// Create an exporter sending to the given endpoint. The `host` field of every message
+	/// is set to the given host.
+	pub fn new<A, S>(endpoint: A, host: S) -> io::Result<Self>
+	where
+		A: ToSocketAddrs,
+		S: Into<String>,
+	{
+		let socket = Self::connect(endpoint)?;
+
+		let seed = UNIX_EPOCH
+			.elapsed()
+			.map(|elapsed| elapsed.as_nanos() as u64)
+			.unwrap_or_default();
+
+		Ok(
+			Self {
+				socket,
+				host: host.into(),
+				chunk_size: Self::DEFAULT_CHUNK_SIZE,
+				message_id: AtomicU64::new(seed),
+			}
+		)
+	}
+
+
+	/// Connect a socket to the first usable address of the endpoint, bound to the
+	/// unspecified address of the same family.
+	fn connect<A>(endpoint: A) -> io::Result<UdpSocket>
+	where
+		A: ToSocketAddrs
+	{
+		let mut error = None;
+
+		for address in endpoint.to_socket_addrs()? {
+			let unspecified = match address {
+				SocketAddr::V4(_) => IpAddr::from(Ipv4Addr::UNSPECIFIED),
+				SocketAddr::V6(_) => IpAddr::from(Ipv6Addr::UNSPECIFIED),
+			};
+
+			let socket = UdpSocket::bind((unspecified, 0))
+				.and_then(|socket| socket.connect(address).map(|()| socket));
+
+			match socket {
+				Ok(socket) => return Ok(socket),
+				Err(err) => error = Some(err),
+			}
+		}
+
+		Err(
+			error.unwrap_or_else(
+				|| io::Error::new(io::ErrorKind::NotFound, "gelf endpoint has no address")
+			)
+		)
+	}
+
+
+	/// Set the maximum datagram size. Larger messages are chunked.
+	///
+	/// Panics if the size does not exceed the chunk header size.
+	pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+		assert!(chunk_size > CHUNK_HEADER, "chunk size must exceed the chunk header");
+		self.chunk_size = chunk_size;
+		self
+	}
+
+
+	/// Send the given entries, in order.
+	///
+	/// Sending stops at the first error.
+	pub fn export<I>(&self, entries: I) -> io::Result<()>
+	where
+		I: IntoIterator,
+		I::Item: Borrow<LogEntry>,
+	{
+		for entry in entries {
+			self.send(entry.borrow())?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Send a single entry.
+	pub fn send(&self, entry: &LogEntry) -> io::Result<()> {
+		let payload = self.encode(entry);
+		let payload = payload.as_bytes();
+
+		if payload.len() <= self.chunk_size {
+			self.socket.send(payload)?;
+			return Ok(());
+		}
+
+		let chunk_data = self.chunk_size - CHUNK_HEADER;
+		let count = payload.len().div_ceil(chunk_data);
+
+		if count > MAX_CHUNKS {
+			return Err(
+				io::Error::new(io::ErrorKind::InvalidData, "GELF message too large")
+			);
+		}
+
+		let message_id = self.message_id
+			.fetch_add(1, Ordering::Relaxed)
+			.to_be_bytes();
+
+		let mut datagram = Vec::with_capacity(self.chunk_size);
+
+		for (sequence, chunk) in payload.chunks(chunk_data).enumerate() {
+			datagram.clear();
+			datagram.extend_from_slice(&CHUNK_MAGIC);
+			datagram.extend_from_slice(&message_id);
+			datagram.push(sequence as u8);
+			datagram.push(count as u8);
+			datagram.extend_from_slice(chunk);
+
+			self.socket.send(&datagram)?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Encode an entry as a GELF JSON payload.
+	fn encode(&self, entry: &LogEntry) -> String {
+		let mut payload = String::new();
+
+		self.write_payload(&mut payload, entry)
+			.expect("std::fmt::Write should never fail for String");
+
+		payload
+	}
+
+
+	fn write_payload(&self, out: &mut String, entry: &LogEntry) -> fmt::Result {
+		let timestamp = entry.timestamp
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default();
+
+		out.push_str("{\"version\":\"1.1\",\"host\":");
+		json::write_str(out, &self.host)?;
+
+		out.push_str(",\"short_message\":");
+		json::write_str(out, entry.message())?;
+
+		write!(
+			out,
+			",\"timestamp\":{}.{:06},\"level\":{},\"_target\":",
+			timestamp.as_secs(),
+			timestamp.subsec_micros(),
+			format::severity(entry.level),
+		)?;
+		json::write_str(out, &entry.target)?;
+
+		out.push('}');
+
+		Ok(())
+	}
+}
+
+
+impl Sink for GelfExporter {
+	fn export(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+		GelfExporter::export(self, entries)
+	}
+}
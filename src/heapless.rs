@@ -0,0 +1,302 @@
+//! A memory logger with a fixed capacity, for allocation-averse targets. Entries are kept
+//! in a byte array embedded in the logger, overwriting the oldest entries once full, so
+//! that logging never allocates. The logger may be declared as a `static`, not allocating
+//! at all.
+//!
+//! Entries are rendered as `[target] LEVEL | message`. Entries longer than the capacity are
+//! truncated.
+//!
+//! ```
+//! # use memory_logger::heapless::MemoryLogger;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! static LOGGER: MemoryLogger<64> = MemoryLogger::new(log::Level::Info);
+//!
+//! LOGGER.install()?;
+//!
+//! log::info!(target: "mycrate", "First entry.");
+//! log::info!(target: "mycrate", "Second entry.");
+//!
+//! let mut dump = Vec::new();
+//! LOGGER.dump(&mut dump)?;
+//!
+//! assert_eq!(dump, b"[mycrate] INFO  | Second entry.\n");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+	fmt::{self, Write as _},
+	io::{self, Write},
+	sync::{Mutex, MutexGuard},
+};
+
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+
+use crate::format;
+
+
+/// The length of a record's header: the line length, in little endian.
+const HEADER: usize = 2;
+
+
+/// A memory logger keeping up to `N` bytes of entries. See the [module](index.html) docs.
+#[derive(Debug)]
+pub struct MemoryLogger<const N: usize> {
+	level: Level,
+	ring: Mutex<Ring<N>>,
+}
+
+
+impl<const N: usize> MemoryLogger<N> {
+	/// Create a logger capturing entries up to the given level.
+	pub const fn new(level: Level) -> Self {
+		Self {
+			level,
+			ring: Mutex::new(
+				Ring {
+					bytes: [0; N],
+					head: 0,
+					used: 0,
+					entries: 0,
+				}
+			),
+		}
+	}
+
+
+	/// Install this logger as the global logger. This function should only be called once.
+	pub fn install(&'static self) -> Result<(), SetLoggerError> {
+		log::set_logger(self)?;
+
+		log::set_max_level(
+			self.level.to_level_filter()
+		);
+
+		Ok(())
+	}
+
+
+	/// Dump the contents to a writer, cleaning the buffered contents.
+	pub fn dump<W>(&self, writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let ring = &mut self.lock();
+
+		ring.dump(writer)?;
+		ring.clear();
+
+		Ok(())
+	}
+
+
+	/// Dump the contents to a writer like `dump`, but keeping the buffered contents.
+	pub fn dump_keep<W>(&self, writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		self.lock().dump(writer)
+	}
+
+
+	/// Discard the buffered entries.
+	pub fn clear(&self) {
+		self.lock().clear()
+	}
+
+
+	/// The number of buffered entries.
+	pub fn len(&self) -> usize {
+		self.lock().entries
+	}
+
+
+	/// Whether there are no buffered entries.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+
+	fn lock(&self) -> MutexGuard<'_, Ring<N>> {
+		self.ring
+			.lock()
+			.expect("inner lock poisoned")
+	}
+}
+
+
+impl<const N: usize> Log for MemoryLogger<N> {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level
+	}
+
+
+	fn log(&self, record: &Record) {
+		if self.enabled(record.metadata()) {
+			let mut ring = self.lock();
+
+			let mut writer = match ring.begin() {
+				Some(writer) => writer,
+				None => return,
+			};
+
+			let _ = write!(
+				writer,
+				"[{}] {:<5} | {}",
+				format::record_target(record),
+				record.level(),
+				record.args()
+			);
+
+			writer.commit();
+		}
+	}
+
+
+	fn flush(&self) { }
+}
+
+
+/// A ring of records, each made of a header followed by the line. Records may wrap around
+/// the end of the array.
+#[derive(Debug)]
+struct Ring<const N: usize> {
+	bytes: [u8; N],
+	/// The offset of the oldest record.
+	head: usize,
+	/// The number of bytes in use.
+	used: usize,
+	entries: usize,
+}
+
+
+impl<const N: usize> Ring<N> {
+	/// Start a new record, or `None` if the capacity cannot hold any line.
+	fn begin(&mut self) -> Option<RecordWriter<'_, N>> {
+		if N <= HEADER {
+			return None;
+		}
+
+		self.reserve(HEADER);
+
+		let start = (self.head + self.used) % N;
+		self.used += HEADER;
+
+		Some(
+			RecordWriter {
+				ring: self,
+				start,
+				len: 0,
+			}
+		)
+	}
+
+
+	/// Evict the oldest records until there are `len` free bytes.
+	fn reserve(&mut self, len: usize) {
+		while N - self.used < len {
+			let record = HEADER + usize::from(u16::from_le_bytes([self.byte(0), self.byte(1)]));
+
+			self.head = (self.head + record) % N;
+			self.used -= record;
+			self.entries -= 1;
+		}
+	}
+
+
+	/// The byte at the given offset from the head.
+	fn byte(&self, offset: usize) -> u8 {
+		self.bytes[(self.head + offset) % N]
+	}
+
+
+	/// The bytes in the given range of offsets from the head, in up to two slices.
+	fn slices(&self, start: usize, len: usize) -> (&[u8], &[u8]) {
+		let start = (self.head + start) % N;
+		let first = len.min(N - start);
+
+		(&self.bytes[start .. start + first], &self.bytes[.. len - first])
+	}
+
+
+	fn dump<W>(&self, mut writer: W) -> io::Result<()>
+	where
+		W: Write
+	{
+		let mut offset = 0;
+
+		for _ in 0 .. self.entries {
+			let len = usize::from(u16::from_le_bytes([self.byte(offset), self.byte(offset + 1)]));
+			let (first, second) = self.slices(offset + HEADER, len);
+
+			writer.write_all(first)?;
+			writer.write_all(second)?;
+			writer.write_all(b"\n")?;
+
+			offset += HEADER + len;
+		}
+
+		Ok(())
+	}
+
+
+	fn clear(&mut self) {
+		self.head = 0;
+		self.used = 0;
+		self.entries = 0;
+	}
+}
+
+
+/// Writes a record's line into the ring, evicting the oldest records as needed.
+struct RecordWriter<'a, const N: usize> {
+	ring: &'a mut Ring<N>,
+	/// The offset of the record's header in the array.
+	start: usize,
+	len: usize,
+}
+
+
+impl<'a, const N: usize> RecordWriter<'a, N> {
+	/// The longest line that fits in the ring.
+	const MAX: usize = if N.saturating_sub(HEADER) < u16::MAX as usize {
+		N.saturating_sub(HEADER)
+	} else {
+		u16::MAX as usize
+	};
+
+
+	/// Write the line's length, completing the record.
+	fn commit(self) {
+		let len = (self.len as u16).to_le_bytes();
+
+		self.ring.bytes[self.start] = len[0];
+		self.ring.bytes[(self.start + 1) % N] = len[1];
+		self.ring.entries += 1;
+	}
+}
+
+
+impl<'a, const N: usize> fmt::Write for RecordWriter<'a, N> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		// Truncate at a char boundary, so that lines remain valid UTF-8.
+		let mut end = s.len().min(Self::MAX - self.len);
+
+		while !s.is_char_boundary(end) {
+			end -= 1;
+		}
+
+		// The record being written is never evicted, as it fits in the ring.
+		self.ring.reserve(end);
+
+		for &byte in &s.as_bytes()[.. end] {
+			let offset = (self.start + HEADER + self.len) % N;
+			self.ring.bytes[offset] = byte;
+			self.len += 1;
+		}
+
+		self.ring.used += end;
+
+		Ok(())
+	}
+}
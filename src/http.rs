@@ -0,0 +1,325 @@
+//! Framework-agnostic glue for serving the buffered entries over HTTP, so that services
+//! may expose their in-memory logs on an admin endpoint.
+//!
+//! Requests are described by their query string, such as `level=warn&since=42`:
+//!
+//! - `level`: the most verbose level to include, such as `warn` for errors and warnings.
+//! - `target`: only include entries whose target starts with the given prefix.
+//! - `since`: only include entries whose sequence number is at least the given one.
+//! - `limit`: only include the newest given number of entries.
+//!
+//! Responses are JSON objects with the matching `entries`, oldest first, and the `next`
+//! sequence number to pass as `since` to poll for newer entries:
+//!
+//! ```json
+//! {"entries":[{"sequence":42,"timestamp":"2024-01-01T00:00:00.000000Z","level":"WARN",
+//! "target":"mycrate","message":"Retrying.","line":"[mycrate] WARN  | Retrying."}],"next":43}
+//! ```
+//!
+//! Invalid queries are answered with status 400 and a JSON object with an `error` message.
+
+use std::{
+	borrow::Cow,
+	fmt::Write,
+	str::FromStr,
+};
+
+use log::Level;
+
+use crate::{json, LogEntry};
+
+
+/// A request for the buffered entries, parsed from a query string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Request {
+	level: Option<Level>,
+	target: Option<String>,
+	since: Option<u64>,
+	limit: Option<usize>,
+}
+
+
+impl Request {
+	/// Parse a query string, with or without the leading `?`. Unknown parameters are
+	/// ignored.
+	///
+	/// ```
+	/// # use memory_logger::http::Request;
+	/// assert!(Request::parse("?level=warn&target=mycrate%3A%3Anet&since=42").is_ok());
+	/// assert!(Request::parse("level=loud").is_err());
+	/// ```
+	pub fn parse(query: &str) -> Result<Self, String> {
+		let mut request = Self::default();
+
+		let pairs = query
+			.trim_start_matches('?')
+			.split('&')
+			.filter(|pair| !pair.is_empty())
+			.map(|pair| pair.split_once('=').unwrap_or((pair, "")));
+
+		for (key, value) in pairs {
+			let value = decode(value);
+
+			match key {
+				"level" => request.level = Some(parse(key, &value)?),
+				"target" => request.target = Some(value.into_owned()),
+				"since" => request.since = Some(parse(key, &value)?),
+				"limit" => request.limit = Some(parse(key, &value)?),
+				_ => (),
+			}
+		}
+
+		Ok(request)
+	}
+
+
+	/// Whether the entry matches the level, target and since parameters.
+	fn matches(&self, entry: &LogEntry) -> bool {
+		self.level.map_or(true, |level| entry.level <= level)
+			&& self.target.as_ref().map_or(true, |target| entry.target.starts_with(target.as_str()))
+			&& self.since.map_or(true, |since| entry.sequence >= since)
+	}
+
+
+	/// Render the response for the given entries, oldest first.
+	pub fn respond<'a, I>(&self, entries: I) -> Response
+	where
+		I: IntoIterator<Item = &'a LogEntry>
+	{
+		let entries: Vec<_> = entries
+			.into_iter()
+			.filter(|entry| self.matches(entry))
+			.collect();
+
+		let skip = self.limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+		let entries = &entries[skip ..];
+
+		let next = entries
+			.last()
+			.map(|entry| entry.sequence + 1)
+			.or(self.since)
+			.unwrap_or(0);
+
+		let mut body = String::from("{\"entries\":[");
+
+		for (index, entry) in entries.iter().enumerate() {
+			if index > 0 {
+				body.push(',');
+			}
+
+			json::write_entry(&mut body, entry)
+				.expect("std::fmt::Write should never fail for String");
+		}
+
+		write!(body, "],\"next\":{}}}", next)
+			.expect("std::fmt::Write should never fail for String");
+
+		Response::json(200, body)
+	}
+}
+
+
+/// An HTTP response, to be sent by the web framework in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+	status: u16,
+	body: Vec<u8>,
+}
+
+
+impl Response {
+	fn json(status: u16, body: String) -> Self {
+		Self {
+			status,
+			body: body.into_bytes(),
+		}
+	}
+
+
+	/// A 400 response for an invalid query.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn bad_request(message: &str) -> Self {
+		let mut body = String::from("{\"error\":");
+
+		json::write_str(&mut body, message)
+			.expect("std::fmt::Write should never fail for String");
+
+		body.push('}');
+
+		Self::json(400, body)
+	}
+
+
+	/// The status code.
+	pub fn status(&self) -> u16 {
+		self.status
+	}
+
+
+	/// The content type, which is always JSON.
+	pub fn content_type(&self) -> &'static str {
+		"application/json"
+	}
+
+
+	/// The body.
+	pub fn body(&self) -> &[u8] {
+		&self.body
+	}
+
+
+	/// Take the body.
+	pub fn into_body(self) -> Vec<u8> {
+		self.body
+	}
+}
+
+
+/// Serve a request from the given entries, oldest first. This is used by the flavors'
+/// `serve`.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn serve<'a, I>(query: &str, entries: I) -> Response
+where
+	I: IntoIterator<Item = &'a LogEntry>
+{
+	match Request::parse(query) {
+		Ok(request) => request.respond(entries),
+		Err(message) => Response::bad_request(&message),
+	}
+}
+
+
+fn parse<T>(key: &str, value: &str) -> Result<T, String>
+where
+	T: FromStr
+{
+	value
+		.parse()
+		.map_err(|_| format!("invalid {}: {:?}", key, value))
+}
+
+
+/// Decode a percent-encoded query value, where `+` stands for a space.
+fn decode(value: &str) -> Cow<'_, str> {
+	if !value.contains(['%', '+']) {
+		return Cow::Borrowed(value);
+	}
+
+	let mut bytes = Vec::with_capacity(value.len());
+	let mut rest = value.as_bytes();
+
+	while let Some((&byte, tail)) = rest.split_first() {
+		let hex = tail
+			.get(.. 2)
+			.and_then(|hex| std::str::from_utf8(hex).ok())
+			.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+		match (byte, hex) {
+			(b'%', Some(decoded)) => {
+				bytes.push(decoded);
+				rest = &tail[2 ..];
+				continue;
+			}
+
+			(b'+', _) => bytes.push(b' '),
+
+			(byte, _) => bytes.push(byte),
+		}
+
+		rest = tail;
+	}
+
+	Cow::Owned(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+
+/// A logger that can serve its buffered entries over HTTP. This is implemented by both
+/// flavors' `MemoryLogger`, for use by the framework handlers.
+pub trait Serve: Sync {
+	/// Serve a request for the buffered entries, described by its query string. The
+	/// buffered contents are kept.
+	fn serve_http(&self, query: &str) -> Response;
+}
+
+
+/// An axum router serving `GET /logs` from the given logger.
+///
+/// ```
+/// # #[cfg(feature = "blocking")]
+/// # use memory_logger::blocking::MemoryLogger;
+/// # #[cfg(not(feature = "blocking"))]
+/// # use memory_logger::asynchronous::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+///
+/// let admin: axum::Router = axum::Router::new()
+///     .merge(memory_logger::http::router(logger));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "axum")]
+pub fn router<L, S>(logger: &'static L) -> axum::Router<S>
+where
+	L: Serve + 'static,
+	S: Clone + Send + Sync + 'static,
+{
+	use axum::{
+		extract::RawQuery,
+		http::{header, StatusCode},
+		response::IntoResponse,
+		routing::get,
+	};
+
+	let handler = move |RawQuery(query): RawQuery| async move {
+		let response = logger.serve_http(query.as_deref().unwrap_or(""));
+
+		(
+			StatusCode::from_u16(response.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+			[(header::CONTENT_TYPE, response.content_type())],
+			response.into_body(),
+		)
+			.into_response()
+	};
+
+	axum::Router::new().route("/logs", get(handler))
+}
+
+
+/// An actix-web resource serving `GET /logs` from the given logger.
+///
+/// ```
+/// # #[cfg(feature = "blocking")]
+/// # use memory_logger::blocking::MemoryLogger;
+/// # #[cfg(not(feature = "blocking"))]
+/// # use memory_logger::asynchronous::MemoryLogger;
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+///
+/// let admin = actix_web::App::new()
+///     .service(memory_logger::http::resource(logger));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "actix")]
+pub fn resource<L>(logger: &'static L) -> actix_web::Resource
+where
+	L: Serve + 'static,
+{
+	use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
+
+	let handler = move |request: HttpRequest| {
+		let response = logger.serve_http(request.query_string());
+
+		async move {
+			HttpResponse::build(
+				StatusCode::from_u16(response.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+			)
+				.content_type(response.content_type())
+				.body(response.into_body())
+		}
+	};
+
+	web::resource("/logs").route(web::get().to(handler))
+}
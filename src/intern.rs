@@ -0,0 +1,43 @@
+//! Interning of targets, so that entries of the same target share a single allocation
+//! instead of each holding a copy of the module path.
+//!
+//! Targets are interned per thread, so that logging doesn't contend on a lock.
+
+use std::{
+	cell::RefCell,
+	collections::HashSet,
+	sync::Arc,
+};
+
+
+/// How many targets are interned per thread. Targets are module paths in practice, so this
+/// is only reached if they are built dynamically, in which case further targets are copied.
+const CAPACITY: usize = 1024;
+
+
+/// Get a shared copy of a target.
+pub(crate) fn intern(target: &str) -> Arc<str> {
+	thread_local! {
+		static TARGETS: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+	}
+
+	TARGETS
+		.try_with(
+			|targets| {
+				let mut targets = targets.borrow_mut();
+
+				if let Some(interned) = targets.get(target) {
+					return interned.clone();
+				}
+
+				let interned: Arc<str> = target.into();
+
+				if targets.len() < CAPACITY {
+					targets.insert(interned.clone());
+				}
+
+				interned
+			}
+		)
+		.unwrap_or_else(|_| target.into())
+}
@@ -0,0 +1,66 @@
+use std::{
+	io::{self, Write},
+	os::unix::net::UnixDatagram,
+};
+
+use log::Level;
+
+use crate::format;
+
+
+/// The socket for the native journal protocol.
+const SOCKET: &str = "/run/systemd/journal/socket";
+
+
+/// A connection to the systemd journal.
+#[derive(Debug)]
+pub(crate) struct Journal {
+	socket: UnixDatagram,
+	datagram: Vec<u8>,
+}
+
+
+impl Journal {
+	pub(crate) fn connect() -> io::Result<Self> {
+		let socket = UnixDatagram::unbound()?;
+		socket.connect(SOCKET)?;
+
+		Ok(
+			Self {
+				socket,
+				datagram: Vec::new(),
+			}
+		)
+	}
+
+
+	/// Send an entry with the `MESSAGE`, `PRIORITY` and `CODE_MODULE` fields.
+	pub(crate) fn send(&mut self, level: Level, target: &str, message: &str) -> io::Result<()> {
+		self.datagram.clear();
+
+		write_field(&mut self.datagram, "MESSAGE", message)?;
+		write_field(
+			&mut self.datagram,
+			"PRIORITY",
+			&format::severity(level).to_string()
+		)?;
+		write_field(&mut self.datagram, "CODE_MODULE", target)?;
+
+		self.socket.send(&self.datagram)?;
+
+		Ok(())
+	}
+}
+
+
+/// Write a field in the native journal protocol. Values containing newlines are written
+/// in the binary form, prefixed by their length.
+fn write_field(out: &mut Vec<u8>, name: &str, value: &str) -> io::Result<()> {
+	if value.contains('\n') {
+		writeln!(out, "{}", name)?;
+		out.write_all(&(value.len() as u64).to_le_bytes())?;
+		writeln!(out, "{}", value)
+	} else {
+		writeln!(out, "{}={}", name, value)
+	}
+}
@@ -0,0 +1,346 @@
+use std::fmt::{self, Write};
+
+use crate::{format, LogEntry};
+
+
+/// Write a string as a quoted and escaped JSON string.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn write_str<W>(out: &mut W, value: &str) -> fmt::Result
+where
+	W: Write
+{
+	out.write_char('"')?;
+
+	for c in value.chars() {
+		match c {
+			'"' => out.write_str("\\\"")?,
+			'\\' => out.write_str("\\\\")?,
+			'\n' => out.write_str("\\n")?,
+			'\r' => out.write_str("\\r")?,
+			'\t' => out.write_str("\\t")?,
+			c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+			c => out.write_char(c)?,
+		}
+	}
+
+	out.write_char('"')
+}
+
+
+/// Write an entry as a JSON object, with its sequence, RFC 3339 timestamp, level, target,
+/// message and rendered line, followed by its key-value pairs, if any.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) fn write_entry<W>(out: &mut W, entry: &LogEntry) -> fmt::Result
+where
+	W: Write
+{
+	write!(out, "{{\"sequence\":{},\"timestamp\":\"", entry.sequence)?;
+	format::write_rfc3339(out, entry.timestamp)?;
+	write!(out, "\",\"level\":\"{}\",\"target\":", entry.level)?;
+	write_str(out, &entry.target)?;
+	out.write_str(",\"message\":")?;
+	write_str(out, entry.message())?;
+	out.write_str(",\"line\":")?;
+	write_str(out, &entry.line)?;
+
+	let mut key_values = entry.key_values().peekable();
+
+	if key_values.peek().is_some() {
+		out.write_str(",\"key_values\":{")?;
+
+		for (i, (key, value)) in key_values.enumerate() {
+			if i > 0 {
+				out.write_char(',')?;
+			}
+
+			write_str(out, key)?;
+			out.write_char(':')?;
+			write_str(out, value)?;
+		}
+
+		out.write_char('}')?;
+	}
+
+	out.write_char('}')
+}
+
+
+/// A parsed JSON value. Numbers are kept as text, so that integers are parsed losslessly.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+	Null,
+	Bool(bool),
+	Number(String),
+	String(String),
+	Array(Vec<Value>),
+	Object(Vec<(String, Value)>),
+}
+
+
+impl Value {
+	/// The value of the given field, if this is an object with such field.
+	pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+		match self {
+			Self::Object(fields) => fields
+				.iter()
+				.find(|(field, _)| field == key)
+				.map(|(_, value)| value),
+			_ => None,
+		}
+	}
+
+
+	pub(crate) fn as_str(&self) -> Option<&str> {
+		match self {
+			Self::String(string) => Some(string),
+			_ => None,
+		}
+	}
+
+
+	pub(crate) fn as_u64(&self) -> Option<u64> {
+		match self {
+			Self::Number(number) => number.parse().ok(),
+			_ => None,
+		}
+	}
+}
+
+
+/// How deeply arrays and objects may be nested, so that untrusted documents can't
+/// overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+
+/// Parse a JSON document. Returns `None` if it is invalid, or nested too deeply.
+pub(crate) fn parse(text: &str) -> Option<Value> {
+	let mut parser = Parser { text, position: 0, depth: 0 };
+
+	let value = parser.value()?;
+	parser.whitespace();
+
+	if parser.position == text.len() {
+		Some(value)
+	} else {
+		None
+	}
+}
+
+
+struct Parser<'a> {
+	text: &'a str,
+	position: usize,
+	depth: usize,
+}
+
+
+impl<'a> Parser<'a> {
+	fn peek(&self) -> Option<u8> {
+		self.text
+			.as_bytes()
+			.get(self.position)
+			.copied()
+	}
+
+
+	fn whitespace(&mut self) {
+		while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+			self.position += 1;
+		}
+	}
+
+
+	/// Consume the given text, if it follows.
+	fn eat(&mut self, expected: &str) -> bool {
+		let found = self.text[self.position ..].starts_with(expected);
+
+		if found {
+			self.position += expected.len();
+		}
+
+		found
+	}
+
+
+	fn value(&mut self) -> Option<Value> {
+		self.whitespace();
+
+		match self.peek()? {
+			b'{' => self.nested(Self::object),
+			b'[' => self.nested(Self::array),
+			b'"' => self.string().map(Value::String),
+			b'-' | b'0' ..= b'9' => self.number(),
+			_ if self.eat("null") => Some(Value::Null),
+			_ if self.eat("true") => Some(Value::Bool(true)),
+			_ if self.eat("false") => Some(Value::Bool(false)),
+			_ => None,
+		}
+	}
+
+
+	/// Parse an array or object, one level deeper.
+	fn nested(&mut self, parse: fn(&mut Self) -> Option<Value>) -> Option<Value> {
+		if self.depth == MAX_DEPTH {
+			return None;
+		}
+
+		self.depth += 1;
+		let value = parse(self);
+		self.depth -= 1;
+
+		value
+	}
+
+
+	fn object(&mut self) -> Option<Value> {
+		self.position += 1;
+
+		let mut fields = Vec::new();
+
+		self.whitespace();
+
+		if self.eat("}") {
+			return Some(Value::Object(fields));
+		}
+
+		loop {
+			self.whitespace();
+
+			if self.peek()? != b'"' {
+				return None;
+			}
+
+			let key = self.string()?;
+
+			self.whitespace();
+
+			if !self.eat(":") {
+				return None;
+			}
+
+			fields.push((key, self.value()?));
+
+			self.whitespace();
+
+			if self.eat("}") {
+				return Some(Value::Object(fields));
+			}
+
+			if !self.eat(",") {
+				return None;
+			}
+		}
+	}
+
+
+	fn array(&mut self) -> Option<Value> {
+		self.position += 1;
+
+		let mut values = Vec::new();
+
+		self.whitespace();
+
+		if self.eat("]") {
+			return Some(Value::Array(values));
+		}
+
+		loop {
+			values.push(self.value()?);
+
+			self.whitespace();
+
+			if self.eat("]") {
+				return Some(Value::Array(values));
+			}
+
+			if !self.eat(",") {
+				return None;
+			}
+		}
+	}
+
+
+	fn number(&mut self) -> Option<Value> {
+		let start = self.position;
+
+		while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0' ..= b'9') = self.peek() {
+			self.position += 1;
+		}
+
+		let number = &self.text[start .. self.position];
+
+		number
+			.parse::<f64>()
+			.ok()
+			.map(|_| Value::Number(number.into()))
+	}
+
+
+	fn string(&mut self) -> Option<String> {
+		self.position += 1;
+
+		let mut string = String::new();
+
+		loop {
+			let rest = &self.text[self.position ..];
+			let end = rest.find(&['"', '\\'][..])?;
+
+			string.push_str(&rest[.. end]);
+			self.position += end + 1;
+
+			if rest.as_bytes()[end] == b'"' {
+				return Some(string);
+			}
+
+			let escaped = match self.peek()? {
+				b'"' => '"',
+				b'\\' => '\\',
+				b'/' => '/',
+				b'b' => '\u{8}',
+				b'f' => '\u{c}',
+				b'n' => '\n',
+				b'r' => '\r',
+				b't' => '\t',
+				b'u' => {
+					let high = self.code_unit()?;
+
+					let code = if (0xD800 .. 0xDC00).contains(&high) {
+						if !self.eat("\\") {
+							return None;
+						}
+
+						let low = self.code_unit()?;
+
+						if !(0xDC00 .. 0xE000).contains(&low) {
+							return None;
+						}
+
+						0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+					} else {
+						high
+					};
+
+					string.push(char::from_u32(code)?);
+					continue;
+				}
+				_ => return None,
+			};
+
+			string.push(escaped);
+			self.position += 1;
+		}
+	}
+
+
+	/// Parse the `uXXXX` of a unicode escape.
+	fn code_unit(&mut self) -> Option<u32> {
+		if !self.eat("u") {
+			return None;
+		}
+
+		let digits = self.text.get(self.position .. self.position + 4)?;
+		self.position += 4;
+
+		u32::from_str_radix(digits, 16).ok()
+	}
+}
@@ -0,0 +1,257 @@
+use std::{
+	error::Error,
+	fmt::{self, Write},
+};
+
+use log::SetLoggerError;
+use tracing_core::{
+	dispatcher::{self, SetGlobalDefaultError},
+	field::{Field, Visit},
+	span,
+	Dispatch,
+	Event,
+	Level,
+	Subscriber,
+};
+use tracing_subscriber::{
+	layer::{Context, SubscriberExt},
+	registry::LookupSpan,
+	Layer,
+	Registry,
+};
+
+
+/// A `tracing-subscriber` layer that forwards `tracing` events to the installed memory
+/// logger, so that they are buffered in the same storage as `log` records. This eases
+/// migrating from `log` to `tracing` while keeping memory_logger as the capture backend.
+///
+/// Events are rendered as messages prefixed by their span context, from the outermost span,
+/// and followed by their fields, as in `request{id=7}: Logged in. user=bob`. The logger's
+/// level and target filters apply to events as they do to records.
+///
+/// ```
+/// # use regex::Regex;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, MemoryLayer};
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// let subscriber = tracing_subscriber::registry().with(MemoryLayer::new());
+///
+/// tracing::subscriber::with_default(
+///     subscriber,
+///     || {
+///         let span = tracing::info_span!("request", id = 7);
+///         let _entered = span.enter();
+///
+///         tracing::info!(target: "mycrate", user = "bob", "Logged in.");
+///     }
+/// );
+///
+/// assert_eq!(logger.read().to_string(), "[mycrate] INFO  | request{id=7}: Logged in. user=bob\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryLayer {
+	_private: (),
+}
+
+
+impl MemoryLayer {
+	/// Create a layer forwarding events to the installed logger.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+
+impl<S> Layer<S> for MemoryLayer
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_new_span(&self, attributes: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+		let span = match ctx.span(id) {
+			Some(span) => span,
+			None => return,
+		};
+
+		let mut fields = Fields::default();
+		attributes.record(&mut fields);
+
+		span.extensions_mut().insert(fields);
+	}
+
+
+	fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+		if let Some(span) = ctx.span(id) {
+			let mut extensions = span.extensions_mut();
+
+			if let Some(fields) = extensions.get_mut::<Fields>() {
+				values.record(fields);
+			}
+		}
+	}
+
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		let metadata = event.metadata();
+		let level = log_level(*metadata.level());
+
+		let log_metadata = log::Metadata::builder()
+			.level(level)
+			.target(metadata.target())
+			.build();
+
+		let logger = log::logger();
+
+		if !logger.enabled(&log_metadata) {
+			return;
+		}
+
+		let mut message = String::new();
+
+		for span in ctx.event_scope(event).into_iter().flat_map(|scope| scope.from_root()) {
+			let extensions = span.extensions();
+			let fields = extensions.get::<Fields>();
+
+			let _ = match fields.filter(|fields| !fields.fields.is_empty()) {
+				Some(fields) => write!(message, "{}{{{}}}: ", span.name(), fields.fields),
+				None => write!(message, "{}: ", span.name()),
+			};
+		}
+
+		let mut fields = Fields::default();
+		event.record(&mut fields);
+
+		message.push_str(&fields.message);
+
+		if !fields.fields.is_empty() {
+			if !fields.message.is_empty() {
+				message.push(' ');
+			}
+
+			message.push_str(&fields.fields);
+		}
+
+		logger.log(
+			&log::Record::builder()
+				.metadata(log_metadata)
+				.args(format_args!("{}", message))
+				.module_path(metadata.module_path())
+				.file(metadata.file())
+				.line(metadata.line())
+				.build()
+		);
+	}
+}
+
+
+/// An error setting up a logger with a `tracing` bridge.
+#[derive(Debug)]
+pub enum BridgeError {
+	/// The logger could not be installed, as another one was already installed.
+	Logger(SetLoggerError),
+	/// The `tracing` subscriber could not be installed, as another one was already
+	/// installed. The logger remains installed.
+	Tracing(SetGlobalDefaultError),
+}
+
+
+impl fmt::Display for BridgeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Logger(error) => write!(f, "failed to install the logger: {}", error),
+			Self::Tracing(error) => write!(f, "failed to install the tracing subscriber: {}", error),
+		}
+	}
+}
+
+
+impl Error for BridgeError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			Self::Logger(error) => Some(error),
+			Self::Tracing(error) => Some(error),
+		}
+	}
+}
+
+
+impl From<SetLoggerError> for BridgeError {
+	fn from(error: SetLoggerError) -> Self {
+		Self::Logger(error)
+	}
+}
+
+
+impl From<SetGlobalDefaultError> for BridgeError {
+	fn from(error: SetGlobalDefaultError) -> Self {
+		Self::Tracing(error)
+	}
+}
+
+
+/// Install a global `tracing` subscriber forwarding events through a `MemoryLayer`.
+pub(crate) fn install_subscriber() -> Result<(), SetGlobalDefaultError> {
+	let subscriber = Registry::default().with(MemoryLayer::new());
+
+	dispatcher::set_global_default(
+		Dispatch::new(subscriber)
+	)
+}
+
+
+/// The fields of an event or span: the message, if any, and the others as `key=value`
+/// pairs.
+#[derive(Debug, Default)]
+struct Fields {
+	message: String,
+	fields: String,
+}
+
+
+impl Fields {
+	/// Separate the next field from the previous ones, if any.
+	fn separate(&mut self) {
+		if !self.fields.is_empty() {
+			self.fields.push(' ');
+		}
+	}
+}
+
+
+impl Visit for Fields {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "message" {
+			self.message.push_str(value);
+		} else {
+			self.separate();
+			let _ = write!(self.fields, "{}={}", field.name(), value);
+		}
+	}
+
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		if field.name() == "message" {
+			let _ = write!(self.message, "{:?}", value);
+		} else {
+			self.separate();
+			let _ = write!(self.fields, "{}={:?}", field.name(), value);
+		}
+	}
+}
+
+
+/// The `log` level matching a `tracing` level.
+fn log_level(level: Level) -> log::Level {
+	match level {
+		Level::ERROR => log::Level::Error,
+		Level::WARN => log::Level::Warn,
+		Level::INFO => log::Level::Info,
+		Level::DEBUG => log::Level::Debug,
+		_ => log::Level::Trace,
+	}
+}
@@ -11,11 +11,236 @@ One should **not** attempt to use both flavors simultaneously.
 - `blocking`: enables the `blocking` module. Should not be used with `asynchronous`.
 - `asynchronous`: enables the `asynchronous` module. Should not be used with `blocking`.
 - `target`: enables the `target` regex parameter for both flavors, allowing filtering
-  logs by target (module name).
+  logs by target (module name), and `setup_routed`, partitioning entries into separate
+  loggers by target.
+- `redact`: enables `Config::redact`, which scrubs sensitive data from messages using
+  regex rules before they are buffered.
+- `kv`: captures the structured key-value pairs of records, through `log`'s `kv` feature.
+  They are available through `LogEntry::key_values`, and rendered before messages.
+- `actix`: enables `http::resource`, an actix-web resource serving the buffered entries.
+  Implies `http`.
+- `axum`: enables `http::router`, an axum router serving the buffered entries. Implies
+  `http`.
+- `control`: enables the `control` module and `serve_uds` for both flavors, accepting
+  commands such as `dump` and `set-level debug` over a Unix domain socket. Only available
+  on unix.
+- `crypto`: enables the `crypto` module and `dump_encrypted` for both flavors, encrypting
+  entries with AES-256-GCM on dump, or at rest in a `Storage`.
+- `framing`: enables the `framing` module and `dump_framed` for both flavors, streaming
+  entries as frames over byte-oriented links, such as UART or RTT.
+- `heapless`: enables the `heapless` module, a logger with a fixed capacity that never
+  allocates, for embedded and other allocation-averse targets.
+- `gelf`: enables the `GelfExporter`, which ships entries to Graylog over UDP.
+- `log4rs`: enables the `MemoryAppender` and `setup_appender` for both flavors, which
+  buffers records as a log4rs appender.
+- `macros`: enables the `test` attribute, which wraps test functions, capturing the
+  entries they log in a `TestCapture`.
+- `loki`: enables the `LokiExporter`, which pushes entries to Grafana Loki over HTTP.
+- `mmap`: enables the `mmap` module, which keeps retained entries in a crash-safe ring
+  file.
+- `otel`: enables the `OtelExporter`, which emits entries as OpenTelemetry log records.
+- `parking_lot`: uses `parking_lot` locks in the `blocking` flavor, which are faster under
+  contention and cannot be poisoned by a panicking thread.
+- `sentry`: enables `add_breadcrumbs` for both flavors, which adds the most recent entries
+  as Sentry breadcrumbs.
+- `serde`: implements `Serialize` and `Deserialize` for `LogEntry`, so that entries may
+  be persisted, transmitted and reloaded in any `serde` format.
+- `slog`: enables the `MemoryDrain`, a `slog` drain which buffers `slog` records through
+  the installed logger.
+- `snapshot`: enables the `Normalizer` and `dump_normalized` for both flavors, which
+  replace volatile fields of dumps, such as timestamps, with placeholders for snapshot
+  testing.
+- `http`: enables the `http` module and `serve_http` for both flavors, answering
+  framework-agnostic requests for the buffered entries with JSON.
+- `journald`: enables `dump_to_journal` for both flavors, which writes entries to the
+  systemd journal. Only available on unix.
+- `sqlite`: enables the `SqliteStorage`, which keeps retained entries in an SQLite
+  database.
+- `sse`: enables the `sse` module and `events` for the asynchronous flavor, which streams
+  entries as Server-Sent Events. Implies `asynchronous`.
+- `stream`: enables `stream` for the asynchronous flavor, which consumes entries as a
+  `futures_core::Stream`. Implies `asynchronous`.
+- `tokio`: enables `updates` for both flavors, a `tokio::sync::watch` receiver announcing
+  new entries.
+- `tracing`: enables the `MemoryLayer`, a `tracing-subscriber` layer which buffers
+  `tracing` events, with their span context, through the installed logger.
+- `tui`: enables the `tui` module, a ratatui widget rendering entries as a log console.
+- `wasm`: enables `dump_to_console` for both flavors, which writes entries to the browser
+  console, and timestamps entries with the JavaScript clock. Only available on
+  `wasm32-unknown-unknown`.
+- `ws`: enables the `ws` module and `serve_ws` for the asynchronous flavor, which pushes
+  entries to WebSocket viewers as they are logged. Implies `asynchronous`.
+
+# WebAssembly
+Both flavors support `wasm32-unknown-unknown`, where there are no threads: the asynchronous
+flavor moves retained entries out of the channel on reads instead of in a background
+thread. Operations that wait for entries, or that spawn threads, are not available there:
+`export`, `serve_tcp`, `wait_for`, and for the asynchronous flavor `recv_timeout`,
+`serve_ws` and `Subscription::recv`, as well as the `ws` module and `LokiExporter::spawn`.
+The `wasm` feature is required there for timestamps, as the system clock is not available
+in the browser.
+
+# Formats
+Entries are rendered as `[target] LEVEL | message` by default. Other formats, such as
+RFC 5424 syslog, may be selected through [`Config`](struct.Config.html).
+
+Rendering may be deferred out of the logging path by selecting `Format::Message`, which
+keeps only the messages, and rendering entries in the desired format once read, through
+`LogEntry::render` or the flavors' `dump_as`.
+
+# Exporting
+Entries may be exported periodically by a background worker, through a
+[`Pipeline`](struct.Pipeline.html) of [`Sink`](trait.Sink.html)s. The `GelfExporter` and
+`LokiExporter` are sinks.
+
+The compiled feature set may be inspected at runtime through [`capabilities`](fn.capabilities.html).
 */
 
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm")))]
+compile_error!("the `wasm` feature is required on wasm32-unknown-unknown, for timestamps");
+
+#[cfg(feature = "log4rs")]
+mod appender;
+#[cfg(feature = "blocking")]
+mod arena;
+#[cfg(feature = "asynchronous")]
+mod batch;
+mod capabilities;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod capture;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod composable;
+mod clock;
+mod config;
+#[cfg(all(feature = "wasm", target_arch = "wasm32", target_os = "unknown"))]
+mod console;
+mod entry;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod file;
+mod format;
+mod intern;
+mod metrics;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod persist;
+#[cfg(feature = "asynchronous")]
+mod pool;
+mod process;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod query;
+#[cfg(any(feature = "blocking", feature = "asynchronous", feature = "crypto", feature = "mmap"))]
+mod record;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod retention;
+#[cfg(all(feature = "target", any(feature = "blocking", feature = "asynchronous")))]
+mod routing;
+mod schema;
+mod sink;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+mod storage;
+mod summary;
+#[cfg(all(feature = "macros", any(feature = "blocking", feature = "asynchronous")))]
+mod testing;
+#[cfg(all(
+	any(feature = "blocking", feature = "asynchronous"),
+	not(all(target_arch = "wasm32", target_os = "unknown")),
+))]
+mod tail;
+#[cfg(feature = "slog")]
+mod drain;
+#[cfg(feature = "gelf")]
+mod gelf;
+#[cfg(all(feature = "journald", unix, any(feature = "blocking", feature = "asynchronous")))]
+mod journald;
+mod json;
+#[cfg(feature = "tracing")]
+mod layer;
+#[cfg(feature = "loki")]
+mod loki;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "sentry")]
+mod sentry;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "tokio")]
+mod updates;
+mod view;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+mod writer;
+
+#[cfg(feature = "log4rs")]
+pub use appender::MemoryAppender;
+pub use capabilities::{capabilities, Capabilities};
+pub use clock::{Clock, ManualClock};
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+pub use capture::Expectation;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+pub use composable::MemoryLog;
+pub use config::Config;
+pub use entry::{Location, LogEntry, ThreadInfo};
+pub use format::{Format, Syslog};
+pub use metrics::{LevelCounts, MemoryUsage, Metrics, Stats};
+pub use process::Process;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+pub use query::{Hit, Query};
+#[cfg(all(feature = "target", any(feature = "blocking", feature = "asynchronous")))]
+pub use routing::{Routes, Routing};
+pub use schema::Schema;
+pub use sink::{Lines, Pipeline, Sink, Worker};
+#[cfg(feature = "snapshot")]
+pub use snapshot::Normalizer;
+pub use storage::{FileStorage, RingStorage, SpillStorage, Storage, StringStorage};
+pub use summary::Summary;
+#[cfg(all(feature = "macros", any(feature = "blocking", feature = "asynchronous")))]
+pub use testing::{run as __run_test, TestCapture};
+pub use view::View;
+#[cfg(any(feature = "blocking", feature = "asynchronous"))]
+pub use writer::LogWriter;
+#[cfg(feature = "slog")]
+pub use drain::MemoryDrain;
+#[cfg(feature = "gelf")]
+pub use gelf::GelfExporter;
+#[cfg(feature = "tracing")]
+pub use layer::{BridgeError, MemoryLayer};
+#[cfg(feature = "loki")]
+pub use loki::LokiExporter;
+#[cfg(feature = "otel")]
+pub use otel::OtelExporter;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+#[cfg(all(feature = "macros", any(feature = "blocking", feature = "asynchronous")))]
+pub use memory_logger_macros::test;
+
 #[cfg(feature = "blocking")]
 pub mod blocking;
 
 #[cfg(feature = "asynchronous")]
 pub mod asynchronous;
+
+#[cfg(feature = "framing")]
+pub mod framing;
+
+#[cfg(feature = "mmap")]
+pub mod mmap;
+
+#[cfg(all(feature = "control", unix))]
+pub mod control;
+
+#[cfg(feature = "crypto")]
+pub mod crypto;
+
+#[cfg(feature = "heapless")]
+pub mod heapless;
+
+#[cfg(feature = "http")]
+pub mod http;
+
+#[cfg(feature = "sse")]
+pub mod sse;
+
+#[cfg(feature = "tui")]
+pub mod tui;
+
+#[cfg(all(feature = "ws", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+pub mod ws;
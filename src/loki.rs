@@ -0,0 +1,315 @@
+use std::{
+	borrow::Borrow,
+	collections::BTreeMap,
+	fmt::{self, Write as _},
+	io::{self, Read, Write},
+	net::{TcpStream, ToSocketAddrs},
+	time::{Duration, UNIX_EPOCH},
+};
+
+use log::Level;
+
+use crate::{json, LogEntry, Sink};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::Worker;
+
+
+/// The default path of Loki's push API.
+const PUSH_PATH: &str = "/loki/api/v1/push";
+
+
+/// The label set from each entry's level.
+const LEVEL_LABEL: &str = "level";
+
+
+/// An exporter that pushes log entries to Grafana Loki's push API.
+///
+/// Entries are grouped in streams by level, each labeled with the configured labels plus
+/// a `level` label. Only plain HTTP is supported: HTTPS endpoints should be reached through
+/// a local agent or proxy.
+///
+/// ```
+/// # use regex::Regex;
+/// # use std::io::{Read, Write};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, LokiExporter};
+/// # let loki = std::net::TcpListener::bind("127.0.0.1:0")?;
+/// # let endpoint = format!("http://{}", loki.local_addr()?);
+/// # let server = std::thread::spawn(move || {
+/// #     let (mut stream, _) = loki.accept().unwrap();
+/// #     stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+/// #     stream.shutdown(std::net::Shutdown::Write).unwrap();
+/// #     let mut request = String::new();
+/// #     stream.read_to_string(&mut request).unwrap();
+/// #     request
+/// # });
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// let exporter = LokiExporter::new(&endpoint)?.label("app", "myapp");
+///
+/// log::info!(target: "mycrate", "This is a info.");
+///
+/// exporter.push(logger.drain())?;
+/// # let request = server.join().unwrap();
+/// # assert!(request.starts_with("POST /loki/api/v1/push HTTP/1.1\r\n"));
+/// # assert!(request.contains(r#"{"stream":{"app":"myapp","level":"info"},"values":[["#));
+/// # assert!(request.contains(r#"","[mycrate] INFO  | This is a info."]]}"#));
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LokiExporter {
+	host: String,
+	path: String,
+	labels: BTreeMap<String, String>,
+	timeout: Duration,
+	max_pending: usize,
+}
+
+
+impl LokiExporter {
+	/// Create an exporter pushing to the given endpoint, such as `http://localhost:3100`.
+	/// If the endpoint has no path, Loki's default push path is used.
+	///
+	/// Fails if the endpoint is not a plain HTTP url.
+	pub fn new(endpoint: &str) -> io::Result<Self> {
+		let address = endpoint
+			.strip_prefix("http://")
+			.ok_or_else(
+				|| io::Error::new(io::ErrorKind::InvalidInput, "only http endpoints are supported")
+			)?;
+
+		let (host, path) = match address.find('/') {
+			Some(index) if index + 1 < address.len() => address.split_at(index),
+			Some(index) => (&address[.. index], PUSH_PATH),
+			None => (address, PUSH_PATH),
+		};
+
+		Ok(
+			Self {
+				host: host.into(),
+				path: path.into(),
+				labels: BTreeMap::new(),
+				timeout: Duration::from_secs(10),
+				max_pending: 10_000,
+			}
+		)
+	}
+
+
+	/// Add a label to every pushed stream.
+	///
+	/// Panics if the key is `level`, which is reserved for the entries' level.
+	pub fn label<K, V>(mut self, key: K, value: V) -> Self
+	where
+		K: Into<String>,
+		V: Into<String>,
+	{
+		let key = key.into();
+		assert!(key != LEVEL_LABEL, "the level label is reserved");
+
+		self.labels.insert(key, value.into());
+		self
+	}
+
+
+	/// Set the timeout of connecting to Loki, and of each read and write of a push.
+	/// Defaults to 10 seconds.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+
+	/// Set how many entries the background thread keeps for retrying while pushes fail.
+	/// Beyond that, the oldest entries are dropped. Defaults to 10000.
+	pub fn max_pending(mut self, max_pending: usize) -> Self {
+		self.max_pending = max_pending;
+		self
+	}
+
+
+	/// Push the given entries in a single request. Nothing is sent if there are no entries.
+	pub fn push<I>(&self, entries: I) -> io::Result<()>
+	where
+		I: IntoIterator,
+		I::Item: Borrow<LogEntry>,
+	{
+		let mut streams: BTreeMap<Level, Vec<I::Item>> = BTreeMap::new();
+
+		for entry in entries {
+			streams
+				.entry(entry.borrow().level)
+				.or_default()
+				.push(entry);
+		}
+
+		if streams.is_empty() {
+			return Ok(());
+		}
+
+		let mut body = String::new();
+		self.write_body(&mut body, &streams)
+			.expect("std::fmt::Write should never fail for String");
+
+		self.post(&body)
+	}
+
+
+	/// Spawn a background thread that periodically drains entries through the given
+	/// function and pushes them. This is usually `move || logger.drain()` for the blocking
+	/// flavor, or `move || logger.read_entries().collect()` for the asynchronous flavor.
+	///
+	/// If a push fails, its entries are retried along with the next batch, up to
+	/// [`max_pending`](#method.max_pending) entries. The thread is stopped through the
+	/// returned handle, after a final push.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	pub fn spawn<F>(self, interval: Duration, mut drain: F) -> io::Result<Worker>
+	where
+		F: FnMut() -> Vec<LogEntry> + Send + 'static
+	{
+		let mut pending = Vec::new();
+
+		Worker::spawn(
+			"memory_logger_loki",
+			interval,
+			move || {
+				pending.extend(drain());
+
+				let excess = pending.len().saturating_sub(self.max_pending);
+				pending.drain(.. excess);
+
+				if self.push(&pending).is_ok() {
+					pending.clear();
+				}
+			}
+		)
+	}
+
+
+	fn write_body<E>(&self, out: &mut String, streams: &BTreeMap<Level, Vec<E>>) -> fmt::Result
+	where
+		E: Borrow<LogEntry>
+	{
+		out.push_str("{\"streams\":[");
+
+		for (index, (level, entries)) in streams.iter().enumerate() {
+			if index > 0 {
+				out.push(',');
+			}
+
+			out.push_str("{\"stream\":{");
+
+			for (key, value) in &self.labels {
+				json::write_str(out, key)?;
+				out.push(':');
+				json::write_str(out, value)?;
+				out.push(',');
+			}
+
+			json::write_str(out, LEVEL_LABEL)?;
+			out.push(':');
+			json::write_str(out, &level.as_str().to_lowercase())?;
+
+			out.push_str("},\"values\":[");
+
+			for (index, entry) in entries.iter().enumerate() {
+				let entry = entry.borrow();
+
+				let timestamp = entry.timestamp
+					.duration_since(UNIX_EPOCH)
+					.unwrap_or_default();
+
+				if index > 0 {
+					out.push(',');
+				}
+
+				write!(out, "[\"{}\",", timestamp.as_nanos())?;
+				json::write_str(out, entry.as_str())?;
+				out.push(']');
+			}
+
+			out.push_str("]}");
+		}
+
+		out.push_str("]}");
+
+		Ok(())
+	}
+
+
+	fn post(&self, body: &str) -> io::Result<()> {
+		let mut stream = self.connect()?;
+
+		write!(
+			stream,
+			"POST {} HTTP/1.1\r\n\
+			 Host: {}\r\n\
+			 Content-Type: application/json\r\n\
+			 Content-Length: {}\r\n\
+			 Connection: close\r\n\
+			 \r\n",
+			self.path,
+			self.host,
+			body.len()
+		)?;
+		stream.write_all(body.as_bytes())?;
+		stream.flush()?;
+
+		let mut response = Vec::new();
+		stream.read_to_end(&mut response)?;
+
+		let status = response
+			.split(|&byte| byte == b' ')
+			.nth(1)
+			.unwrap_or_default();
+
+		if status.first() == Some(&b'2') {
+			Ok(())
+		} else {
+			let status_line = response
+				.split(|&byte| byte == b'\r')
+				.next()
+				.unwrap_or_default();
+
+			Err(
+				io::Error::other(
+					format!("loki push failed: {}", String::from_utf8_lossy(status_line))
+				)
+			)
+		}
+	}
+
+
+	/// Connect to the first reachable address of the host.
+	fn connect(&self) -> io::Result<TcpStream> {
+		let mut error = None;
+
+		for address in self.host.to_socket_addrs()? {
+			match TcpStream::connect_timeout(&address, self.timeout) {
+				Ok(stream) => {
+					stream.set_read_timeout(Some(self.timeout))?;
+					stream.set_write_timeout(Some(self.timeout))?;
+
+					return Ok(stream);
+				}
+				Err(err) => error = Some(err),
+			}
+		}
+
+		Err(
+			error.unwrap_or_else(
+				|| io::Error::new(io::ErrorKind::NotFound, "loki host has no address")
+			)
+		)
+	}
+}
+
+
+impl Sink for LokiExporter {
+	fn export(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+		self.push(entries)
+	}
+}
@@ -0,0 +1,280 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Write},
+	sync::{Mutex, MutexGuard},
+	time::SystemTime,
+};
+
+#[cfg(feature = "asynchronous")]
+use std::borrow::Borrow;
+
+use log::Level;
+
+#[cfg(feature = "asynchronous")]
+use crate::LogEntry;
+
+
+/// Log entry counts for each level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelCounts([u64; 5]);
+
+
+impl LevelCounts {
+	/// The count for the given level.
+	pub fn get(&self, level: Level) -> u64 {
+		self.0[index(level)]
+	}
+
+
+	/// The count for all levels.
+	pub fn total(&self) -> u64 {
+		self.0.iter().sum()
+	}
+
+
+	/// Iterate over the levels and their counts, from error to trace.
+	pub fn iter(&self) -> impl Iterator<Item = (Level, u64)> + '_ {
+		Level::iter().map(move |level| (level, self.get(level)))
+	}
+
+
+	fn add(&mut self, other: &Self) {
+		for (count, other) in self.0.iter_mut().zip(&other.0) {
+			*count += other;
+		}
+	}
+}
+
+
+/// A snapshot of the counts of captured entries, by level and target.
+///
+/// Counts are cumulative: they are not affected by clearing or dumping the buffer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+	levels: LevelCounts,
+	targets: BTreeMap<String, LevelCounts>,
+}
+
+
+impl Metrics {
+	/// The counts by level, for all targets.
+	pub fn levels(&self) -> &LevelCounts {
+		&self.levels
+	}
+
+
+	/// The counts by level, for each target.
+	pub fn targets(&self) -> &BTreeMap<String, LevelCounts> {
+		&self.targets
+	}
+
+
+	/// Encode the counts in the Prometheus text exposition format, as the
+	/// `memory_logger_entries_total` counter, labeled by level and target.
+	pub fn to_prometheus(&self) -> String {
+		let mut text = String::new();
+
+		self.write_prometheus(&mut text)
+			.expect("std::fmt::Write should never fail for String");
+
+		text
+	}
+
+
+	fn write_prometheus(&self, out: &mut String) -> fmt::Result {
+		out.push_str("# HELP memory_logger_entries_total Log entries captured, by level and target.\n");
+		out.push_str("# TYPE memory_logger_entries_total counter\n");
+
+		for (target, counts) in &self.targets {
+			for (level, count) in counts.iter() {
+				write!(
+					out,
+					"memory_logger_entries_total{{level=\"{}\",target=\"",
+					level.as_str().to_lowercase()
+				)?;
+
+				for c in target.chars() {
+					match c {
+						'\\' => out.push_str("\\\\"),
+						'"' => out.push_str("\\\""),
+						'\n' => out.push_str("\\n"),
+						c => out.push(c),
+					}
+				}
+
+				writeln!(out, "\"}} {}", count)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+
+/// Counts by level and target, updated as entries are captured.
+#[derive(Debug, Default)]
+pub(crate) struct Tally(HashMap<Box<str>, LevelCounts>);
+
+
+impl Tally {
+	pub(crate) fn record(&mut self, level: Level, target: &str) {
+		let counts = match self.0.get_mut(target) {
+			Some(counts) => counts,
+			None => self.0.entry(target.into()).or_default(),
+		};
+
+		counts.0[index(level)] += 1;
+	}
+
+
+	#[cfg_attr(not(feature = "blocking"), allow(dead_code))]
+	pub(crate) fn remove(&mut self, level: Level, target: &str) {
+		if let Some(counts) = self.0.get_mut(target) {
+			counts.0[index(level)] -= 1;
+
+			if counts.total() == 0 {
+				self.0.remove(target);
+			}
+		}
+	}
+
+
+	pub(crate) fn snapshot(&self) -> Metrics {
+		let mut metrics = Metrics::default();
+
+		for (target, counts) in &self.0 {
+			metrics.levels.add(counts);
+			metrics.targets.insert(target.to_string(), *counts);
+		}
+
+		metrics
+	}
+}
+
+
+/// Live counters, updated when entries are logged.
+#[derive(Debug, Default)]
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+pub(crate) struct Counters(Mutex<Tally>);
+
+
+impl Counters {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	fn lock(&self) -> MutexGuard<'_, Tally> {
+		self.0
+			.lock()
+			.expect("metrics lock poisoned")
+	}
+
+
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn record(&self, level: Level, target: &str) {
+		self.lock().record(level, target)
+	}
+
+
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn snapshot(&self) -> Metrics {
+		self.lock().snapshot()
+	}
+}
+
+
+/// Statistics of the buffered entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+	pub(crate) counts: Metrics,
+	pub(crate) first: Option<SystemTime>,
+	pub(crate) last: Option<SystemTime>,
+}
+
+
+impl Stats {
+	/// Compute the statistics of the given entries, oldest first.
+	#[cfg(feature = "asynchronous")]
+	pub(crate) fn of<I>(entries: I) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Borrow<LogEntry>,
+	{
+		let mut tally = Tally::default();
+		let mut stats = Self::default();
+
+		for entry in entries {
+			let entry = entry.borrow();
+
+			tally.record(entry.level, &entry.target);
+
+			stats.first.get_or_insert(entry.timestamp);
+			stats.last = Some(entry.timestamp);
+		}
+
+		stats.counts = tally.snapshot();
+
+		stats
+	}
+
+
+	/// The number of buffered entries.
+	pub fn len(&self) -> u64 {
+		self.counts.levels.total()
+	}
+
+
+	/// Whether there are no buffered entries.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+
+	/// The counts by level, for all targets.
+	pub fn levels(&self) -> &LevelCounts {
+		self.counts.levels()
+	}
+
+
+	/// The counts by level, for each target.
+	pub fn targets(&self) -> &BTreeMap<String, LevelCounts> {
+		self.counts.targets()
+	}
+
+
+	/// When the oldest buffered entry was logged.
+	pub fn first(&self) -> Option<SystemTime> {
+		self.first
+	}
+
+
+	/// When the newest buffered entry was logged.
+	pub fn last(&self) -> Option<SystemTime> {
+		self.last
+	}
+}
+
+
+/// The memory used by the buffered entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+	pub(crate) bytes: usize,
+	pub(crate) entries: usize,
+}
+
+
+impl MemoryUsage {
+	/// The total length of the buffered lines, in bytes.
+	pub fn bytes(&self) -> usize {
+		self.bytes
+	}
+
+
+	/// The number of buffered entries.
+	pub fn entries(&self) -> usize {
+		self.entries
+	}
+}
+
+
+/// The position of a level in per-level arrays, from error to trace.
+pub(crate) fn index(level: Level) -> usize {
+	level as usize - 1
+}
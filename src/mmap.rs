@@ -0,0 +1,474 @@
+/*!
+A crash-safe ring file, holding the most recent entries in a fixed-size memory-mapped file.
+
+Since entries are written directly to the mapped file, the most recent window survives
+the process being killed, and may be recovered by another process through
+[`recover`](fn.recover.html). Surviving a power loss requires flushing the mapping.
+
+The file may also be placed in shared memory, such as under `/dev/shm` on Linux, so that
+other processes can attach to it through a [`Reader`](struct.Reader.html) and read the
+logs of the running process.
+
+The file starts with a header, followed by the ring of records. All integers are little
+endian. The header is:
+
+- magic (8 bytes), `MLRING01`.
+- capacity of the ring, in bytes (8 bytes).
+- offset of the oldest record in the ring (8 bytes).
+- offset of the next record in the ring (8 bytes).
+- number of records (8 bytes).
+- generation (8 bytes), which is odd while the ring is being modified.
+
+Each record is:
+
+- record length (4 bytes), including this field. Zero marks that the ring wraps around.
+- sequence (8 bytes).
+- timestamp, in microseconds since the unix epoch (8 bytes), or `0` if unknown.
+- level (1 byte), from `1` (error) to `5` (trace).
+- offset of the message in the line (4 bytes).
+- target length (2 bytes), followed by the target bytes.
+- line bytes, up to the end of the record.
+
+Records never straddle the end of the ring: the ring wraps around when fewer than 4 bytes
+are left, or at a zero record length.
+
+While a storage is writing the file, other processes may only read it. Writing to it,
+truncating it or creating another storage over it meanwhile is undefined behavior, both
+for the storage and for attached readers.
+*/
+
+use std::{
+	collections::VecDeque,
+	fs::{self, File, OpenOptions},
+	io,
+	ops::Range,
+	path::Path,
+	sync::atomic::{self, AtomicU64, Ordering},
+	thread,
+};
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::{record, LogEntry, Storage};
+
+
+const MAGIC: &[u8; 8] = b"MLRING01";
+/// The header length, in bytes.
+const HEADER: usize = 48;
+/// The offset of the generation in the header.
+const GENERATION: usize = 40;
+/// How many times a reader retries while the ring is being modified.
+const ATTEMPTS: usize = 1000;
+
+
+/// A storage writing entries to a fixed-size ring file, evicting the oldest entries once
+/// full. See the [module](index.html) documentation.
+///
+/// Entries that do not fit in the ring, or whose target is longer than 64 KiB, are
+/// skipped. The ring does not hold the location, thread or key-value pairs of entries, so
+/// entries read back lack them, and key-value pairs rendered as JSON end up in the message.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "asynchronous")] {
+/// # use memory_logger::{asynchronous::MemoryLogger, mmap::{self, MmapStorage}, Config};
+/// let path = std::env::temp_dir().join("memory_logger.ring");
+/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+///     .storage(MmapStorage::create(&path, 64 * 1024)?);
+/// let logger = MemoryLogger::setup_with(config)?;
+///
+/// log::info!(target: "mycrate", "Connected.");
+/// # logger.peek();
+///
+/// // After a crash, from another process:
+/// let entries = mmap::recover(&path)?;
+///
+/// assert_eq!(entries[0].message(), "Connected.");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MmapStorage {
+	map: MmapMut,
+	capacity: usize,
+	/// The offset of the next record.
+	head: usize,
+	/// The offset, length and line length of the records, oldest first.
+	records: VecDeque<(usize, usize, usize)>,
+	bytes: usize,
+}
+
+
+impl MmapStorage {
+	/// Create a ring file at the given path with a ring of `capacity` bytes. Existing files
+	/// are overwritten.
+	pub fn create<P>(path: P, capacity: usize) -> io::Result<Self>
+	where
+		P: AsRef<Path>
+	{
+		let file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.open(path)?;
+
+		file.set_len((HEADER + capacity) as u64)?;
+
+		// Safety: a mapping may only be modified through itself while mapped. Other processes
+		// only map the file for reading, through `Reader`, and the storage never resizes it.
+		// Modifying it otherwise is unsupported, as stated in the module documentation.
+		let map = unsafe { MmapMut::map_mut(&file)? };
+
+		let mut storage = Self {
+			map,
+			capacity,
+			head: 0,
+			records: VecDeque::new(),
+			bytes: 0,
+		};
+
+		storage.map[.. 8].copy_from_slice(MAGIC);
+		storage.write_u64(8, capacity as u64);
+		storage.commit();
+
+		Ok(storage)
+	}
+
+
+	/// Flush the mapping to the file, so that it survives a power loss.
+	pub fn flush(&self) -> io::Result<()> {
+		self.map.flush()
+	}
+
+
+	fn ring(&mut self) -> &mut [u8] {
+		&mut self.map[HEADER ..]
+	}
+
+
+	fn write_u64(&mut self, offset: usize, value: u64) {
+		self.map[offset .. offset + 8].copy_from_slice(&value.to_le_bytes());
+	}
+
+
+	/// Write the offsets of the oldest and next records, and the number of records, to the
+	/// header.
+	fn commit(&mut self) {
+		let tail = self.records
+			.front()
+			.map_or(self.head, |&(offset, _, _)| offset);
+
+		self.write_u64(16, tail as u64);
+		self.write_u64(24, self.head as u64);
+		self.write_u64(32, self.records.len() as u64);
+	}
+
+
+	/// The generation counter, which is odd while the ring is being modified.
+	fn generation(&mut self) -> &AtomicU64 {
+		// Safety: the file is at least a header long, so the counter is in bounds, and the
+		// mapping is page aligned, so the counter is aligned. It is only accessed atomically,
+		// and the mutable borrow keeps slices of the mapping from overlapping it.
+		unsafe { AtomicU64::from_ptr(self.map.as_mut_ptr().add(GENERATION).cast()) }
+	}
+
+
+	/// Mark the start of a modification, for concurrent readers.
+	fn begin(&mut self) {
+		self.generation().fetch_add(1, Ordering::Relaxed);
+		atomic::fence(Ordering::Release);
+	}
+
+
+	/// Mark the end of a modification, for concurrent readers.
+	fn end(&mut self) {
+		self.generation().fetch_add(1, Ordering::Release);
+	}
+
+
+	/// Evict the oldest record.
+	fn pop(&mut self) {
+		if let Some((_, _, line)) = self.records.pop_front() {
+			self.bytes -= line;
+		}
+	}
+}
+
+
+impl Storage for MmapStorage {
+	fn append(&mut self, entry: LogEntry) {
+		let len = match record::len(&entry) {
+			Some(len) if len <= self.capacity => len,
+			_ => return,
+		};
+
+		self.begin();
+
+		let previous = self.head;
+		let wrap = self.capacity - previous < len;
+		let offset = if wrap { 0 } else { previous };
+
+		// Evict the records in the way, which are the oldest ones.
+		while let Some(&(front, front_len, _)) = self.records.front() {
+			let skipped = wrap && front >= previous;
+			let overlaps = front < offset + len && offset < front + front_len;
+
+			if !skipped && !overlaps {
+				break;
+			}
+
+			self.pop();
+		}
+
+		// The oldest record must be committed before it may be overwritten.
+		self.commit();
+
+		if wrap && self.capacity - previous >= 4 {
+			self.ring()[previous .. previous + 4].copy_from_slice(&0u32.to_le_bytes());
+		}
+
+		record::encode(&entry, &mut self.ring()[offset .. offset + len]);
+
+		self.records.push_back((offset, len, entry.line.len()));
+		self.bytes += entry.line.len();
+		self.head = offset + len;
+
+		self.commit();
+		self.end();
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		let ring = &self.map[HEADER ..];
+
+		Box::new(
+			self.records
+				.iter()
+				.filter_map(move |&(offset, len, _)| record::decode(&ring[offset .. offset + len]))
+		)
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		self.begin();
+
+		for _ in 0 .. count {
+			self.pop();
+		}
+
+		self.commit();
+		self.end();
+	}
+
+
+	fn clear(&mut self) {
+		self.begin();
+
+		self.records.clear();
+		self.bytes = 0;
+
+		self.commit();
+		self.end();
+	}
+
+
+	fn len(&self) -> usize {
+		self.records.len()
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.bytes
+	}
+}
+
+
+/// Recover the entries of the ring file at the given path, oldest first. The file is not
+/// modified.
+///
+/// Fails if the file is not a ring file. Recovery stops at the first invalid record.
+pub fn recover<P>(path: P) -> io::Result<Vec<LogEntry>>
+where
+	P: AsRef<Path>
+{
+	parse(&fs::read(path)?)
+}
+
+
+/// A reader attached to a ring file that is being written by another process, such as
+/// one in shared memory.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "asynchronous")] {
+/// # use memory_logger::{asynchronous::MemoryLogger, mmap::{MmapStorage, Reader}, Config};
+/// # let path = std::env::temp_dir().join("memory_logger_shared.ring");
+/// // In the running process, with a path such as /dev/shm/myapp.ring:
+/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+///     .storage(MmapStorage::create(&path, 64 * 1024)?);
+/// let logger = MemoryLogger::setup_with(config)?;
+///
+/// log::info!(target: "mycrate", "Connected.");
+/// # logger.peek();
+///
+/// // In a sidecar process:
+/// let reader = Reader::attach(&path)?;
+///
+/// assert_eq!(reader.read()?[0].message(), "Connected.");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Reader {
+	map: Mmap,
+}
+
+
+impl Reader {
+	/// Attach to the ring file at the given path.
+	///
+	/// Fails if the file is not a ring file.
+	pub fn attach<P>(path: P) -> io::Result<Self>
+	where
+		P: AsRef<Path>
+	{
+		let file = File::open(path)?;
+
+		// Safety: the writer modifies the file while it is mapped, so the mapping is never
+		// borrowed as a slice, which would assume it immutable. It is only read through raw
+		// pointers, in `generation` and `copy`, and copies made while the writer modifies the
+		// ring are discarded. The writer never resizes the file.
+		let map = unsafe { Mmap::map(&file)? };
+
+		let reader = Self { map };
+
+		if reader.map.len() < HEADER || reader.copy(0 .. 8) != MAGIC {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "not a ring file"));
+		}
+
+		Ok(reader)
+	}
+
+
+	/// Read the entries in the ring, oldest first.
+	///
+	/// Fails with `WouldBlock` if the ring is continuously being modified, or if its writer
+	/// crashed while modifying it, in which case [`recover`](fn.recover.html) may be used.
+	pub fn read(&self) -> io::Result<Vec<LogEntry>> {
+		for _ in 0 .. ATTEMPTS {
+			let before = self.generation();
+
+			if before % 2 == 0 {
+				atomic::fence(Ordering::Acquire);
+
+				let copy = self.copy(0 .. self.map.len());
+
+				atomic::fence(Ordering::Acquire);
+
+				if self.generation() == before {
+					return parse(&copy);
+				}
+			}
+
+			thread::yield_now();
+		}
+
+		Err(io::Error::new(io::ErrorKind::WouldBlock, "ring is being modified"))
+	}
+
+
+	/// The generation counter. The mapping is read only, so the counter is read volatilely
+	/// instead of atomically.
+	fn generation(&self) -> u64 {
+		// Safety: the mapping is page aligned and at least a header long, so the counter is
+		// in bounds and aligned. Any value is a valid `u64`, and a value read while the writer
+		// modifies the ring is odd or differs from the one read after copying it.
+		let generation = unsafe { self.map.as_ptr().add(GENERATION).cast::<u64>().read_volatile() };
+
+		u64::from_le(generation)
+	}
+
+
+	/// Copy a range of the mapping, which may be modified by the writer meanwhile, in which
+	/// case the copy is discarded. The range must be in bounds of the mapping.
+	fn copy(&self, range: Range<usize>) -> Vec<u8> {
+		assert!(range.end <= self.map.len(), "range out of bounds of the mapping");
+
+		let map = self.map.as_ptr();
+
+		range
+			// Safety: the offsets are in bounds of the mapping, as asserted, and any value is
+			// a valid byte.
+			.map(|offset| unsafe { map.add(offset).read_volatile() })
+			.collect()
+	}
+}
+
+
+/// Parse the entries of a ring file.
+fn parse(file: &[u8]) -> io::Result<Vec<LogEntry>> {
+	let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a ring file");
+
+	let header = file
+		.get(.. HEADER)
+		.ok_or_else(invalid)?;
+
+	if &header[.. 8] != MAGIC {
+		return Err(invalid());
+	}
+
+	let read_u64 = |offset: usize| {
+		let mut bytes = [0; 8];
+		bytes.copy_from_slice(&header[offset .. offset + 8]);
+		u64::from_le_bytes(bytes) as usize
+	};
+
+	let capacity = read_u64(8);
+	let mut offset = read_u64(16);
+	let count = read_u64(32);
+
+	let ring = HEADER
+		.checked_add(capacity)
+		.and_then(|end| file.get(HEADER .. end))
+		.ok_or_else(invalid)?;
+
+	let mut entries = Vec::new();
+	let mut wrapped = false;
+
+	while entries.len() < count {
+		let rest = ring
+			.get(offset ..)
+			.ok_or_else(invalid)?;
+
+		let len = match record::peek_len(rest) {
+			// A corrupted ring could wrap around forever.
+			Some(0) | None if offset == 0 || wrapped => break,
+
+			Some(0) | None => {
+				offset = 0;
+				wrapped = true;
+				continue;
+			}
+
+			Some(len) => len,
+		};
+
+		let entry = rest
+			.get(.. len)
+			.and_then(record::decode);
+
+		match entry {
+			Some(entry) => entries.push(entry),
+			None => break,
+		}
+
+		offset += len;
+	}
+
+	Ok(entries)
+}
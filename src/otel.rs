@@ -0,0 +1,95 @@
+use std::{borrow::Borrow, time::SystemTime};
+
+use log::Level;
+
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, Severity};
+
+use crate::LogEntry;
+
+
+/// An exporter that converts log entries into OpenTelemetry log records, emitting them
+/// through an OpenTelemetry logger.
+///
+/// The logger is usually obtained from an SDK logger provider configured with an OTLP
+/// exporter, allowing memory_logger to act as a buffering front-end for observability
+/// pipelines.
+///
+/// ```
+/// # use memory_logger::{blocking::MemoryLogger, OtelExporter};
+/// # use opentelemetry::logs::{LoggerProvider, NoopLoggerProvider};
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("")?)?;
+/// let provider = NoopLoggerProvider::new(); // Usually an SDK provider with OTLP export.
+/// let exporter = OtelExporter::new(provider.logger("memory_logger"));
+///
+/// log::info!("This is a info.");
+///
+/// exporter.export(logger.drain());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OtelExporter<L> {
+	logger: L,
+}
+
+
+impl<L> OtelExporter<L>
+where
+	L: Logger
+{
+	/// Create an exporter emitting through the given logger.
+	pub fn new(logger: L) -> Self {
+		Self { logger }
+	}
+
+
+	/// Emit the given entries, in order.
+	pub fn export<I>(&self, entries: I)
+	where
+		I: IntoIterator,
+		I::Item: Borrow<LogEntry>,
+	{
+		for entry in entries {
+			self.send(entry.borrow());
+		}
+	}
+
+
+	/// Emit a single entry.
+	///
+	/// The record's timestamp is the entry's timestamp, and its observed timestamp is the
+	/// time of the export. The record's target is the entry's target, and its body is the
+	/// entry's message.
+	pub fn send(&self, entry: &LogEntry) {
+		let mut record = self.logger.create_log_record();
+
+		record.set_timestamp(entry.timestamp);
+		record.set_observed_timestamp(SystemTime::now());
+		record.set_severity_number(severity(entry.level));
+		record.set_severity_text(entry.level.as_str());
+		record.set_target(String::from(&*entry.target));
+		record.set_body(AnyValue::from(entry.message().to_owned()));
+
+		self.logger.emit(record);
+	}
+
+
+	/// The wrapped logger.
+	pub fn logger(&self) -> &L {
+		&self.logger
+	}
+}
+
+
+/// The OpenTelemetry severity for a log level.
+fn severity(level: Level) -> Severity {
+	match level {
+		Level::Error => Severity::Error,
+		Level::Warn => Severity::Warn,
+		Level::Info => Severity::Info,
+		Level::Debug => Severity::Debug,
+		Level::Trace => Severity::Trace,
+	}
+}
@@ -0,0 +1,74 @@
+//! Snapshots of the buffered entries, carried from one run to the next. A snapshot is the
+//! magic `MLSNAP01`, followed by the entries' records.
+
+use std::{
+	ffi::OsString,
+	fs,
+	io,
+	path::{Path, PathBuf},
+};
+
+use crate::{record, LogEntry};
+
+
+const MAGIC: &[u8; 8] = b"MLSNAP01";
+
+
+/// Write a snapshot of the entries to the given path. The snapshot is written to a
+/// temporary file first, so that a crash never leaves a partial snapshot behind.
+pub(crate) fn write<'a, I>(path: &Path, entries: I) -> io::Result<()>
+where
+	I: IntoIterator<Item = &'a LogEntry>
+{
+	let mut bytes = MAGIC.to_vec();
+
+	for entry in entries {
+		if let Some(len) = record::len(entry) {
+			let start = bytes.len();
+			bytes.resize(start + len, 0);
+			record::encode(entry, &mut bytes[start ..]);
+		}
+	}
+
+	let temporary = temporary(path);
+
+	fs::write(&temporary, bytes)?;
+
+	fs::rename(temporary, path)
+}
+
+
+/// Read the entries of the snapshot at the given path. Reading stops at the first invalid
+/// record.
+pub(crate) fn read(path: &Path) -> io::Result<Vec<LogEntry>> {
+	let bytes = fs::read(path)?;
+
+	let mut rest = match bytes.strip_prefix(MAGIC) {
+		Some(rest) => rest,
+		None => return Err(io::Error::new(io::ErrorKind::InvalidData, "not a memory_logger snapshot")),
+	};
+
+	let mut entries = Vec::new();
+
+	while let Some(len) = record::peek_len(rest) {
+		let entry = rest
+			.get(.. len)
+			.and_then(record::decode);
+
+		match entry {
+			Some(entry) => entries.push(entry),
+			None => break,
+		}
+
+		rest = &rest[len ..];
+	}
+
+	Ok(entries)
+}
+
+
+fn temporary(path: &Path) -> PathBuf {
+	let mut name = OsString::from(path);
+	name.push(".tmp");
+	name.into()
+}
@@ -0,0 +1,45 @@
+//! Pooling of entry buffers, so that high-rate capture reuses allocations instead of
+//! allocating a buffer per entry.
+//!
+//! Buffers are pooled per thread, so that logging doesn't contend on a lock: the buffers of
+//! the entries a thread dumps are reused for the entries it logs.
+
+use std::cell::RefCell;
+
+
+/// The largest capacity of a pooled buffer. Larger buffers, left by long entries, are
+/// shrunk before being pooled, so that the pool doesn't hold on to their peak size.
+const MAX_CAPACITY: usize = 1024;
+
+
+thread_local! {
+	static POOL: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+
+/// Get an empty buffer, from the current thread's pool if available.
+pub(crate) fn take() -> String {
+	POOL
+		.try_with(|pool| pool.borrow_mut().pop())
+		.ok()
+		.flatten()
+		.unwrap_or_default()
+}
+
+
+/// Return a buffer to the current thread's pool, unless the pool already holds `size`
+/// buffers.
+pub(crate) fn recycle(mut buffer: String, size: usize) {
+	buffer.clear();
+	buffer.shrink_to(MAX_CAPACITY);
+
+	let _ = POOL.try_with(
+		|pool| {
+			let mut pool = pool.borrow_mut();
+
+			if pool.len() < size {
+				pool.push(buffer);
+			}
+		}
+	);
+}
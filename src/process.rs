@@ -0,0 +1,108 @@
+use std::{env, fmt, fs, path::Path, process};
+
+
+/// Process-level context, written as a header line before the entries of each dump, so
+/// that archived dumps are self-describing. Enabled through
+/// [`Config::process`](struct.Config.html#method.process).
+///
+/// The header lists the known fields, as in:
+///
+/// ```text
+/// # pid=4242 executable=myapp hostname=build-01 version=1.2.0
+/// ```
+///
+/// ```
+/// let process = memory_logger::Process::current().version("1.2.0");
+/// let header = process.to_string();
+///
+/// assert!(header.starts_with("# pid="));
+/// assert!(header.ends_with(" version=1.2.0"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+	pid: Option<u32>,
+	executable: Option<String>,
+	hostname: Option<String>,
+	version: Option<String>,
+}
+
+
+impl Process {
+	/// Capture the context of the current process. Fields that cannot be determined on the
+	/// current platform are left out.
+	pub fn current() -> Self {
+		let pid = if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+			None
+		} else {
+			Some(process::id())
+		};
+
+		let executable = env::current_exe()
+			.ok()
+			.and_then(
+				|path| path
+					.file_name()
+					.map(|name| name.to_string_lossy().into_owned())
+			);
+
+		Self {
+			pid,
+			executable,
+			hostname: hostname(),
+			version: None,
+		}
+	}
+
+
+	/// Set the application version, such as `env!("CARGO_PKG_VERSION")`.
+	pub fn version<S>(mut self, version: S) -> Self
+	where
+		S: Into<String>
+	{
+		self.version = Some(version.into());
+		self
+	}
+}
+
+
+/// Renders the header line, without a trailing newline.
+impl fmt::Display for Process {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("#")?;
+
+		if let Some(pid) = self.pid {
+			write!(f, " pid={}", pid)?;
+		}
+
+		let fields = [
+			("executable", &self.executable),
+			("hostname", &self.hostname),
+			("version", &self.version),
+		];
+
+		for (name, value) in fields.iter() {
+			if let Some(value) = value {
+				write!(f, " {}={}", name, value)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+
+/// The host name, from the kernel on Linux, or the environment elsewhere.
+fn hostname() -> Option<String> {
+	let hostname = fs::read_to_string(Path::new("/proc/sys/kernel/hostname"))
+		.ok()
+		.or_else(|| env::var("HOSTNAME").ok())
+		.or_else(|| env::var("COMPUTERNAME").ok())?;
+
+	let hostname = hostname.trim();
+
+	if hostname.is_empty() {
+		None
+	} else {
+		Some(hostname.into())
+	}
+}
@@ -0,0 +1,212 @@
+use std::{
+	fmt,
+	ops::{Bound, Range, RangeBounds},
+	time::SystemTime,
+};
+
+use log::Level;
+
+#[cfg(feature = "target")]
+use regex::Regex;
+
+use crate::LogEntry;
+
+
+/// A query over the buffered entries, built through a logger's `query` method.
+///
+/// All criteria must match for an entry to be selected. A query without criteria selects
+/// all entries.
+pub struct Query<'a> {
+	source: &'a dyn Source,
+	criteria: Criteria,
+}
+
+
+impl<'a> Query<'a> {
+	pub(crate) fn new(source: &'a dyn Source) -> Self {
+		Self {
+			source,
+			criteria: Criteria::default(),
+		}
+	}
+
+
+	/// Select entries whose level is in the given range. As in the `log` crate, more
+	/// severe levels are lower, so `Level::Error ..= Level::Warn` selects errors and
+	/// warnings.
+	pub fn levels<R>(mut self, range: R) -> Self
+	where
+		R: RangeBounds<Level>
+	{
+		self.criteria.levels = (range.start_bound().cloned(), range.end_bound().cloned());
+		self
+	}
+
+
+	/// Select entries whose target matches the given regex.
+	#[cfg(feature = "target")]
+	pub fn target(mut self, regex: Regex) -> Self {
+		self.criteria.target = Some(regex);
+		self
+	}
+
+
+	/// Select entries whose message contains the given string.
+	pub fn contains<S>(mut self, pattern: S) -> Self
+	where
+		S: Into<String>
+	{
+		self.criteria.contains = Some(pattern.into());
+		self
+	}
+
+
+	/// Select entries whose message matches the given regex.
+	#[cfg(feature = "target")]
+	pub fn message(mut self, regex: Regex) -> Self {
+		self.criteria.message = Some(regex);
+		self
+	}
+
+
+	/// Select entries logged in the given time range.
+	pub fn time<R>(mut self, range: R) -> Self
+	where
+		R: RangeBounds<SystemTime>
+	{
+		self.criteria.time = (range.start_bound().cloned(), range.end_bound().cloned());
+		self
+	}
+
+
+	/// Evaluate the query, returning the selected entries in order.
+	pub fn run(&self) -> Vec<LogEntry> {
+		self.source.select(&self.criteria)
+	}
+}
+
+
+impl fmt::Debug for Query<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Query")
+			.field("criteria", &self.criteria)
+			.finish()
+	}
+}
+
+
+/// The criteria of a query.
+#[derive(Debug, Clone)]
+pub(crate) struct Criteria {
+	levels: (Bound<Level>, Bound<Level>),
+	#[cfg(feature = "target")]
+	target: Option<Regex>,
+	contains: Option<String>,
+	#[cfg(feature = "target")]
+	message: Option<Regex>,
+	time: (Bound<SystemTime>, Bound<SystemTime>),
+}
+
+
+impl Default for Criteria {
+	fn default() -> Self {
+		Self {
+			levels: (Bound::Unbounded, Bound::Unbounded),
+			#[cfg(feature = "target")]
+			target: None,
+			contains: None,
+			#[cfg(feature = "target")]
+			message: None,
+			time: (Bound::Unbounded, Bound::Unbounded),
+		}
+	}
+}
+
+
+impl Criteria {
+	/// Whether an entry with the given parts matches.
+	pub(crate) fn matches(
+		&self,
+		level: Level,
+		target: &str,
+		message: &str,
+		timestamp: SystemTime,
+	) -> bool {
+		#[cfg(feature = "target")]
+		{
+			if self.target.as_ref().is_some_and(|regex| !regex.is_match(target)) {
+				return false;
+			}
+
+			if self.message.as_ref().is_some_and(|regex| !regex.is_match(message)) {
+				return false;
+			}
+		}
+
+		#[cfg(not(feature = "target"))]
+		let _ = target;
+
+		self.levels.contains(&level)
+			&& self.time.contains(&timestamp)
+			&& self.contains
+				.as_ref()
+				.map_or(true, |pattern| message.contains(pattern.as_str()))
+	}
+}
+
+
+/// An entry matching a search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+	pub(crate) index: usize,
+	pub(crate) entry: LogEntry,
+	pub(crate) matches: Vec<Range<usize>>,
+}
+
+
+impl Hit {
+	/// The entry's index. Indices are assigned in logging order, and are not reused when
+	/// the buffer is cleared.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+
+	/// The matching entry.
+	pub fn entry(&self) -> &LogEntry {
+		&self.entry
+	}
+
+
+	/// The byte ranges of the matches in the entry's line.
+	pub fn matches(&self) -> &[Range<usize>] {
+		&self.matches
+	}
+
+
+	/// Search an entry for a pattern, returning a hit if there are any matches. An empty
+	/// pattern matches nothing.
+	pub(crate) fn search(index: usize, entry: LogEntry, pattern: &str) -> Option<Self> {
+		if pattern.is_empty() {
+			return None;
+		}
+
+		let matches: Vec<_> = entry.line
+			.match_indices(pattern)
+			.map(|(start, matched)| start .. start + matched.len())
+			.collect();
+
+		if matches.is_empty() {
+			None
+		} else {
+			Some(Self { index, entry, matches })
+		}
+	}
+}
+
+
+/// A store of entries that can be queried.
+pub(crate) trait Source {
+	/// The entries matching the given criteria, in order.
+	fn select(&self, criteria: &Criteria) -> Vec<LogEntry>;
+}
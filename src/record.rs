@@ -0,0 +1,103 @@
+//! A binary encoding of entries, preserving their metadata. Each record is:
+//!
+//! - record length (4 bytes), including this field.
+//! - sequence (8 bytes).
+//! - timestamp, in microseconds since the unix epoch (8 bytes), or `0` if unknown.
+//! - level (1 byte), from `1` (error) to `5` (trace).
+//! - offset of the message in the line (4 bytes).
+//! - target length (2 bytes), followed by the target bytes.
+//! - line bytes, up to the end of the record.
+//!
+//! All integers are little endian.
+//!
+//! The details of entries are not encoded: their location, thread and key-value pairs are
+//! lost, and key-value pairs rendered as JSON stay in the line, so that they become part
+//! of the decoded message.
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use log::Level;
+
+use crate::{intern::intern, LogEntry};
+
+
+/// The length of the fixed fields of a record, in bytes.
+const FIXED: usize = 27;
+
+
+/// The length of an entry's record, or `None` if the entry cannot be encoded, which is the
+/// case for targets longer than 64 KiB.
+pub(crate) fn len(entry: &LogEntry) -> Option<usize> {
+	if entry.target.len() > usize::from(u16::MAX) {
+		return None;
+	}
+
+	Some(FIXED + entry.target.len() + entry.line.len())
+}
+
+
+/// Encode an entry into a record, which must have the entry's record length.
+pub(crate) fn encode(entry: &LogEntry, record: &mut [u8]) {
+	let timestamp = entry.timestamp
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_micros() as u64);
+
+	let len = record.len() as u32;
+
+	let (fixed, rest) = record.split_at_mut(FIXED);
+	let (target, line) = rest.split_at_mut(entry.target.len());
+
+	fixed[.. 4].copy_from_slice(&len.to_le_bytes());
+	fixed[4 .. 12].copy_from_slice(&entry.sequence.to_le_bytes());
+	fixed[12 .. 20].copy_from_slice(&timestamp.to_le_bytes());
+	fixed[20] = entry.level as u8;
+	fixed[21 .. 25].copy_from_slice(&(entry.message as u32).to_le_bytes());
+	fixed[25 .. 27].copy_from_slice(&(entry.target.len() as u16).to_le_bytes());
+	target.copy_from_slice(entry.target.as_bytes());
+	line.copy_from_slice(entry.line.as_bytes());
+}
+
+
+/// The length of the record starting with the given bytes, if at least 4 bytes are given.
+pub(crate) fn peek_len(bytes: &[u8]) -> Option<usize> {
+	match bytes {
+		&[a, b, c, d, ..] => Some(u32::from_le_bytes([a, b, c, d]) as usize),
+		_ => None,
+	}
+}
+
+
+/// Decode a record. Returns `None` if the record is invalid.
+pub(crate) fn decode(record: &[u8]) -> Option<LogEntry> {
+	let (fixed, rest) = record.split_at_checked(FIXED)?;
+
+	let u64_at = |offset: usize| {
+		let mut bytes = [0; 8];
+		bytes.copy_from_slice(&fixed[offset .. offset + 8]);
+		u64::from_le_bytes(bytes)
+	};
+
+	let level = Level::iter().find(|level| *level as u8 == fixed[20])?;
+	let message = u32::from_le_bytes([fixed[21], fixed[22], fixed[23], fixed[24]]) as usize;
+	let target_len = usize::from(u16::from_le_bytes([fixed[25], fixed[26]]));
+
+	let (target, line) = rest.split_at_checked(target_len)?;
+	let target = std::str::from_utf8(target).ok()?;
+	let line = std::str::from_utf8(line).ok()?;
+
+	if !line.is_char_boundary(message) {
+		return None;
+	}
+
+	Some(
+		LogEntry {
+			sequence: u64_at(4),
+			level,
+			target: intern(target),
+			timestamp: UNIX_EPOCH + Duration::from_micros(u64_at(12)),
+			line: line.into(),
+			message,
+			details: None,
+		}
+	)
+}
@@ -0,0 +1,362 @@
+use std::{
+	collections::{HashMap, VecDeque},
+	fmt,
+	io::Write,
+	sync::{
+		atomic::{AtomicU32, Ordering},
+		Arc,
+		Mutex,
+		MutexGuard,
+	},
+	time::{Duration, SystemTime},
+};
+
+use log::Level;
+
+use crate::{config::FlightRecorder, metrics, Config, Format, LogEntry};
+
+
+/// The retention engine, deciding which entries are buffered. It is shared by both
+/// flavors, which pass every entry through `admit` when any policy is configured.
+#[derive(Debug)]
+pub(crate) struct Retention {
+	recorder: Option<FlightRecorder>,
+
+	/// How long entries are held waiting for an error, if conditional retention is enabled.
+	window: Option<Duration>,
+
+	/// The maximum entries per second for each target, if rate limiting is enabled.
+	rate_limit: Option<u32>,
+
+	/// Whether runs of identical entries are collapsed.
+	deduplicate: bool,
+
+	/// The format used to render markers.
+	format: Format,
+
+	/// Keep one in how many entries, by level.
+	sampling: [u32; 5],
+
+	/// How many entries have been sampled, by level.
+	sampled: [AtomicU32; 5],
+
+	state: Mutex<State>,
+}
+
+
+#[derive(Debug, Default)]
+struct State {
+	/// The most recent verbose entries, kept by the flight recorder.
+	recorded: VecDeque<LogEntry>,
+
+	/// The entries held by conditional retention, oldest first.
+	held: VecDeque<LogEntry>,
+
+	/// When the last error was logged.
+	last_error: Option<SystemTime>,
+
+	/// The rate limiting state of each target.
+	buckets: HashMap<Arc<str>, Bucket>,
+
+	/// The last admitted entry, and how many times it has been repeated since.
+	last: Option<(LogEntry, u64)>,
+}
+
+
+impl State {
+	/// Count an entry against its target's rate limit. Returns `None` if the entry is
+	/// suppressed, or how many entries were suppressed since the last admitted one.
+	fn limit(&mut self, entry: &LogEntry, limit: u32) -> Option<u64> {
+		let bucket = match self.buckets.get_mut(&entry.target) {
+			Some(bucket) => bucket,
+			None => self.buckets
+				.entry(entry.target.clone())
+				.or_insert(
+					Bucket {
+						since: entry.timestamp,
+						count: 0,
+						suppressed: 0,
+					}
+				),
+		};
+
+		let elapsed = entry.timestamp
+			.duration_since(bucket.since)
+			.map_or(true, |elapsed| elapsed >= Duration::from_secs(1));
+
+		if elapsed {
+			bucket.since = entry.timestamp;
+			bucket.count = 0;
+		}
+
+		if bucket.count >= limit {
+			bucket.suppressed += 1;
+			return None;
+		}
+
+		bucket.count += 1;
+
+		Some(std::mem::take(&mut bucket.suppressed))
+	}
+}
+
+
+/// The entries counted for a target in the current one second period.
+#[derive(Debug)]
+struct Bucket {
+	since: SystemTime,
+	count: u32,
+	/// Entries suppressed since the last admitted one.
+	suppressed: u64,
+}
+
+
+impl Retention {
+	/// Build the engine for the configured policies, if any.
+	pub(crate) fn new(config: &Config) -> Option<Self> {
+		let configured = config.recorder.is_some()
+			|| config.window.is_some()
+			|| config.rate_limit.is_some()
+			|| config.deduplicate
+			|| config.sampling.iter().any(|&rate| rate > 1);
+
+		configured.then(
+			|| Self {
+				recorder: config.recorder.clone(),
+				window: config.window,
+				rate_limit: config.rate_limit,
+				deduplicate: config.deduplicate,
+				format: config.format.clone(),
+				sampling: config.sampling,
+				sampled: Default::default(),
+				state: Mutex::default(),
+			}
+		)
+	}
+
+
+	/// Whether an entry with the given level is kept by sampling. This is checked before
+	/// rendering entries, so that dropped entries are cheap.
+	pub(crate) fn sample(&self, level: Level) -> bool {
+		let index = metrics::index(level);
+
+		match self.sampling[index] {
+			0 | 1 => true,
+			rate => self.sampled[index].fetch_add(1, Ordering::Relaxed) % rate == 0,
+		}
+	}
+
+
+	/// Pass an entry through the retention policies, calling `buffer` with the entries
+	/// that should be buffered. Entries synthesized by the policies, such as suppression
+	/// markers, are numbered through `sequence`. A marker buffered before the entry takes
+	/// the entry's sequence number, and the entry is renumbered, so that sequence numbers
+	/// follow the buffer order.
+	pub(crate) fn admit<S, F>(&self, mut entry: LogEntry, sequence: S, mut buffer: F)
+	where
+		S: Fn() -> u64,
+		F: FnMut(LogEntry),
+	{
+		let mut state = self.lock();
+
+		if self.deduplicate {
+			match &mut state.last {
+				Some((last, repeated)) if last.level == entry.level
+					&& last.target == entry.target
+					&& last.message() == entry.message() =>
+				{
+					*repeated += 1;
+					return;
+				}
+
+				_ => (),
+			}
+
+			if let Some((last, repeated @ 1 ..)) = state.last.replace((entry.clone(), 0)) {
+				let marker = self.marker(
+					&last.target,
+					last.timestamp,
+					std::mem::replace(&mut entry.sequence, sequence()),
+					format_args!("last message repeated {} times", repeated)
+				);
+
+				self.retain(&mut state, marker, &mut buffer);
+			}
+		}
+
+		if let Some(limit) = self.rate_limit {
+			match state.limit(&entry, limit) {
+				None => return,
+
+				Some(0) => (),
+
+				Some(suppressed) => {
+					let number = std::mem::replace(&mut entry.sequence, sequence());
+
+					let marker = self.marker(
+						&entry.target,
+						entry.timestamp,
+						number,
+						format_args!("suppressed {} messages", suppressed)
+					);
+
+					self.retain(&mut state, marker, &mut buffer);
+				}
+			}
+		}
+
+		self.retain(&mut state, entry, &mut buffer);
+	}
+
+
+	/// Buffer the markers still pending, so that they are not lost when the buffer is
+	/// dumped, drained or read: the marker for the pending run of repeated entries, and the
+	/// suppression count of every rate limited target. The repeated entry is still
+	/// remembered, so further repeats start a new run.
+	pub(crate) fn flush_pending<S, F>(&self, sequence: S, mut buffer: F)
+	where
+		S: Fn() -> u64,
+		F: FnMut(LogEntry),
+	{
+		let mut state = self.lock();
+
+		let mut markers = Vec::new();
+
+		if let Some((last, repeated @ 1 ..)) = &mut state.last {
+			markers.push(
+				self.marker(
+					&last.target,
+					last.timestamp,
+					sequence(),
+					format_args!("last message repeated {} times", repeated)
+				)
+			);
+
+			*repeated = 0;
+		}
+
+		let mut suppressed: Vec<_> = state.buckets
+			.iter_mut()
+			.filter(|(_, bucket)| bucket.suppressed > 0)
+			.collect();
+
+		suppressed.sort_unstable_by_key(|(target, _)| *target);
+
+		for (target, bucket) in suppressed {
+			markers.push(
+				self.marker(
+					target,
+					bucket.since,
+					sequence(),
+					format_args!("suppressed {} messages", bucket.suppressed)
+				)
+			);
+
+			bucket.suppressed = 0;
+		}
+
+		for marker in markers {
+			self.retain(&mut state, marker, &mut buffer);
+		}
+	}
+
+
+	/// Render a marker entry for the given target.
+	fn marker(
+		&self,
+		target: &str,
+		timestamp: SystemTime,
+		sequence: u64,
+		message: fmt::Arguments
+	) -> LogEntry {
+		LogEntry::render_into(
+			String::new(),
+			&self.format,
+			sequence,
+			Level::Warn,
+			target,
+			None,
+			message,
+			timestamp
+		)
+	}
+
+
+	/// Apply the flight recorder and conditional retention to an entry.
+	fn retain<F>(&self, state: &mut State, entry: LogEntry, buffer: &mut F)
+	where
+		F: FnMut(LogEntry)
+	{
+		if let Some(recorder) = &self.recorder {
+			if entry.level >= Level::Debug {
+				if state.recorded.len() == recorder.capacity {
+					state.recorded.pop_front();
+				}
+
+				if recorder.capacity > 0 {
+					state.recorded.push_back(entry);
+				}
+
+				return;
+			}
+
+			if entry.level == Level::Error {
+				let recorded = std::mem::take(&mut state.recorded);
+
+				// Logging must not fail, so errors writing to the sink are ignored.
+				let _ = flush(&recorder.sink, recorded.iter().chain(Some(&entry)));
+			}
+		}
+
+		if let Some(window) = self.window {
+			let expired = |timestamp: SystemTime| {
+				entry.timestamp
+					.duration_since(timestamp)
+					.is_ok_and(|age| age > window)
+			};
+
+			while state.held.front().is_some_and(|held| expired(held.timestamp)) {
+				state.held.pop_front();
+			}
+
+			if entry.level == Level::Error {
+				state.last_error = Some(entry.timestamp);
+			} else if state.last_error.map_or(true, expired) {
+				state.held.push_back(entry);
+
+				return;
+			}
+
+			state.held
+				.drain(..)
+				.for_each(&mut *buffer);
+		}
+
+		buffer(entry)
+	}
+
+
+	fn lock(&self) -> MutexGuard<'_, State> {
+		self.state
+			.lock()
+			.expect("retention lock poisoned")
+	}
+}
+
+
+/// Write entries to a sink, one per line.
+fn flush<'a, I>(sink: &Mutex<dyn Write + Send>, entries: I) -> std::io::Result<()>
+where
+	I: IntoIterator<Item = &'a LogEntry>
+{
+	let mut sink = sink
+		.lock()
+		.expect("sink lock poisoned");
+
+	for entry in entries {
+		sink.write_all(entry.as_bytes())?;
+		sink.write_all(b"\n")?;
+	}
+
+	sink.flush()
+}
@@ -0,0 +1,199 @@
+use std::{fmt, ops::Index};
+
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+use regex::Regex;
+
+use crate::{Config, MemoryLog};
+
+
+/// Rules partitioning entries into named memory loggers by target, so that subsystems can
+/// be inspected separately. Each route has its own configuration, and thus its own level,
+/// format and capacity caps.
+///
+/// Entries are routed to the first route whose pattern matches their target, or to the
+/// fallback route, if any. Routes are installed through `setup_routed`:
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, Config, Routing};
+/// let routes = MemoryLogger::setup_routed(
+///     Routing::new()
+///         .route("network", Regex::new("^mycrate::net")?, Config::new(log::Level::Debug, Regex::new("")?))
+///         .route("db", Regex::new("^mycrate::db")?, Config::new(log::Level::Info, Regex::new("")?))
+///         .fallback("other", Config::new(log::Level::Warn, Regex::new("")?))
+/// )?;
+///
+/// log::debug!(target: "mycrate::net", "Polling.");
+/// log::info!(target: "mycrate::db", "Connected.");
+/// log::warn!(target: "mycrate::ui", "Slow frame.");
+///
+/// assert_eq!(&*routes["network"].read(), "[mycrate::net] DEBUG | Polling.\n");
+/// assert_eq!(&*routes["db"].read(), "[mycrate::db] INFO  | Connected.\n");
+/// assert_eq!(&*routes["other"].read(), "[mycrate::ui] WARN  | Slow frame.\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// As the routes share the global maximum level, changing the level of a routed logger
+/// sets the maximum level for all of them.
+#[derive(Default)]
+pub struct Routing {
+	routes: Vec<(String, Option<Regex>, Config)>,
+}
+
+
+impl Routing {
+	/// Create a routing without routes.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+
+	/// Add a route for the entries whose target matches the given pattern.
+	pub fn route<S>(mut self, name: S, target: Regex, config: Config) -> Self
+	where
+		S: Into<String>
+	{
+		self.routes.push((name.into(), Some(target), config));
+		self
+	}
+
+
+	/// Add a route for the entries not matched by any other route. Without a fallback route,
+	/// such entries are discarded.
+	pub fn fallback<S>(mut self, name: S, config: Config) -> Self
+	where
+		S: Into<String>
+	{
+		self.routes.push((name.into(), None, config));
+		self
+	}
+
+
+	/// Setup a logger per route through `setup`, and install a logger routing records to
+	/// them.
+	pub(crate) fn install<L, F>(self, mut setup: F) -> Result<Routes<L>, SetLoggerError>
+	where
+		L: 'static,
+		F: FnMut(Config) -> (&'static L, MemoryLog),
+	{
+		let level = self.routes
+			.iter()
+			.map(|(_, _, config)| config.level)
+			.max()
+			.unwrap_or(Level::Error);
+
+		// The fallback route is matched last, wherever it was added.
+		let mut routes = self.routes;
+		routes.sort_by_key(|(_, target, _)| target.is_none());
+
+		let mut router = Router { routes: Vec::new() };
+		let mut loggers = Vec::new();
+
+		for (name, target, config) in routes {
+			let (logger, log) = setup(config);
+
+			router.routes.push((target, log));
+			loggers.push((name, logger));
+		}
+
+		log::set_boxed_logger(Box::new(router))?;
+
+		log::set_max_level(
+			level.to_level_filter()
+		);
+
+		Ok(Routes { loggers })
+	}
+}
+
+
+impl fmt::Debug for Routing {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_list()
+			.entries(self.routes.iter().map(|(name, target, _)| (name, target)))
+			.finish()
+	}
+}
+
+
+/// The memory loggers of installed routes, by name.
+#[derive(Debug)]
+pub struct Routes<L: 'static> {
+	loggers: Vec<(String, &'static L)>,
+}
+
+
+impl<L> Routes<L> {
+	/// The logger of the route with the given name, if any.
+	pub fn get(&self, name: &str) -> Option<&'static L> {
+		self.loggers
+			.iter()
+			.find(|(route, _)| route == name)
+			.map(|(_, logger)| *logger)
+	}
+
+
+	/// The routes, by name, in the order they were added, with the fallback route last.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &'static L)> {
+		self.loggers
+			.iter()
+			.map(|(name, logger)| (name.as_str(), *logger))
+	}
+}
+
+
+/// Gets the logger of the route with the given name.
+///
+/// # Panics
+///
+/// Panics if there is no route with the given name.
+impl<L> Index<&str> for Routes<L> {
+	type Output = L;
+
+	fn index(&self, name: &str) -> &Self::Output {
+		self.get(name)
+			.unwrap_or_else(|| panic!("no route named {:?}", name))
+	}
+}
+
+
+/// The installed logger, forwarding records to the logger of their route.
+struct Router {
+	routes: Vec<(Option<Regex>, MemoryLog)>,
+}
+
+
+impl Router {
+	fn route(&self, target: &str) -> Option<&MemoryLog> {
+		self.routes
+			.iter()
+			.find(|(pattern, _)| pattern.as_ref().map_or(true, |pattern| pattern.is_match(target)))
+			.map(|(_, log)| log)
+	}
+}
+
+
+impl Log for Router {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		self.route(metadata.target())
+			.is_some_and(|log| log.enabled(metadata))
+	}
+
+
+	fn log(&self, record: &Record) {
+		if let Some(log) = self.route(record.target()) {
+			log.log(record);
+		}
+	}
+
+
+	fn flush(&self) {
+		for (_, log) in &self.routes {
+			log.flush();
+		}
+	}
+}
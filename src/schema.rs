@@ -0,0 +1,372 @@
+use std::{
+	convert::TryFrom,
+	io::{self, Read, Write},
+	str::FromStr,
+	time::{Duration, UNIX_EPOCH},
+};
+
+use log::Level;
+
+use crate::{
+	entry::Details,
+	format,
+	intern::intern,
+	json::{self, Value},
+	LogEntry,
+};
+
+
+/// The magic of binary dumps, followed by the version.
+const MAGIC: &[u8; 6] = b"MLDUMP";
+
+/// The `schema` field of the header of JSON dumps.
+const NAME: &str = "memory_logger";
+
+
+/// A versioned, machine-readable format for dumped entries, so that dumps written by one
+/// version of this crate can be read by tooling built against another. Dumps start with a
+/// format-version marker, and are written through the flavors' `dump_schema`.
+///
+/// Readers accept dumps of their version and older ones, and reject newer ones. Within a
+/// version, fields may be added to JSON objects, and readers ignore unknown fields.
+///
+/// ```
+/// use memory_logger::{LogEntry, Schema};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dump = concat!(
+///     "{\"schema\":\"memory_logger\",\"version\":1}\n",
+///     "{\"sequence\":7,\"timestamp\":\"1970-01-01T00:00:01.000000Z\",\"level\":\"WARN\",",
+///     "\"target\":\"mycrate\",\"message\":\"Retrying.\",\"line\":\"[mycrate] WARN  | Retrying.\"}\n",
+/// );
+///
+/// let entries = Schema::read(dump.as_bytes())?;
+///
+/// assert_eq!(entries[0].sequence(), 7);
+/// assert_eq!(entries[0].message(), "Retrying.");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+	/// JSON Lines: a header object with the `schema` name, `memory_logger`, and the
+	/// `version`, followed by an object per entry, with its `sequence`, RFC 3339
+	/// `timestamp`, `level`, `target`, `message`, rendered `line`, and `key_values` object,
+	/// if any.
+	Json,
+	/// The magic `MLDUMP` and the version, as a little endian `u16`, followed by a record
+	/// per entry. Records are made of, with integers in little endian:
+	///
+	/// - the record length, including this field (`u32`).
+	/// - the sequence (`u64`).
+	/// - the timestamp, in microseconds since the unix epoch (`u64`), or `0` if unknown.
+	/// - the level (`u8`), from `1` for error to `5` for trace.
+	/// - the offset and length of the message in the line (`u32` each).
+	/// - the target length (`u32`), followed by the target.
+	/// - the line, up to the end of the record.
+	///
+	/// Key-value pairs are only kept in the line.
+	Binary,
+}
+
+
+impl Schema {
+	/// The current version of both formats.
+	pub const VERSION: u16 = 1;
+
+
+	/// Read the entries of a dump in either format, which is detected from its marker.
+	///
+	/// Returns an error of kind `InvalidData` if the dump is in neither format, is of a
+	/// newer version, or is malformed.
+	pub fn read<R>(mut reader: R) -> io::Result<Vec<LogEntry>>
+	where
+		R: Read
+	{
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes)?;
+
+		match bytes.strip_prefix(MAGIC) {
+			Some(rest) => read_binary(rest),
+			None => read_json(&bytes),
+		}
+	}
+
+
+	/// Read the entries of a dump in any format this crate writes: a schema dump in either
+	/// format, JSON Lines of entry objects, or text lines in the default or syslog format.
+	/// Text lines that can't be parsed are continuations of the preceding entry, such as of
+	/// multi-line messages, or are skipped if there is none, such as the process header.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn import<R>(mut reader: R) -> io::Result<Vec<LogEntry>>
+	where
+		R: Read
+	{
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes)?;
+
+		if bytes.starts_with(MAGIC) {
+			return Self::read(&bytes[..]);
+		}
+
+		let text = String::from_utf8_lossy(&bytes);
+
+		let first = text
+			.lines()
+			.find(|line| !line.trim().is_empty());
+
+		match first.and_then(json::parse) {
+			Some(header) if header.get("schema").is_some() => Self::read(&bytes[..]),
+			Some(_) => read_json_lines(&text),
+			None => Ok(read_text(&text)),
+		}
+	}
+
+
+	/// Write the format-version marker.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn write_header(self, writer: &mut dyn Write) -> io::Result<()> {
+		match self {
+			Self::Json => writeln!(writer, "{{\"schema\":\"{}\",\"version\":{}}}", NAME, Self::VERSION),
+			Self::Binary => {
+				writer.write_all(MAGIC)?;
+				writer.write_all(&Self::VERSION.to_le_bytes())
+			}
+		}
+	}
+
+
+	/// Write an entry, following the marker.
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn write_entry(self, writer: &mut dyn Write, entry: &LogEntry) -> io::Result<()> {
+		match self {
+			Self::Json => {
+				let mut line = String::new();
+
+				json::write_entry(&mut line, entry)
+					.expect("std::fmt::Write should never fail for String");
+
+				line.push('\n');
+
+				writer.write_all(line.as_bytes())
+			}
+			Self::Binary => writer.write_all(&encode(entry)?),
+		}
+	}
+}
+
+
+/// The length of the fixed fields of a binary record, in bytes.
+const FIXED: usize = 33;
+
+
+/// Encode an entry as a binary record. This is independent of the internal record
+/// encoding, so that the format stays stable.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+fn encode(entry: &LogEntry) -> io::Result<Vec<u8>> {
+	let too_long = || io::Error::new(io::ErrorKind::InvalidInput, "entry too long for a dump record");
+
+	let len = u32::try_from(FIXED + entry.target.len() + entry.line.len())
+		.map_err(|_| too_long())?;
+
+	let timestamp = entry.timestamp
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_micros() as u64);
+
+	let mut record = Vec::with_capacity(len as usize);
+
+	record.extend_from_slice(&len.to_le_bytes());
+	record.extend_from_slice(&entry.sequence.to_le_bytes());
+	record.extend_from_slice(&timestamp.to_le_bytes());
+	record.push(entry.level as u8);
+	record.extend_from_slice(&(entry.message as u32).to_le_bytes());
+	record.extend_from_slice(&(entry.message().len() as u32).to_le_bytes());
+	record.extend_from_slice(&(entry.target.len() as u32).to_le_bytes());
+	record.extend_from_slice(entry.target.as_bytes());
+	record.extend_from_slice(entry.line.as_bytes());
+
+	Ok(record)
+}
+
+
+/// Decode a binary record. Returns `None` if the record is invalid.
+fn decode(record: &[u8]) -> Option<LogEntry> {
+	let fixed = record.get(.. FIXED)?;
+
+	let u32_at = |offset: usize| {
+		let mut bytes = [0; 4];
+		bytes.copy_from_slice(&fixed[offset .. offset + 4]);
+		u32::from_le_bytes(bytes) as usize
+	};
+
+	let u64_at = |offset: usize| {
+		let mut bytes = [0; 8];
+		bytes.copy_from_slice(&fixed[offset .. offset + 8]);
+		u64::from_le_bytes(bytes)
+	};
+
+	let level = Level::iter().find(|level| *level as u8 == fixed[20])?;
+	let message = u32_at(21);
+	let message_len = u32_at(25);
+	let target_len = u32_at(29);
+
+	let rest = &record[FIXED ..];
+	let target = std::str::from_utf8(rest.get(.. target_len)?).ok()?;
+	let line = std::str::from_utf8(&rest[target_len ..]).ok()?;
+
+	let end = message.checked_add(message_len)?;
+
+	// Slicing the message out of the line must not panic.
+	line.get(message .. end)?;
+
+	let suffix = line.len() - end;
+
+	Some(
+		LogEntry {
+			sequence: u64_at(4),
+			level,
+			target: intern(target),
+			timestamp: UNIX_EPOCH + Duration::from_micros(u64_at(12)),
+			line: line.into(),
+			message,
+			details: (suffix > 0).then(|| Box::new(Details { json: true, suffix, ..Details::default() })),
+		}
+	)
+}
+
+
+fn invalid(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+/// Reject versions newer than the current one.
+fn check_version(version: u64) -> io::Result<()> {
+	if version > u64::from(Schema::VERSION) {
+		Err(invalid(format!("unsupported dump version: {}", version)))
+	} else {
+		Ok(())
+	}
+}
+
+
+fn read_binary(bytes: &[u8]) -> io::Result<Vec<LogEntry>> {
+	let (version, mut rest) = match bytes {
+		&[low, high, ref rest @ ..] => (u16::from_le_bytes([low, high]), rest),
+		_ => return Err(invalid("truncated dump header".into())),
+	};
+
+	check_version(version.into())?;
+
+	let mut entries = Vec::new();
+
+	while !rest.is_empty() {
+		let entry = rest
+			.get(.. 4)
+			.map(|len| u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+			.and_then(|len| Some((rest.get(.. len)?, len)))
+			.and_then(|(bytes, len)| Some((decode(bytes)?, len)));
+
+		let (entry, len) = entry.ok_or_else(|| invalid(format!("invalid record {}", entries.len())))?;
+
+		entries.push(entry);
+		rest = &rest[len ..];
+	}
+
+	Ok(entries)
+}
+
+
+fn read_json(bytes: &[u8]) -> io::Result<Vec<LogEntry>> {
+	let text = std::str::from_utf8(bytes)
+		.map_err(|_| invalid("not a memory_logger dump".into()))?;
+
+	let mut lines = text
+		.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty());
+
+	let header = lines
+		.next()
+		.and_then(|(_, line)| json::parse(line))
+		.filter(|header| header.get("schema").and_then(Value::as_str) == Some(NAME))
+		.ok_or_else(|| invalid("not a memory_logger dump".into()))?;
+
+	let version = header
+		.get("version")
+		.and_then(Value::as_u64)
+		.ok_or_else(|| invalid("missing dump version".into()))?;
+
+	check_version(version)?;
+
+	lines
+		.map(
+			|(index, line)| json::parse(line)
+				.as_ref()
+				.and_then(entry_of)
+				.ok_or_else(|| invalid(format!("invalid entry at line {}", index + 1)))
+		)
+		.collect()
+}
+
+
+/// Read JSON Lines of entry objects, without a header.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+fn read_json_lines(text: &str) -> io::Result<Vec<LogEntry>> {
+	text
+		.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.map(
+			|(index, line)| json::parse(line)
+				.as_ref()
+				.and_then(entry_of)
+				.ok_or_else(|| invalid(format!("invalid entry at line {}", index + 1)))
+		)
+		.collect()
+}
+
+
+/// Read text lines, appending those that can't be parsed to the preceding entry.
+#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+fn read_text(text: &str) -> Vec<LogEntry> {
+	let mut entries: Vec<LogEntry> = Vec::new();
+
+	for line in text.lines() {
+		match (LogEntry::parse(line), entries.last_mut()) {
+			(Some(entry), _) => entries.push(entry),
+			(None, Some(entry)) => entry.continue_line(line),
+			(None, None) => (),
+		}
+	}
+
+	entries
+}
+
+
+/// The entry described by a JSON object.
+pub(crate) fn entry_of(object: &Value) -> Option<LogEntry> {
+	let field = |name| object.get(name).and_then(Value::as_str);
+
+	let key_values = match object.get("key_values") {
+		Some(Value::Object(pairs)) => pairs
+			.iter()
+			.map(|(key, value)| Some((intern(key), value.as_str()?.to_owned())))
+			.collect::<Option<_>>()?,
+		Some(_) => return None,
+		None => Vec::new(),
+	};
+
+	LogEntry::from_line(
+		object.get("sequence")?.as_u64()?,
+		Level::from_str(field("level")?).ok()?,
+		field("target")?,
+		format::parse_rfc3339(field("timestamp")?)?,
+		field("line")?.to_owned(),
+		field("message")?,
+		Details {
+			key_values,
+			..Details::default()
+		}
+	)
+}
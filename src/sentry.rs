@@ -0,0 +1,38 @@
+use std::time::SystemTime;
+
+use log::Level;
+
+use sentry_core::protocol::{Breadcrumb, Level as SentryLevel};
+
+use crate::LogEntry;
+
+
+impl From<&LogEntry> for Breadcrumb {
+	/// Convert an entry into a `log` breadcrumb, categorized by the entry's target.
+	fn from(entry: &LogEntry) -> Self {
+		breadcrumb(entry.level, &entry.target, entry.message(), entry.timestamp)
+	}
+}
+
+
+/// Build a `log` breadcrumb, categorized by target.
+pub(crate) fn breadcrumb(
+	level: Level,
+	target: &str,
+	message: &str,
+	timestamp: SystemTime,
+) -> Breadcrumb {
+	Breadcrumb {
+		timestamp,
+		ty: "log".into(),
+		category: Some(target.into()),
+		level: match level {
+			Level::Error => SentryLevel::Error,
+			Level::Warn => SentryLevel::Warning,
+			Level::Info => SentryLevel::Info,
+			Level::Debug | Level::Trace => SentryLevel::Debug,
+		},
+		message: Some(message.into()),
+		..Breadcrumb::default()
+	}
+}
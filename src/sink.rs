@@ -0,0 +1,218 @@
+use std::{
+	fmt,
+	io::{self, Write},
+	sync::mpsc,
+	thread::JoinHandle,
+	time::Duration,
+};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::{sync::mpsc::RecvTimeoutError, thread};
+
+use crate::LogEntry;
+
+
+/// A destination for exported entries, such as a file or a remote service.
+///
+/// Sinks are registered in a [`Pipeline`](struct.Pipeline.html), whose worker periodically
+/// drains the buffered entries into them. Closures taking a slice of entries are sinks too.
+pub trait Sink: Send {
+	/// Export a batch of entries, in order.
+	fn export(&mut self, entries: &[LogEntry]) -> io::Result<()>;
+}
+
+
+impl<F> Sink for F
+where
+	F: FnMut(&[LogEntry]) -> io::Result<()> + Send
+{
+	fn export(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+		self(entries)
+	}
+}
+
+
+/// A sink writing entries to a writer, one per line, such as a file.
+#[derive(Debug)]
+pub struct Lines<W>(W);
+
+
+impl<W> Lines<W>
+where
+	W: Write + Send
+{
+	/// Create a sink writing to the given writer.
+	pub fn new(writer: W) -> Self {
+		Self(writer)
+	}
+
+
+	/// Unwraps the writer.
+	pub fn into_inner(self) -> W {
+		self.0
+	}
+}
+
+
+impl<W> Sink for Lines<W>
+where
+	W: Write + Send
+{
+	fn export(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+		for entry in entries {
+			self.0.write_all(entry.as_bytes())?;
+			self.0.write_all(b"\n")?;
+		}
+
+		self.0.flush()
+	}
+}
+
+
+type ErrorHandler = Box<dyn FnMut(io::Error) + Send>;
+
+
+/// A set of sinks, fed by a background worker that drains the buffered entries at a fixed
+/// interval. See `export` in either flavor.
+pub struct Pipeline {
+	interval: Duration,
+	sinks: Vec<Box<dyn Sink>>,
+	on_error: Option<ErrorHandler>,
+}
+
+
+impl Pipeline {
+	/// Create a pipeline without sinks, draining the buffer at the given interval.
+	pub fn new(interval: Duration) -> Self {
+		Self {
+			interval,
+			sinks: Vec::new(),
+			on_error: None,
+		}
+	}
+
+
+	/// Register a sink. Every drained batch is exported to all sinks, in registration order.
+	pub fn sink<S>(mut self, sink: S) -> Self
+	where
+		S: Sink + 'static
+	{
+		self.sinks.push(Box::new(sink));
+		self
+	}
+
+
+	/// Set a callback for errors exporting to a sink. A failing sink does not prevent
+	/// exporting to the others, and the batch is not retried. Errors are ignored by default.
+	pub fn on_error<F>(mut self, callback: F) -> Self
+	where
+		F: FnMut(io::Error) + Send + 'static
+	{
+		self.on_error = Some(Box::new(callback));
+		self
+	}
+
+
+	/// Spawn the worker, calling `drain` to get the entries to be exported.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn spawn<D>(mut self, mut drain: D) -> io::Result<Worker>
+	where
+		D: FnMut() -> Vec<LogEntry> + Send + 'static
+	{
+		let interval = self.interval;
+
+		Worker::spawn("memory_logger_export", interval, move || self.export(&drain()))
+	}
+
+
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	fn export(&mut self, entries: &[LogEntry]) {
+		if entries.is_empty() {
+			return;
+		}
+
+		for sink in &mut self.sinks {
+			if let Err(error) = sink.export(entries) {
+				if let Some(on_error) = &mut self.on_error {
+					on_error(error);
+				}
+			}
+		}
+	}
+}
+
+
+impl fmt::Debug for Pipeline {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Pipeline")
+			.field("interval", &self.interval)
+			.field("sinks", &self.sinks.len())
+			.finish()
+	}
+}
+
+
+/// The handle of a background worker, such as a pipeline's. The worker is stopped when this
+/// is dropped, after a final export of the buffered entries.
+#[derive(Debug)]
+pub struct Worker {
+	stop: mpsc::Sender<()>,
+	thread: Option<JoinHandle<()>>,
+}
+
+
+impl Worker {
+	/// Spawn a worker thread calling `tick` at the given interval, and once more when
+	/// stopped.
+	#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn spawn<F>(name: &str, interval: Duration, mut tick: F) -> io::Result<Self>
+	where
+		F: FnMut() + Send + 'static
+	{
+		let (stop, stopped) = mpsc::channel();
+
+		let thread = thread::Builder::new()
+			.name(name.into())
+			.spawn(
+				move || loop {
+					// Stop when signaled, or when the handle is gone.
+					let stopping = !matches!(
+						stopped.recv_timeout(interval),
+						Err(RecvTimeoutError::Timeout)
+					);
+
+					tick();
+
+					if stopping {
+						break;
+					}
+				}
+			)?;
+
+		Ok(
+			Self {
+				stop,
+				thread: Some(thread),
+			}
+		)
+	}
+
+
+	/// Stop the worker, waiting for the final export to finish.
+	pub fn stop(self) {
+		drop(self)
+	}
+}
+
+
+impl Drop for Worker {
+	fn drop(&mut self) {
+		let _ = self.stop.send(());
+
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+
+
+/// Rules normalizing volatile fields of dumps to placeholders, so that dumps can be
+/// committed as snapshots, such as with `insta`, without churn. Used by the flavors'
+/// `dump_normalized`.
+///
+/// By default, RFC 3339 timestamps are replaced by `[timestamp]`, thread ids by
+/// `ThreadId([id])`, and hexadecimal pointers by `[pointer]`. Further rules are applied
+/// after them, in the order they were added.
+///
+/// ```
+/// use memory_logger::Normalizer;
+/// use regex::Regex;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let normalizer = Normalizer::new()
+///     .rule(Regex::new(r"took \d+ms")?, "took [duration]");
+///
+/// assert_eq!(
+///     normalizer.normalize("2024-05-01T12:00:00.123456Z worker at 0x7ffd5e8c took 12ms"),
+///     "[timestamp] worker at [pointer] took [duration]"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Normalizer {
+	rules: Vec<(Regex, String)>,
+}
+
+
+impl Normalizer {
+	/// Create a normalizer with the default rules.
+	pub fn new() -> Self {
+		let rules = [
+			(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:\d{2})", "[timestamp]"),
+			(r"ThreadId\(\d+\)", "ThreadId([id])"),
+			(r"\b0x[0-9a-fA-F]+\b", "[pointer]"),
+		];
+
+		Self {
+			rules: rules
+				.iter()
+				.map(
+					|(pattern, replacement)| (
+						Regex::new(pattern).expect("the default rules should be valid"),
+						(*replacement).to_owned(),
+					)
+				)
+				.collect(),
+		}
+	}
+
+
+	/// Add a rule replacing the matches of the given pattern. The replacement may refer
+	/// to capture groups, as in `Regex::replace_all`.
+	pub fn rule<S>(mut self, pattern: Regex, replacement: S) -> Self
+	where
+		S: Into<String>
+	{
+		self.rules.push((pattern, replacement.into()));
+		self
+	}
+
+
+	/// Normalize a text through the rules.
+	pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+		let mut text = Cow::Borrowed(text);
+
+		for (pattern, replacement) in &self.rules {
+			if let Cow::Owned(replaced) = pattern.replace_all(&text, replacement.as_str()) {
+				text = Cow::Owned(replaced);
+			}
+		}
+
+		text
+	}
+}
+
+
+impl Default for Normalizer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,266 @@
+use std::{
+	path::Path,
+	time::{Duration, UNIX_EPOCH},
+};
+
+use log::Level;
+
+use rusqlite::{params, Connection, Params};
+
+use crate::{intern::intern, LogEntry, Storage};
+
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS entries (
+		id INTEGER PRIMARY KEY AUTOINCREMENT,
+		sequence INTEGER NOT NULL,
+		level INTEGER NOT NULL,
+		target TEXT NOT NULL,
+		timestamp INTEGER NOT NULL,
+		line TEXT NOT NULL,
+		message INTEGER NOT NULL,
+		bytes INTEGER NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS entries_level ON entries (level);
+	CREATE INDEX IF NOT EXISTS entries_target ON entries (target);
+	CREATE INDEX IF NOT EXISTS entries_timestamp ON entries (timestamp);
+";
+
+
+/// A storage appending entries to an SQLite database, so that they survive restarts and
+/// may be queried with SQL.
+///
+/// Entries are stored in the `entries` table, with the `sequence`, `level` (from `1`
+/// for error to `5` for trace), `target`, `timestamp` (in microseconds since the unix
+/// epoch), `line` and `message` (the offset of the message in the line) columns. The level,
+/// target and timestamp columns are indexed. Rows with an unknown level, or whose message
+/// offset is not a char boundary of the line, are skipped when reading.
+///
+/// Entries already in the database are kept when it is opened. SQLite errors are ignored,
+/// since logging must not fail: entries that fail to be inserted are skipped, iteration
+/// yields nothing if querying fails, and the counts are reloaded from the database if
+/// evicting fails.
+///
+/// ```
+/// # use regex::Regex;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "asynchronous")] {
+/// # use memory_logger::{asynchronous::MemoryLogger, Config, SqliteStorage};
+/// let path = std::env::temp_dir().join("memory_logger.sqlite");
+/// # let _ = std::fs::remove_file(&path);
+/// let config = Config::new(log::Level::Info, Regex::new("^mycrate")?)
+///     .storage(SqliteStorage::open(&path)?);
+/// let logger = MemoryLogger::setup_with(config)?;
+///
+/// log::info!(target: "mycrate::net", "Connected.");
+/// log::error!(target: "mycrate::db", "Connection lost.");
+/// # logger.peek();
+///
+/// let database = rusqlite::Connection::open(&path)?;
+/// let target: String = database.query_row(
+///     "SELECT target FROM entries WHERE level = 1",
+///     [],
+///     |row| row.get(0)
+/// )?;
+///
+/// assert_eq!(target, "mycrate::db");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SqliteStorage {
+	connection: Connection,
+	len: usize,
+	bytes: usize,
+}
+
+
+impl SqliteStorage {
+	/// Open the database at the given path, creating it if it does not exist.
+	pub fn open<P>(path: P) -> rusqlite::Result<Self>
+	where
+		P: AsRef<Path>
+	{
+		Self::new(Connection::open(path)?)
+	}
+
+
+	/// Use the given connection, creating the `entries` table if it does not exist.
+	pub fn new(connection: Connection) -> rusqlite::Result<Self> {
+		connection.execute_batch(SCHEMA)?;
+
+		let (len, bytes) = totals(&connection, "", [])?;
+
+		Ok(
+			Self {
+				connection,
+				len,
+				bytes,
+			}
+		)
+	}
+
+
+	fn insert(&self, entry: &LogEntry) -> rusqlite::Result<usize> {
+		let timestamp = entry.timestamp
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |duration| duration.as_micros() as i64);
+
+		self.connection
+			.prepare_cached(
+				"INSERT INTO entries (sequence, level, target, timestamp, line, message, bytes)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+			)?
+			.execute(
+				params![
+					entry.sequence as i64,
+					entry.level as i64,
+					&*entry.target,
+					timestamp,
+					entry.line,
+					entry.message as i64,
+					entry.line.len() as i64,
+				]
+			)
+	}
+
+
+	fn select(&self) -> rusqlite::Result<Vec<LogEntry>> {
+		let mut statement = self.connection.prepare_cached(
+			"SELECT sequence, level, target, timestamp, line, message FROM entries ORDER BY id"
+		)?;
+
+		let rows = statement.query_map(
+			[],
+			|row| {
+				let level: i64 = row.get(1)?;
+				let target: String = row.get(2)?;
+				let timestamp: i64 = row.get(3)?;
+				let line: String = row.get(4)?;
+				let message = row.get::<_, i64>(5)? as usize;
+
+				let level = match Level::iter().find(|candidate| *candidate as i64 == level) {
+					Some(level) => level,
+					None => return Ok(None),
+				};
+
+				if message > line.len() || !line.is_char_boundary(message) {
+					return Ok(None);
+				}
+
+				Ok(
+					Some(
+						LogEntry {
+							sequence: row.get::<_, i64>(0)? as u64,
+							level,
+							target: intern(&target),
+							timestamp: UNIX_EPOCH + Duration::from_micros(timestamp as u64),
+							line,
+							message,
+							details: None,
+						}
+					)
+				)
+			}
+		)?;
+
+		rows
+			.filter_map(Result::transpose)
+			.collect()
+	}
+
+
+	fn delete(&mut self, count: usize) -> rusqlite::Result<()> {
+		let oldest = "SELECT id FROM entries ORDER BY id LIMIT ?1";
+
+		let transaction = self.connection.unchecked_transaction()?;
+
+		let (len, bytes) = totals(
+			&transaction,
+			&format!("WHERE id IN ({})", oldest),
+			[count as i64]
+		)?;
+
+		transaction.execute(
+			&format!("DELETE FROM entries WHERE id IN ({})", oldest),
+			[count as i64]
+		)?;
+
+		transaction.commit()?;
+
+		self.len -= len;
+		self.bytes -= bytes;
+
+		Ok(())
+	}
+
+
+	/// Bring the counters in line with the database. If it can't be queried, it is
+	/// considered empty, as iteration yields nothing then.
+	fn resync(&mut self) {
+		let (len, bytes) = totals(&self.connection, "", []).unwrap_or_default();
+
+		self.len = len;
+		self.bytes = bytes;
+	}
+}
+
+
+impl Storage for SqliteStorage {
+	fn append(&mut self, entry: LogEntry) {
+		if self.insert(&entry).is_ok() {
+			self.len += 1;
+			self.bytes += entry.line.len();
+		}
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		Box::new(
+			self.select()
+				.unwrap_or_default()
+				.into_iter()
+		)
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		if self.delete(count).is_err() {
+			self.resync();
+		}
+	}
+
+
+	fn clear(&mut self) {
+		if self.connection.execute("DELETE FROM entries", []).is_ok() {
+			self.len = 0;
+			self.bytes = 0;
+		}
+	}
+
+
+	fn len(&self) -> usize {
+		self.len
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.bytes
+	}
+}
+
+
+/// The number of entries matching a condition, and the total length of their lines.
+fn totals<P>(connection: &Connection, condition: &str, params: P) -> rusqlite::Result<(usize, usize)>
+where
+	P: Params
+{
+	let (len, bytes): (i64, i64) = connection.query_row(
+		&format!("SELECT COUNT(*), COALESCE(SUM(bytes), 0) FROM entries {}", condition),
+		params,
+		|row| Ok((row.get(0)?, row.get(1)?))
+	)?;
+
+	Ok((len as usize, bytes as usize))
+}
@@ -0,0 +1,174 @@
+//! Server-Sent Events, for live-tailing the logger from a browser through `EventSource`.
+//!
+//! Each entry is sent as an event of type `log`, identified by its sequence number and
+//! carrying the entry as JSON, as described in the [`http`](../http/index.html) module:
+//!
+//! ```text
+//! id: 42
+//! event: log
+//! data: {"sequence":42,"timestamp":"2024-01-01T00:00:00.000000Z","level":"WARN",...}
+//!
+//! ```
+//!
+//! Browsers reconnect automatically, sending the last received id in the `Last-Event-ID`
+//! header, which may be passed to
+//! [`MemoryLogger::events`](../asynchronous/struct.MemoryLogger.html#method.events) to
+//! resume without gaps.
+
+use std::{
+	collections::VecDeque,
+	fmt::Write,
+	time::Duration,
+};
+
+use flume::{Receiver, RecvTimeoutError};
+
+#[cfg(feature = "stream")]
+use std::{
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+#[cfg(feature = "stream")]
+use flume::r#async::RecvStream;
+
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+
+use crate::{json, LogEntry};
+
+
+/// The content type of event streams.
+pub const CONTENT_TYPE: &str = "text/event-stream";
+
+
+/// A comment sent when there are no entries, keeping the connection alive.
+const KEEP_ALIVE: &str = ": keep-alive\n\n";
+
+
+/// Render an entry as an event.
+pub fn event(entry: &LogEntry) -> String {
+	let mut event = String::new();
+
+	write!(event, "id: {}\nevent: log\ndata: ", entry.sequence)
+		.and_then(|()| json::write_entry(&mut event, entry))
+		.expect("std::fmt::Write should never fail for String");
+
+	event.push_str("\n\n");
+
+	event
+}
+
+
+/// The events of the buffered entries, followed by the events of new entries as they are
+/// logged. Iterating blocks while waiting for new entries, yielding a keep-alive comment
+/// at every interval without one, so that disconnected clients are noticed when writing.
+/// It never ends.
+///
+/// See [`MemoryLogger::events`](../asynchronous/struct.MemoryLogger.html#method.events).
+#[derive(Debug)]
+pub struct Events {
+	backlog: VecDeque<LogEntry>,
+	rx: Receiver<LogEntry>,
+	/// The sequence number of the last event, to skip new entries already in the backlog.
+	last: Option<u64>,
+	keep_alive: Duration,
+}
+
+
+impl Events {
+	pub(crate) fn new(backlog: Vec<LogEntry>, rx: Receiver<LogEntry>, last: Option<u64>) -> Self {
+		Self {
+			backlog: backlog.into(),
+			rx,
+			last,
+			keep_alive: Duration::from_secs(15),
+		}
+	}
+
+
+	/// Set the keep-alive interval. Defaults to 15 seconds.
+	pub fn keep_alive(mut self, interval: Duration) -> Self {
+		self.keep_alive = interval;
+		self
+	}
+
+
+	/// Gets a stream of the events, which waits for new entries as they are logged. Streams
+	/// don't send keep-alive comments, which should be left to the web framework. It never
+	/// ends.
+	#[cfg(feature = "stream")]
+	pub fn stream(self) -> impl Stream<Item = String> + Unpin {
+		EventStream {
+			backlog: self.backlog,
+			pending: self.rx.into_stream(),
+			last: self.last,
+		}
+	}
+}
+
+
+impl Iterator for Events {
+	type Item = String;
+
+	fn next(&mut self) -> Option<String> {
+		while let Some(entry) = self.backlog.pop_front() {
+			if fresh(&mut self.last, &entry) {
+				return Some(event(&entry));
+			}
+		}
+
+		loop {
+			match self.rx.recv_timeout(self.keep_alive) {
+				Ok(entry) if fresh(&mut self.last, &entry) => return Some(event(&entry)),
+				Ok(_) => continue,
+				Err(RecvTimeoutError::Timeout) => return Some(KEEP_ALIVE.into()),
+				Err(RecvTimeoutError::Disconnected) => return None,
+			}
+		}
+	}
+}
+
+
+/// Whether the entry has not been sent yet, given the sequence number of the last event.
+fn fresh(last: &mut Option<u64>, entry: &LogEntry) -> bool {
+	let fresh = last.map_or(true, |last| entry.sequence > last);
+
+	if fresh {
+		*last = Some(entry.sequence);
+	}
+
+	fresh
+}
+
+
+#[cfg(feature = "stream")]
+struct EventStream {
+	backlog: VecDeque<LogEntry>,
+	pending: RecvStream<'static, LogEntry>,
+	last: Option<u64>,
+}
+
+
+#[cfg(feature = "stream")]
+impl Stream for EventStream {
+	type Item = String;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+
+		loop {
+			let entry = match this.backlog.pop_front() {
+				Some(entry) => entry,
+				None => match Pin::new(&mut this.pending).poll_next(cx) {
+					Poll::Ready(Some(entry)) => entry,
+					other => return other.map(|_| None),
+				},
+			};
+
+			if fresh(&mut this.last, &entry) {
+				return Poll::Ready(Some(event(&entry)));
+			}
+		}
+	}
+}
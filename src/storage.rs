@@ -0,0 +1,598 @@
+use std::{
+	collections::VecDeque,
+	fmt,
+	fs::{self, File, OpenOptions},
+	io::{self, Read, Seek, SeekFrom, Write},
+	path::{Path, PathBuf},
+	process,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+		Mutex,
+	},
+	time::SystemTime,
+};
+
+use log::Level;
+
+use crate::{entry::Details, LogEntry};
+
+
+/// A backend holding the buffered entries, oldest first.
+///
+/// Storages are infallible, as logging must not fail. Backends that may fail, such as
+/// file-backed ones, should skip the affected entries instead.
+///
+/// This is used by the asynchronous flavor when retention is enabled, through
+/// [`Config::storage`](struct.Config.html#method.storage).
+pub trait Storage: Send {
+	/// Append an entry.
+	fn append(&mut self, entry: LogEntry);
+
+	/// Iterate over the stored entries, oldest first.
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_>;
+
+	/// Remove the oldest `count` entries, or all entries if there are fewer.
+	fn evict(&mut self, count: usize);
+
+	/// Remove all entries.
+	fn clear(&mut self);
+
+	/// The number of stored entries.
+	fn len(&self) -> usize;
+
+	/// The total length of the stored lines, in bytes.
+	fn bytes(&self) -> usize;
+
+	/// Whether there are no stored entries.
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Remove and return all entries, oldest first.
+	fn take(&mut self) -> Vec<LogEntry> {
+		let entries = self.iter().collect();
+		self.clear();
+		entries
+	}
+}
+
+
+/// A storage keeping up to a fixed number of entries, evicting the oldest once full.
+#[derive(Debug)]
+pub struct RingStorage {
+	capacity: usize,
+	entries: VecDeque<LogEntry>,
+	bytes: usize,
+}
+
+
+impl RingStorage {
+	/// Create a storage keeping up to `capacity` entries.
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			capacity,
+			entries: VecDeque::new(),
+			bytes: 0,
+		}
+	}
+}
+
+
+impl Storage for RingStorage {
+	fn append(&mut self, entry: LogEntry) {
+		if self.capacity == 0 {
+			return;
+		}
+
+		if self.entries.len() == self.capacity {
+			self.evict(1);
+		}
+
+		self.bytes += entry.line.len();
+		self.entries.push_back(entry);
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		Box::new(self.entries.iter().cloned())
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		let count = count.min(self.entries.len());
+
+		for entry in self.entries.drain(.. count) {
+			self.bytes -= entry.line.len();
+		}
+	}
+
+
+	fn clear(&mut self) {
+		self.entries.clear();
+		self.bytes = 0;
+	}
+
+
+	fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.bytes
+	}
+
+
+	fn take(&mut self) -> Vec<LogEntry> {
+		self.bytes = 0;
+		self.entries.drain(..).collect()
+	}
+}
+
+
+/// Metadata of a stored line. The line starts at `offset`, and is followed by a newline.
+#[derive(Debug)]
+struct Slot {
+	offset: usize,
+	len: usize,
+	sequence: u64,
+	level: Level,
+	target: Arc<str>,
+	timestamp: SystemTime,
+	/// Offset of the message in the line.
+	message: usize,
+	details: Option<Box<Details>>,
+}
+
+
+impl Slot {
+	fn new(offset: usize, entry: &LogEntry) -> Self {
+		Self {
+			offset,
+			len: entry.line.len(),
+			sequence: entry.sequence,
+			level: entry.level,
+			target: entry.target.clone(),
+			timestamp: entry.timestamp,
+			message: entry.message,
+			details: entry.details.clone(),
+		}
+	}
+
+
+	/// Build an owned entry from this slot and the text it refers to, which starts at
+	/// offset `base`.
+	fn to_entry(&self, text: &str, base: usize) -> Option<LogEntry> {
+		let start = self.offset - base;
+
+		Some(
+			LogEntry {
+				sequence: self.sequence,
+				level: self.level,
+				target: self.target.clone(),
+				timestamp: self.timestamp,
+				line: text.get(start .. start + self.len)?.into(),
+				message: self.message,
+				details: self.details.clone(),
+			}
+		)
+	}
+}
+
+
+/// Slots and the total length of their lines.
+#[derive(Debug, Default)]
+struct Slots {
+	slots: VecDeque<Slot>,
+	bytes: usize,
+}
+
+
+impl Slots {
+	fn push(&mut self, slot: Slot) {
+		self.bytes += slot.len;
+		self.slots.push_back(slot);
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		let count = count.min(self.slots.len());
+
+		for slot in self.slots.drain(.. count) {
+			self.bytes -= slot.len;
+		}
+	}
+
+
+	fn clear(&mut self) {
+		self.slots.clear();
+		self.bytes = 0;
+	}
+
+
+	/// The offset of the oldest line.
+	fn start(&self) -> Option<usize> {
+		self.slots
+			.front()
+			.map(|slot| slot.offset)
+	}
+}
+
+
+/// An unbounded storage keeping the lines in a single growable string, which avoids an
+/// allocation per entry.
+#[derive(Debug, Default)]
+pub struct StringStorage {
+	text: String,
+	slots: Slots,
+}
+
+
+impl StringStorage {
+	/// Create an empty storage.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+
+impl Storage for StringStorage {
+	fn append(&mut self, entry: LogEntry) {
+		self.slots.push(Slot::new(self.text.len(), &entry));
+		self.text.push_str(&entry.line);
+		self.text.push('\n');
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		Box::new(
+			self.slots.slots
+				.iter()
+				.filter_map(move |slot| slot.to_entry(&self.text, 0))
+		)
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		self.slots.evict(count);
+
+		// Evicted text is reclaimed once it is the larger part of the buffer.
+		let start = self.slots.start().unwrap_or(self.text.len());
+
+		if start > self.text.len() / 2 {
+			self.text.drain(.. start);
+
+			for slot in &mut self.slots.slots {
+				slot.offset -= start;
+			}
+		}
+	}
+
+
+	fn clear(&mut self) {
+		self.text.clear();
+		self.slots.clear();
+	}
+
+
+	fn len(&self) -> usize {
+		self.slots.slots.len()
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.slots.bytes
+	}
+}
+
+
+/// A storage appending the lines to a file, keeping only their metadata in memory. Evicted
+/// lines are reclaimed by compacting the file once they are its larger part, so that the
+/// file is at most twice as long as the stored lines.
+///
+/// I/O errors are ignored, since logging must not fail: entries that fail to be written
+/// are skipped, iteration yields nothing if reading fails, and all entries are dropped if
+/// compacting fails.
+#[derive(Debug)]
+pub struct FileStorage {
+	file: File,
+	/// The length of the file.
+	end: usize,
+	slots: Slots,
+}
+
+
+impl FileStorage {
+	/// Create a storage backed by the file at the given path. The file is created if it
+	/// does not exist, and truncated otherwise.
+	pub fn create<P>(path: P) -> io::Result<Self>
+	where
+		P: AsRef<Path>
+	{
+		let file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.open(path)?;
+
+		Ok(
+			Self {
+				file,
+				end: 0,
+				slots: Slots::default(),
+			}
+		)
+	}
+
+
+	fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+		self.file.seek(SeekFrom::Start(self.end as u64))?;
+		self.file.write_all(entry.line.as_bytes())?;
+		self.file.write_all(b"\n")
+	}
+
+
+	/// Read the lines of the stored entries.
+	fn read(&self) -> io::Result<String> {
+		let mut text = String::new();
+		let mut file = &self.file;
+
+		if let Some(start) = self.slots.start() {
+			file.seek(SeekFrom::Start(start as u64))?;
+			file
+				.take((self.end - start) as u64)
+				.read_to_string(&mut text)?;
+		}
+
+		Ok(text)
+	}
+
+
+	fn truncate(&mut self) {
+		self.end = 0;
+
+		let _ = self.file.set_len(0);
+	}
+
+
+	/// Move the lines of the stored entries to the start of the file.
+	fn compact(&mut self) -> io::Result<()> {
+		let start = self.slots.start().unwrap_or(self.end);
+		let text = self.read()?;
+
+		self.file.seek(SeekFrom::Start(0))?;
+		self.file.write_all(text.as_bytes())?;
+		self.file.set_len(text.len() as u64)?;
+
+		self.end -= start;
+
+		for slot in &mut self.slots.slots {
+			slot.offset -= start;
+		}
+
+		Ok(())
+	}
+}
+
+
+impl Storage for FileStorage {
+	fn append(&mut self, entry: LogEntry) {
+		// A partial write is overwritten by the next entry.
+		if self.write(&entry).is_ok() {
+			self.slots.push(Slot::new(self.end, &entry));
+			self.end += entry.line.len() + 1;
+		}
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		let base = self.slots.start().unwrap_or(0);
+		let text = self.read().unwrap_or_default();
+
+		Box::new(
+			self.slots.slots
+				.iter()
+				.filter_map(move |slot| slot.to_entry(&text, base))
+		)
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		self.slots.evict(count);
+
+		let start = self.slots.start().unwrap_or(self.end);
+
+		if self.slots.slots.is_empty() {
+			self.truncate();
+		} else if start > self.end / 2 && self.compact().is_err() {
+			self.clear();
+		}
+	}
+
+
+	fn clear(&mut self) {
+		self.slots.clear();
+		self.truncate();
+	}
+
+
+	fn len(&self) -> usize {
+		self.slots.slots.len()
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.slots.bytes
+	}
+}
+
+
+/// A storage keeping the newest entries in memory, and spilling older entries to a
+/// temporary file once their total length exceeds a threshold. Iteration yields the
+/// spilled entries followed by the ones in memory.
+///
+/// The spill file is compacted as a [`FileStorage`](struct.FileStorage.html), so it is at
+/// most twice as long as the spilled lines, which are bounded by
+/// [`Config::retain`](struct.Config.html#method.retain) and
+/// [`Config::max_bytes`](struct.Config.html#method.max_bytes). As with `FileStorage`, I/O
+/// errors are ignored.
+///
+/// Loggers are never dropped, so the spill file must not rely on the storage being dropped
+/// to be removed. On unix, the temporary file of [`new`](#method.new) is unlinked as soon
+/// as it is created, and is reclaimed once the program exits. Otherwise, the file is
+/// removed when the storage is dropped, if ever.
+#[derive(Debug)]
+pub struct SpillStorage {
+	threshold: usize,
+	memory: RingStorage,
+	spilled: FileStorage,
+	/// The path of the spill file, unless it has already been unlinked.
+	path: Option<PathBuf>,
+}
+
+
+impl SpillStorage {
+	/// Create a storage keeping up to `threshold` bytes of lines in memory. The spill file
+	/// is created in the system's temporary directory.
+	pub fn new(threshold: usize) -> io::Result<Self> {
+		static SPILLS: AtomicUsize = AtomicUsize::new(0);
+
+		let name = format!(
+			"memory_logger-{}-{}.spill",
+			process::id(),
+			SPILLS.fetch_add(1, Ordering::Relaxed)
+		);
+
+		#[cfg_attr(not(unix), allow(unused_mut))]
+		let mut storage = Self::with_path(threshold, std::env::temp_dir().join(name))?;
+
+		// The open file stays usable once unlinked.
+		#[cfg(unix)]
+		if let Some(path) = storage.path.take() {
+			fs::remove_file(path)?;
+		}
+
+		Ok(storage)
+	}
+
+
+	/// Create a storage spilling to the file at the given path, which is truncated, and
+	/// removed when the storage is dropped.
+	pub fn with_path<P>(threshold: usize, path: P) -> io::Result<Self>
+	where
+		P: Into<PathBuf>
+	{
+		let path = path.into();
+
+		Ok(
+			Self {
+				threshold,
+				memory: RingStorage::new(usize::MAX),
+				spilled: FileStorage::create(&path)?,
+				path: Some(path),
+			}
+		)
+	}
+
+
+	/// The number of entries spilled to the file.
+	pub fn spilled(&self) -> usize {
+		self.spilled.len()
+	}
+}
+
+
+impl Storage for SpillStorage {
+	fn append(&mut self, entry: LogEntry) {
+		self.memory.append(entry);
+
+		while self.memory.bytes() > self.threshold {
+			match self.memory.entries.pop_front() {
+				Some(entry) => {
+					self.memory.bytes -= entry.line.len();
+					self.spilled.append(entry);
+				}
+
+				None => break,
+			}
+		}
+	}
+
+
+	fn iter(&self) -> Box<dyn Iterator<Item = LogEntry> + '_> {
+		Box::new(
+			self.spilled
+				.iter()
+				.chain(self.memory.iter())
+		)
+	}
+
+
+	fn evict(&mut self, count: usize) {
+		let spilled = count.min(self.spilled.len());
+
+		self.spilled.evict(spilled);
+		self.memory.evict(count - spilled);
+	}
+
+
+	fn clear(&mut self) {
+		self.spilled.clear();
+		self.memory.clear();
+	}
+
+
+	fn len(&self) -> usize {
+		self.spilled.len() + self.memory.len()
+	}
+
+
+	fn bytes(&self) -> usize {
+		self.spilled.bytes() + self.memory.bytes()
+	}
+}
+
+
+impl Drop for SpillStorage {
+	fn drop(&mut self) {
+		if let Some(path) = &self.path {
+			let _ = fs::remove_file(path);
+		}
+	}
+}
+
+
+/// A storage set in a configuration, moved into the logger when it is set up.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "asynchronous"), allow(dead_code))]
+pub(crate) struct Backend(Arc<Mutex<Option<Box<dyn Storage>>>>);
+
+
+impl Backend {
+	pub(crate) fn new<S>(storage: S) -> Self
+	where
+		S: Storage + 'static
+	{
+		Self(Arc::new(Mutex::new(Some(Box::new(storage)))))
+	}
+
+
+	/// Take the storage, if it has not been taken by another logger.
+	#[cfg_attr(not(feature = "asynchronous"), allow(dead_code))]
+	pub(crate) fn take(&self) -> Option<Box<dyn Storage>> {
+		self.0
+			.lock()
+			.expect("storage lock poisoned")
+			.take()
+	}
+}
+
+
+impl fmt::Debug for Backend {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("Backend")
+			.finish()
+	}
+}
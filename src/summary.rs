@@ -0,0 +1,147 @@
+use std::{borrow::Borrow, fmt};
+
+use log::Level;
+
+use crate::{metrics::Tally, LevelCounts, LogEntry, Metrics};
+
+
+/// A compact report of captured entries: total entries, counts per level, the noisiest
+/// targets, and the first and last errors.
+///
+/// The `Display` implementation renders the report, which is suitable for printing at the
+/// end of a CLI run:
+///
+/// ```text
+/// 12 entries: 1 error, 2 warn, 9 info, 0 debug, 0 trace
+/// noisiest targets: mycrate::net (8), mycrate::db (4)
+/// first error: [mycrate::db] ERROR | Connection lost.
+/// last error: [mycrate::db] ERROR | Connection lost.
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+	levels: LevelCounts,
+	targets: Vec<(String, u64)>,
+	first_error: Option<LogEntry>,
+	last_error: Option<LogEntry>,
+}
+
+
+impl Summary {
+	/// Summarize the given entries, keeping the `top` noisiest targets.
+	pub fn from_entries<I>(entries: I, top: usize) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Borrow<LogEntry>,
+	{
+		let mut tally = Tally::default();
+		let mut first_error = None;
+		let mut last_error = None;
+
+		for entry in entries {
+			let entry = entry.borrow();
+
+			tally.record(entry.level, &entry.target);
+
+			if entry.level == Level::Error {
+				if first_error.is_none() {
+					first_error = Some(entry.clone());
+				} else {
+					last_error = Some(entry.clone());
+				}
+			}
+		}
+
+		if last_error.is_none() {
+			last_error = first_error.clone();
+		}
+
+		Self::new(&tally.snapshot(), first_error, last_error, top)
+	}
+
+
+	pub(crate) fn new(
+		counts: &Metrics,
+		first_error: Option<LogEntry>,
+		last_error: Option<LogEntry>,
+		top: usize,
+	) -> Self {
+		let mut targets: Vec<_> = counts
+			.targets()
+			.iter()
+			.map(|(target, counts)| (target.clone(), counts.total()))
+			.collect();
+
+		// Noisiest first, ties by name.
+		targets.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		targets.truncate(top);
+
+		Self {
+			levels: *counts.levels(),
+			targets,
+			first_error,
+			last_error,
+		}
+	}
+
+
+	/// The total number of entries.
+	pub fn total(&self) -> u64 {
+		self.levels.total()
+	}
+
+
+	/// The counts by level.
+	pub fn levels(&self) -> &LevelCounts {
+		&self.levels
+	}
+
+
+	/// The noisiest targets and their entry counts, noisiest first.
+	pub fn top_targets(&self) -> &[(String, u64)] {
+		&self.targets
+	}
+
+
+	/// The first error entry, if any.
+	pub fn first_error(&self) -> Option<&LogEntry> {
+		self.first_error.as_ref()
+	}
+
+
+	/// The last error entry, if any. If there is a single error, this is the same as the
+	/// first.
+	pub fn last_error(&self) -> Option<&LogEntry> {
+		self.last_error.as_ref()
+	}
+}
+
+
+impl fmt::Display for Summary {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} entries:", self.total())?;
+
+		for (index, (level, count)) in self.levels.iter().enumerate() {
+			let separator = if index == 0 { "" } else { "," };
+			write!(f, "{} {} {}", separator, count, level.as_str().to_lowercase())?;
+		}
+
+		if !self.targets.is_empty() {
+			write!(f, "\nnoisiest targets: ")?;
+
+			for (index, (target, count)) in self.targets.iter().enumerate() {
+				let separator = if index == 0 { "" } else { ", " };
+				write!(f, "{}{} ({})", separator, target, count)?;
+			}
+		}
+
+		if let Some(entry) = &self.first_error {
+			write!(f, "\nfirst error: {}", entry)?;
+		}
+
+		if let Some(entry) = &self.last_error {
+			write!(f, "\nlast error: {}", entry)?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,47 @@
+use std::{
+	io,
+	net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+	thread,
+	time::Duration,
+};
+
+
+/// How many entries may be pending for a client before it is disconnected.
+#[cfg(feature = "asynchronous")]
+pub(crate) const BACKLOG: usize = 1024;
+
+
+/// How long writing to a client may block before it is disconnected.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// Listen on the given address, serving each client from its own thread. Returns the
+/// bound address. Clients that stop reading are disconnected once a write times out.
+pub(crate) fn serve_tcp<A, F>(address: A, serve: F) -> io::Result<SocketAddr>
+where
+	A: ToSocketAddrs,
+	F: Fn(TcpStream) -> io::Result<()> + Clone + Send + 'static,
+{
+	let listener = TcpListener::bind(address)?;
+	let address = listener.local_addr()?;
+
+	thread::Builder::new()
+		.name("memory_logger_tcp".into())
+		.spawn(
+			move || {
+				for stream in listener.incoming().flatten() {
+					if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+						continue;
+					}
+
+					let serve = serve.clone();
+
+					let _ = thread::Builder::new()
+						.name("memory_logger_tcp_client".into())
+						.spawn(move || serve(stream));
+				}
+			}
+		)?;
+
+	Ok(address)
+}
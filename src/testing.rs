@@ -0,0 +1,115 @@
+use std::sync::OnceLock;
+
+use log::Level;
+
+#[cfg(feature = "target")]
+use regex::Regex;
+
+#[cfg(feature = "blocking")]
+use crate::blocking::{CaptureGuard, MemoryLogger};
+#[cfg(all(feature = "asynchronous", not(feature = "blocking")))]
+use crate::asynchronous::{CaptureGuard, MemoryLogger};
+
+use crate::{capture, Config, Expectation, LogEntry};
+
+
+/// The byte budget of the shared test logger.
+const BUDGET: usize = 16 << 20;
+
+
+/// The entries captured by a test wrapped in the [`test`](attr.test.html) attribute.
+///
+/// Tests share a single memory logger, which is installed by the first test to run, and
+/// which captures every level. As the test harness runs each test in its own thread,
+/// entries are isolated by the logging thread: only the entries logged from the test's
+/// thread are captured. Entries logged from threads spawned by the test are not. The
+/// captured entries are removed once the test finishes, even if it panics.
+///
+/// ```
+/// use memory_logger::TestCapture;
+///
+/// #[memory_logger::test]
+/// fn retries(logs: &TestCapture) {
+///     log::warn!("Retrying.");
+///
+///     assert_eq!(logs.entries()[0].message(), "Retrying.");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TestCapture {
+	logger: &'static MemoryLogger,
+	/// Removes the test's entries once it finishes.
+	guard: CaptureGuard<'static>,
+}
+
+
+impl TestCapture {
+	/// The entries logged from the test's thread so far.
+	pub fn entries(&self) -> Vec<LogEntry> {
+		self.guard.entries()
+	}
+
+
+	/// Assert that an entry with the given level, whose message contains the pattern, was
+	/// logged from the test's thread. See the [`assert_logged`](macro.assert_logged.html)
+	/// macro.
+	#[track_caller]
+	pub fn assert_logged(&self, level: Level, pattern: &str) {
+		if let Err(report) = capture::check_logged(&self.entries(), level, pattern) {
+			panic!("{}", report);
+		}
+	}
+
+
+	/// Assert that no errors were logged from the test's thread. See the
+	/// [`assert_no_errors`](macro.assert_no_errors.html) macro.
+	#[track_caller]
+	pub fn assert_no_errors(&self) {
+		if let Err(report) = capture::check_no_errors(&self.entries()) {
+			panic!("{}", report);
+		}
+	}
+
+
+	/// Start expectations on the entries logged from the test's thread so far, checked by
+	/// [`Expectation::verify`](struct.Expectation.html#method.verify).
+	pub fn expect(&self) -> Expectation {
+		Expectation::new(self.entries())
+	}
+
+
+	/// The shared test logger.
+	pub fn logger(&self) -> &'static MemoryLogger {
+		self.logger
+	}
+}
+
+
+/// Run a test with a capture of its entries. This is used by the `test` attribute.
+#[doc(hidden)]
+pub fn run<F, T>(test: F) -> T
+where
+	F: FnOnce(&TestCapture) -> T
+{
+	static LOGGER: OnceLock<&'static MemoryLogger> = OnceLock::new();
+
+	let logger = LOGGER.get_or_init(
+		|| {
+			let config = Config::new(
+				Level::Trace,
+				#[cfg(feature = "target")]
+				Regex::new("").expect("the empty pattern should be valid"),
+			);
+
+			MemoryLogger::setup_with(config.thread(true).max_bytes(BUDGET))
+				.expect("memory_logger::test requires that no other logger is installed")
+		}
+	);
+
+	let capture = TestCapture {
+		logger,
+		guard: logger.capture_thread_guard(),
+	};
+
+	test(&capture)
+}
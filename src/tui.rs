@@ -0,0 +1,261 @@
+//! A ratatui widget rendering entries as a scrollable log console, with level colors and
+//! live filtering, so that terminal applications may embed their own logs.
+//!
+//! The widget is fed a slice of entries every frame, such as the ones read from the logger
+//! through a cursor, and keeps its scroll position and filters in a [`LogViewState`].
+
+use log::Level;
+
+use ratatui::{
+	buffer::Buffer,
+	layout::Rect,
+	style::{Color, Style},
+	text::Line,
+	widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::LogEntry;
+
+
+/// A widget rendering entries, oldest at the top. Entries that don't fit are scrolled
+/// out from the top, unless scrolled back through the state.
+///
+/// ```
+/// # use ratatui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+/// # use regex::Regex;
+/// # fn rows(buffer: &Buffer) -> Vec<String> {
+/// #     buffer.content().chunks(30).map(|row| row.iter().map(|cell| cell.symbol()).collect()).collect()
+/// # }
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::{blocking::MemoryLogger, tui::{LogView, LogViewState}};
+/// let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// let mut cursor = logger.cursor();
+/// let mut entries = Vec::new();
+/// let mut state = LogViewState::new();
+///
+/// log::info!(target: "mycrate", "Connecting.");
+/// log::warn!(target: "mycrate", "Retrying.");
+/// log::info!(target: "mycrate", "Connected.");
+///
+/// // Every frame:
+/// entries.extend(cursor.read());
+///
+/// let area = Rect::new(0, 0, 30, 2);
+/// let mut buffer = Buffer::empty(area);
+/// LogView::new(&entries).render(area, &mut buffer, &mut state);
+///
+/// assert_eq!(rows(&buffer), ["[mycrate] WARN  | Retrying.   ", "[mycrate] INFO  | Connected.  "]);
+///
+/// state.set_filter("Conn");
+/// state.scroll_up(1);
+///
+/// let mut buffer = Buffer::empty(area);
+/// LogView::new(&entries).render(area, &mut buffer, &mut state);
+///
+/// assert_eq!(rows(&buffer), ["[mycrate] INFO  | Connecting. ", "                              "]);
+/// assert!(!state.is_following());
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogView<'a> {
+	entries: &'a [LogEntry],
+	block: Option<Block<'a>>,
+}
+
+
+impl<'a> LogView<'a> {
+	/// Create a widget rendering the given entries, oldest first.
+	pub fn new(entries: &'a [LogEntry]) -> Self {
+		Self {
+			entries,
+			block: None,
+		}
+	}
+
+
+	/// Surround the entries with a block, such as a bordered one with a title.
+	pub fn block(mut self, block: Block<'a>) -> Self {
+		self.block = Some(block);
+		self
+	}
+}
+
+
+impl<'a> StatefulWidget for LogView<'a> {
+	type State = LogViewState;
+
+	fn render(self, area: Rect, buf: &mut Buffer, state: &mut LogViewState) {
+		let area = match self.block {
+			Some(block) => {
+				let inner = block.inner(area);
+				block.render(area, buf);
+				inner
+			}
+
+			None => area,
+		};
+
+		let visible: Vec<&LogEntry> = self.entries
+			.iter()
+			.filter(|entry| state.matches(entry))
+			.collect();
+
+		let bottom = state.bottom(&visible);
+		let height = usize::from(area.height);
+		let top = (bottom + 1).saturating_sub(height);
+
+		let rows = visible
+			.get(top ..= bottom)
+			.unwrap_or_default();
+
+		for (y, entry) in (area.y ..).zip(rows) {
+			let line = Line::styled(entry.as_str(), style(entry.level));
+			buf.set_line(area.x, y, &line, area.width);
+		}
+	}
+}
+
+
+/// The scroll position and filters of a [`LogView`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogViewState {
+	/// The sequence number of the entry at the bottom, or `None` to follow new entries.
+	anchor: Option<u64>,
+	/// Entries to scroll down, or up if negative, on the next render.
+	scroll: isize,
+	filter: String,
+	level: Level,
+}
+
+
+impl LogViewState {
+	/// Create a state following new entries, with no filters.
+	pub fn new() -> Self {
+		Self {
+			anchor: None,
+			scroll: 0,
+			filter: String::new(),
+			level: Level::Trace,
+		}
+	}
+
+
+	/// Scroll up by the given number of entries, which stops following new entries.
+	pub fn scroll_up(&mut self, entries: usize) {
+		self.scroll = self.scroll.saturating_sub_unsigned(entries);
+	}
+
+
+	/// Scroll down by the given number of entries. Scrolling to the bottom follows new
+	/// entries again.
+	pub fn scroll_down(&mut self, entries: usize) {
+		self.scroll = self.scroll.saturating_add_unsigned(entries);
+	}
+
+
+	/// Scroll to the bottom, following new entries.
+	pub fn follow(&mut self) {
+		self.anchor = None;
+		self.scroll = 0;
+	}
+
+
+	/// Whether new entries are followed.
+	pub fn is_following(&self) -> bool {
+		self.anchor.is_none() && self.scroll >= 0
+	}
+
+
+	/// Only show entries whose line contains the given text. An empty filter shows all
+	/// entries.
+	pub fn set_filter(&mut self, filter: &str) {
+		filter.clone_into(&mut self.filter);
+	}
+
+
+	/// The text filter.
+	pub fn filter(&self) -> &str {
+		&self.filter
+	}
+
+
+	/// Append a character to the text filter, such as when the user types it.
+	pub fn push_filter(&mut self, c: char) {
+		self.filter.push(c);
+	}
+
+
+	/// Remove the last character of the text filter, such as on backspace.
+	pub fn pop_filter(&mut self) {
+		self.filter.pop();
+	}
+
+
+	/// Only show entries at or above the given level. Defaults to trace, showing all
+	/// entries.
+	pub fn set_level(&mut self, level: Level) {
+		self.level = level;
+	}
+
+
+	/// The level filter.
+	pub fn level(&self) -> Level {
+		self.level
+	}
+
+
+	fn matches(&self, entry: &LogEntry) -> bool {
+		entry.level <= self.level && entry.line.contains(self.filter.as_str())
+	}
+
+
+	/// Apply the pending scroll, returning the index of the bottom entry.
+	fn bottom(&mut self, visible: &[&LogEntry]) -> usize {
+		let last = visible.len().saturating_sub(1);
+
+		let anchored = match self.anchor {
+			Some(anchor) => visible
+				.partition_point(|entry| entry.sequence <= anchor)
+				.saturating_sub(1),
+
+			None => last,
+		};
+
+		let bottom = anchored
+			.saturating_add_signed(self.scroll)
+			.min(last);
+
+		self.scroll = 0;
+		self.anchor = if bottom == last {
+			None
+		} else {
+			visible.get(bottom).map(|entry| entry.sequence)
+		};
+
+		bottom
+	}
+}
+
+
+impl Default for LogViewState {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+
+/// The style of an entry's line.
+fn style(level: Level) -> Style {
+	let color = match level {
+		Level::Error => Color::Red,
+		Level::Warn => Color::Yellow,
+		Level::Info => Color::Reset,
+		Level::Debug => Color::Blue,
+		Level::Trace => Color::DarkGray,
+	};
+
+	Style::default().fg(color)
+}
@@ -0,0 +1,18 @@
+use tokio::sync::watch;
+
+
+/// Announce that the entry with the given sequence number has been logged. The count
+/// never decreases, even if concurrent entries are announced out of order.
+pub(crate) fn announce(updates: &watch::Sender<u64>, sequence: u64) {
+	updates.send_if_modified(
+		|count| {
+			let modified = sequence >= *count;
+
+			if modified {
+				*count = sequence + 1;
+			}
+
+			modified
+		}
+	);
+}
@@ -0,0 +1,55 @@
+use crate::LogEntry;
+
+
+/// A copy of the buffered entries, for user interfaces that redraw every frame, such as
+/// egui or iced ones.
+///
+/// Entries are identified by their sequence numbers, which are stable across reads,
+/// clears and evictions, and are therefore suitable as widget ids. The view's version
+/// tells which state of the buffer it reflects, so that interfaces may only take a new
+/// view when the logger's version has changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct View {
+	entries: Vec<LogEntry>,
+	version: u64,
+}
+
+
+impl View {
+	#[cfg_attr(not(any(feature = "blocking", feature = "asynchronous")), allow(dead_code))]
+	pub(crate) fn new(entries: Vec<LogEntry>, version: u64) -> Self {
+		Self { entries, version }
+	}
+
+
+	/// The version of the buffer this view reflects.
+	pub fn version(&self) -> u64 {
+		self.version
+	}
+
+
+	/// The entries, oldest first.
+	pub fn entries(&self) -> &[LogEntry] {
+		&self.entries
+	}
+
+
+	/// The entry with the given sequence number, if in the view.
+	pub fn get(&self, sequence: u64) -> Option<&LogEntry> {
+		self.entries
+			.iter()
+			.find(|entry| entry.sequence == sequence)
+	}
+
+
+	/// The number of entries.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+
+	/// Whether there are no entries.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+}
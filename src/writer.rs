@@ -0,0 +1,156 @@
+use std::{
+	fmt,
+	io::{self, Write},
+};
+
+use log::Level;
+
+
+/// A logger the lines written to adapters are pushed to.
+pub(crate) trait Push {
+	fn push_line(&self, level: Level, target: &str, line: &str);
+}
+
+
+/// An `io::Write` adapter recording each written line as an entry, with a given level and
+/// target, such as to capture the output of a child process. Created through the
+/// flavors' `as_writer`. Entries are pushed regardless of the level and target filters.
+///
+/// Lines are split on newlines, dropping trailing carriage returns, and invalid UTF-8 is
+/// replaced. A trailing partial line is recorded once the writer is dropped.
+///
+/// `fmt::Write` is implemented as well, so that reports built with `write!` are streamed
+/// straight into the buffer:
+///
+/// ```
+/// # use regex::Regex;
+/// use std::fmt::Write;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::blocking::MemoryLogger;
+/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// {
+///     let mut report = logger.as_writer(log::Level::Warn, "report");
+///     writeln!(report, "slow requests: {}", 2)?;
+///     write!(report, "worst: {}ms", 1200)?;
+/// }
+///
+/// assert_eq!(
+///     &*logger.read(),
+///     "[report] WARN  | slow requests: 2\n[report] WARN  | worst: 1200ms\n"
+/// );
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```
+/// # use regex::Regex;
+/// use std::io;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "blocking")] {
+/// # use memory_logger::blocking::MemoryLogger;
+/// # let logger = MemoryLogger::setup(log::Level::Info, Regex::new("^mycrate")?)?;
+/// let mut stdout: &[u8] = b"Compiling.\r\nFinished.\nexit";
+///
+/// io::copy(&mut stdout, &mut logger.as_writer(log::Level::Info, "child"))?;
+///
+/// assert_eq!(
+///     &*logger.read(),
+///     "[child] INFO  | Compiling.\n[child] INFO  | Finished.\n[child] INFO  | exit\n"
+/// );
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LogWriter<'a> {
+	logger: &'a (dyn Push + Sync),
+	level: Level,
+	target: String,
+	/// The bytes of the current line.
+	pending: Vec<u8>,
+}
+
+
+impl<'a> LogWriter<'a> {
+	pub(crate) fn new(logger: &'a (dyn Push + Sync), level: Level, target: &str) -> Self {
+		Self {
+			logger,
+			level,
+			target: target.into(),
+			pending: Vec::new(),
+		}
+	}
+
+
+	/// Record a line, without its newline.
+	fn record(&self, line: &[u8]) {
+		let line = line
+			.strip_suffix(b"\r")
+			.unwrap_or(line);
+
+		self.logger.push_line(
+			self.level,
+			&self.target,
+			&String::from_utf8_lossy(line)
+		);
+	}
+}
+
+
+impl<'a> Write for LogWriter<'a> {
+	fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+		let mut rest = bytes;
+
+		while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+			if self.pending.is_empty() {
+				self.record(&rest[.. end]);
+			} else {
+				self.pending.extend_from_slice(&rest[.. end]);
+				self.record(&self.pending);
+				self.pending.clear();
+			}
+
+			rest = &rest[end + 1 ..];
+		}
+
+		self.pending.extend_from_slice(rest);
+
+		Ok(bytes.len())
+	}
+
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+
+impl<'a> fmt::Write for LogWriter<'a> {
+	fn write_str(&mut self, string: &str) -> fmt::Result {
+		self
+			.write_all(string.as_bytes())
+			.map_err(|_| fmt::Error)
+	}
+}
+
+
+impl<'a> Drop for LogWriter<'a> {
+	fn drop(&mut self) {
+		if !self.pending.is_empty() {
+			self.record(&self.pending);
+		}
+	}
+}
+
+
+impl<'a> fmt::Debug for LogWriter<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("LogWriter")
+			.field("level", &self.level)
+			.field("target", &self.target)
+			.finish()
+	}
+}
@@ -0,0 +1,158 @@
+//! WebSocket live tail, pushing entries to any number of viewers as they are logged.
+//!
+//! Viewers connect to any path, and choose the format through the query string: entries
+//! are sent as text messages with the rendered line by default, or with the entry as JSON
+//! with `?format=json`, as described in the [`http`](../http/index.html) module.
+//!
+//! Each viewer is served by its own thread, with a bounded backlog: if a viewer falls
+//! behind by more than the backlog capacity, that viewer only is disconnected, with a close
+//! frame of code 1013 (try again later) once its pending entries have been sent. Viewers
+//! that stop reading are disconnected once a write times out.
+
+use std::{
+	io,
+	net::{TcpListener, TcpStream},
+	thread,
+	time::Duration,
+};
+
+use flume::Receiver;
+
+use tungstenite::{
+	handshake::server::{ErrorResponse, Request, Response},
+	protocol::{frame::coding::CloseCode, CloseFrame},
+	Message,
+};
+
+use crate::{json, LogEntry};
+
+
+/// How long writing to a viewer may block before it is disconnected.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// The live tail configuration. See
+/// [`MemoryLogger::serve_ws`](../asynchronous/struct.MemoryLogger.html#method.serve_ws).
+#[derive(Debug, Clone)]
+pub struct Tail {
+	capacity: usize,
+}
+
+
+impl Tail {
+	/// Create a live tail with the default backlog capacity of 1024 entries per viewer.
+	pub fn new() -> Self {
+		Self {
+			capacity: 1024,
+		}
+	}
+
+
+	/// Set how many entries may be pending for a viewer before it is disconnected. The
+	/// capacity is at least one.
+	pub fn capacity(mut self, capacity: usize) -> Self {
+		self.capacity = capacity;
+		self
+	}
+
+
+	/// Spawn the thread accepting viewers, each fed by a new subscription.
+	pub(crate) fn spawn<F>(self, listener: TcpListener, subscribe: F) -> io::Result<()>
+	where
+		F: Fn(usize) -> Receiver<LogEntry> + Clone + Send + 'static
+	{
+		thread::Builder::new()
+			.name("memory_logger_ws".into())
+			.spawn(
+				move || {
+					for stream in listener.incoming().flatten() {
+						if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+							continue;
+						}
+
+						let subscribe = subscribe.clone();
+						let capacity = self.capacity;
+
+						let _ = thread::Builder::new()
+							.name("memory_logger_ws_viewer".into())
+							.spawn(move || serve(stream, capacity, subscribe));
+					}
+				}
+			)?;
+
+		Ok(())
+	}
+}
+
+
+impl Default for Tail {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+
+/// Serve a viewer until it disconnects.
+// The handshake callback's signature is dictated by tungstenite.
+#[allow(clippy::result_large_err)]
+fn serve<F>(stream: TcpStream, capacity: usize, subscribe: F)
+where
+	F: FnOnce(usize) -> Receiver<LogEntry>
+{
+	// Subscribe before answering the handshake, so that viewers get every entry logged
+	// once connected. Dropping the receiver unsubscribes once the viewer is gone.
+	let rx = subscribe(capacity);
+
+	let mut json = false;
+
+	let handshake = tungstenite::accept_hdr(
+		stream,
+		|request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+			json = request
+				.uri()
+				.query()
+				.is_some_and(|query| query.split('&').any(|pair| pair == "format=json"));
+
+			Ok(response)
+		}
+	);
+
+	let mut socket = match handshake {
+		Ok(socket) => socket,
+		Err(_) => return,
+	};
+
+	// The subscription is only disconnected once the viewer has fallen behind.
+	while let Ok(entry) = rx.recv() {
+		let message = if json {
+			let mut text = String::new();
+
+			json::write_entry(&mut text, &entry)
+				.expect("std::fmt::Write should never fail for String");
+
+			text
+		} else {
+			entry.line
+		};
+
+		// Only flush once the pending entries have been written.
+		let sent = if rx.is_empty() {
+			socket.send(Message::text(message))
+		} else {
+			socket.write(Message::text(message))
+		};
+
+		if sent.is_err() {
+			return;
+		}
+	}
+
+	let frame = CloseFrame {
+		code: CloseCode::Again,
+		reason: "viewer fell behind".into(),
+	};
+
+	if socket.close(Some(frame)).is_ok() {
+		let _ = socket.flush();
+	}
+}